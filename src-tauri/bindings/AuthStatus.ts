@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AuthStatus = { authorized: boolean, 
+/**
+ * 保存済みの認証情報があるがパスフレーズ未入力
+ */
+locked: boolean, user_name: string | null, user_account: string | null, expires_at: string | null, last_refreshed_at: string | null, last_error: string | null, };
@@ -1,3 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type AuthorInfo = { author_id: number, author_name: string, author_account: string, count: number | null, };
+export type AuthorInfo = { author_id: number, author_name: string, author_account: string, count: number | null, 
+/**
+ * 以前の名前（カンマ区切り）
+ */
+former_names?: string, };
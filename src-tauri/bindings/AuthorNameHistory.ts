@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 作者名の変更履歴
+ */
+export type AuthorNameHistory = { author_name: string, author_account: string, first_seen: string, last_seen: string, };
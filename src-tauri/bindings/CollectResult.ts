@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CollectSummary } from "./CollectSummary";
+import type { MoveFailure } from "./MoveFailure";
+
+/**
+ * 振り分けの結果
+ */
+export type CollectResult = { summary: Array<CollectSummary>, failed_moves: Array<MoveFailure>, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DuplicateFile } from "./DuplicateFile";
+
+/**
+ * 内容が同じファイルのまとまり
+ */
+export type DuplicateGroup = { hash: string, file_size: number, files: Array<DuplicateFile>, };
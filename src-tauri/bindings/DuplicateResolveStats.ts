@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type DuplicateResolveStats = { removed: number, 
+/**
+ * 内容が変わっていた、またはゴミ箱へ移せなかったため残したファイル
+ */
+skipped_files: Array<string>, };
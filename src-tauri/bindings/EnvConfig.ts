@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type EnvConfig = { REFRESH_TOKEN: string, INTERVAL_MILL_SEC: string, DB_NAME: string, };
+export type EnvConfig = { INTERVAL_MILL_SEC: string, DB_NAME: string, FETCH_CONCURRENCY: string, API_BASE_URL: string, AUTH_TOKEN_URL: string, PROXY_URL: string, };
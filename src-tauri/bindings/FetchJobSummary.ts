@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type FetchJobSummary = { job_id: number, total: number, pending: number, done: number, failed: number, skipped: number, created_at: string, };
@@ -1,4 +1,8 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { FolderCount } from "./FolderCount";
 
-export type FileCounts = { folders: Array<FolderCount>, total: number, process_time: string, };
+export type FileCounts = { folders: Array<FolderCount>, total: number, process_time: string, 
+/**
+ * 検査で問題が見つかったファイル数（壊れたものは取り込まない）
+ */
+unhealthy: number, };
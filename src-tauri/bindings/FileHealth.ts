@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FileHealthStatus } from "./FileHealthStatus";
+
+/**
+ * ファイルの検査結果
+ */
+export type FileHealth = { save_dir: string, file_name: string, status: FileHealthStatus, 
+/**
+ * 中身から判定した形式の拡張子
+ */
+detected_format: string | null, width: number | null, height: number | null, message: string | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * ファイル名パターンの試し解析結果
+ */
+export type FileNamePreview = { file_name: string, illust_id: number | null, suffix: number | null, extension: string | null, extras: { [key in string]?: string }, 
+/**
+ * 一致するパターンが無いときの理由
+ */
+error: string | null, };
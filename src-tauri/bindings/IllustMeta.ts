@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type IllustMeta = { illust_id: number, title: string, caption: string, page_count: number, width: number, height: number, total_bookmarks: number, total_view: number, x_restrict: number, illust_ai_type: number, tools: Array<string>, series_id: number | null, series_title: string | null, fetched_at: string, };
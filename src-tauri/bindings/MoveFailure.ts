@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 移動できなかったファイルとその理由
+ */
+export type MoveFailure = { file_name: string, src_dir: string, dest_dir: string, reason: string, };
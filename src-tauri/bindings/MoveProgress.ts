@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * ファイル移動の進捗
+ */
+export type MoveProgress = { current: number, total: number, file_name: string, };
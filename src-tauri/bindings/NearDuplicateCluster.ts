@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NearDuplicateFile } from "./NearDuplicateFile";
+
+/**
+ * 見た目が近い画像のまとまり（先頭が解像度・サイズの最も大きいもの）
+ */
+export type NearDuplicateCluster = { files: Array<NearDuplicateFile>, };
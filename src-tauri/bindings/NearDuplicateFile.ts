@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type NearDuplicateFile = { illust_id: number, suffix: number, save_dir: string, file_name: string, width: number, height: number, file_size: number, 
+/**
+ * 先頭のファイルとのハミング距離
+ */
+distance: number, };
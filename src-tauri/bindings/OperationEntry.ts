@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OperationKind } from "./OperationKind";
+
+/**
+ * 操作履歴の1件
+ */
+export type OperationEntry = { id: number, kind: OperationKind, file_count: number, 
+/**
+ * 移動先のフォルダ、付けたキャラクター名など
+ */
+detail: string | null, created_at: string, 
+/**
+ * 取り消し済み（やり直しの対象）
+ */
+undone: boolean, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type OperationKind = "move_files" | "label_character" | "edit_tags" | "overwrite_tags" | "delete_files";
@@ -1,3 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type ProcessStats = { total_ids: number, successed_ids: number, failed_ids: number, duplicated_files: number, process_time: string, failed_file_paths: Array<string>, };
+export type ProcessStats = { total_ids: number, successed_ids: number, failed_ids: number, duplicated_files: number, remaining_ids: number, cancelled: boolean, process_time: string, failed_file_paths: Array<string>, 
+/**
+ * 同じ作品・ページでも内容が違うため削除しなかったファイル
+ */
+kept_files: Array<string>, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type QuarantineStats = { moved: number, 
+/**
+ * 退避フォルダへ移せず、登録も残したファイル
+ */
+failed_files: Array<string>, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RefreshChangeKind } from "./RefreshChangeKind";
+
+/**
+ * 再取得で検知した変更
+ */
+export type RefreshChange = { illust_id: number, kind: RefreshChangeKind, old_value: string | null, new_value: string | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * 再取得の実行履歴
+ */
+export type RefreshRun = { run_id: number, stale_days: number, target_count: number, success_count: number, fail_count: number, change_count: number, cancelled: boolean, started_at: string, finished_at: string | null, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * ライブラリの移動結果（dry_run なら書き換える予定の件数）
+ */
+export type RelocateStats = { files: number, collect_dirs: number, root_updated: boolean, 
+/**
+ * 書き換え後の場所に見つからないファイル数
+ */
+missing_files: number, 
+/**
+ * 相対パスで保存し直すファイル数
+ */
+relative_files: number, 
+/**
+ * 書き換え後と同じ場所の登録が既にあり、書き換えなかった行数
+ */
+conflicts: number, };
@@ -1,3 +1,15 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type SearchResult = { illust_id: number, file_name: string, thumbnail_url: string, author_name: string, character: string | null, save_dir: string, tags: string | null, };
+export type SearchResult = { illust_id: number, file_name: string, 
+/**
+ * save_dir と file_name から組み立てた画像のパス
+ */
+thumbnail_url: string, author_name: string, character: string | null, save_dir: string, tags: string | null, 
+/**
+ * tags と同じ並びで、翻訳があれば翻訳名、無ければ原文
+ */
+translated_tags: string | null, 
+/**
+ * 画像検索のときだけ、検索画像とのハミング距離
+ */
+distance: number | null, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FileSummary } from "./FileSummary";
+
+/**
+ * DB 同期の結果
+ */
+export type SyncResult = { 
+/**
+ * 登録済みだが見つからないファイル
+ */
+missing_files: Array<FileSummary>, 
+/**
+ * 同じ作品・ページでも内容が違うため削除しなかったファイル
+ */
+kept_files: Array<string>, };
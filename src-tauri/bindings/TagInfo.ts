@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type TagInfo = { tag: string, count: number, };
+export type TagInfo = { tag: string, count: number, translated_name?: string, };
@@ -1,3 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FetchRunState } from "./FetchRunState";
 
-export type TagProgress = { success: number, fail: number, current: number, total: number, elapsed_time: string, remaining_time: string, };
+export type TagProgress = { success: number, fail: number, current: number, total: number, elapsed_time: string, remaining_time: string, current_delay_ms: number, state: FetchRunState, };
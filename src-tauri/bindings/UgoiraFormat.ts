@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * うごイラの変換形式（UGOIRA_INFO.format）
+ */
+export type UgoiraFormat = "webp" | "gif" | "apng";
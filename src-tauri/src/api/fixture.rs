@@ -0,0 +1,107 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::api::pixiv::{not_found_response, IllustSource, PixivClientProvider};
+use crate::models::settings::AuthStatus;
+use crate::util::ResultWithLocationExt;
+
+/// ディレクトリ内の固定JSONを返す作品詳細ソース
+///
+/// - `{illust_id}.json` があればステータス 200 で返す
+/// - `{illust_id}.{status}.json` があればそのステータスで返す
+/// - どちらも無ければ 404 を返す
+/// - うごイラのフレーム情報は `{illust_id}.ugoira.json` から返す
+pub struct FixtureIllustSource {
+    dir: PathBuf,
+}
+
+impl FixtureIllustSource {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn find_fixture(&self, illust_id: u32) -> Result<Option<(u16, PathBuf)>> {
+        let ok_path = self.dir.join(format!("{}.json", illust_id));
+        if ok_path.is_file() {
+            return Ok(Some((200, ok_path)));
+        }
+
+        let prefix = format!("{}.", illust_id);
+        for entry in fs::read_dir(&self.dir).with_location()? {
+            let path = entry.with_location()?.path();
+            let status = path
+                .file_name()
+                .and_then(|f| f.to_str())
+                .and_then(|f| f.strip_prefix(&prefix))
+                .and_then(|f| f.strip_suffix(".json"))
+                .and_then(|s| s.parse::<u16>().ok());
+            if let Some(status) = status {
+                return Ok(Some((status, path)));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl IllustSource for FixtureIllustSource {
+    async fn illust_detail(&self, illust_id: u32) -> Result<(u16, String)> {
+        match self.find_fixture(illust_id)? {
+            Some((status, path)) => Ok((status, fs::read_to_string(path).with_location()?)),
+            None => Ok(not_found_response()),
+        }
+    }
+
+    async fn ugoira_metadata(&self, illust_id: u32) -> Result<(u16, String)> {
+        let path = self.dir.join(format!("{}.ugoira.json", illust_id));
+        if !path.is_file() {
+            return Ok(not_found_response());
+        }
+        Ok((200, fs::read_to_string(path).with_location()?))
+    }
+}
+
+/// 固定JSONのソースを返すプロバイダ
+pub struct FixturePixivClientProvider {
+    source: Arc<FixtureIllustSource>,
+}
+
+impl FixturePixivClientProvider {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            source: Arc::new(FixtureIllustSource::new(dir)),
+        }
+    }
+}
+
+#[async_trait]
+impl PixivClientProvider for FixturePixivClientProvider {
+    async fn refresh_client(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_client(&self) -> Result<Arc<dyn IllustSource>> {
+        Ok(self.source.clone())
+    }
+
+    async fn login(&self, _refresh_token: String, _passphrase: String) -> Result<AuthStatus> {
+        Ok(self.auth_status())
+    }
+
+    async fn unlock(&self, _passphrase: String) -> Result<AuthStatus> {
+        Ok(self.auth_status())
+    }
+
+    fn auth_status(&self) -> AuthStatus {
+        AuthStatus {
+            authorized: true,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use sqlx::{ConnectOptions, Row};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::api::pixiv::{not_found_response, IllustSource};
+use crate::models::fetch::FileDetail;
+use crate::service::library::LibraryPaths;
+use crate::util::{log_error, ResultWithLocationExt};
+
+/// 他のダウンローダーが保存したメタデータを作品詳細APIの形に組み直して返すソース
+///
+/// 通信は行わず、読み込んでいない作品は 404 を返す
+#[derive(Default)]
+pub struct LocalMetadataSource {
+    bodies: HashMap<u32, String>,
+    /// うごイラのフレーム情報
+    ugoira: HashMap<u32, String>,
+}
+
+/// 組み直しに使う作品情報
+struct LocalWork {
+    id: u32,
+    title: String,
+    illust_type: String,
+    caption: String,
+    create_date: String,
+    user_id: u64,
+    user_name: String,
+    user_account: String,
+    tags: Vec<(String, Option<String>)>,
+    tools: Vec<String>,
+    page_count: u64,
+    width: u64,
+    height: u64,
+    total_view: u64,
+    total_bookmarks: u64,
+    x_restrict: u64,
+    illust_ai_type: u64,
+    series: Value,
+    ugoira_frames: Option<Value>,
+}
+
+impl LocalWork {
+    /// 作品詳細APIのレスポンスと同じ形にする（ローカルに無い項目は既定値）
+    fn to_body(&self) -> String {
+        json!({
+            "illust": {
+                "id": self.id,
+                "title": self.title,
+                "type": self.illust_type,
+                "image_urls": { "square_medium": "", "medium": "", "large": "" },
+                "caption": self.caption,
+                "restrict": 0,
+                "user": {
+                    "id": self.user_id,
+                    "name": self.user_name,
+                    "account": self.user_account,
+                    "profile_image_urls": { "medium": "" },
+                    "is_followed": false
+                },
+                "tags": self.tags.iter().map(|(name, translated_name)| json!({
+                    "name": name,
+                    "translated_name": translated_name
+                })).collect::<Vec<_>>(),
+                "tools": self.tools,
+                "create_date": self.create_date,
+                "page_count": self.page_count,
+                "width": self.width,
+                "height": self.height,
+                "sanity_level": 0,
+                "x_restrict": self.x_restrict,
+                "series": self.series,
+                "meta_single_page": {},
+                "meta_pages": [],
+                "total_view": self.total_view,
+                "total_bookmarks": self.total_bookmarks,
+                "is_bookmarked": false,
+                "visible": true,
+                "is_muted": false,
+                "total_comments": 0,
+                "illust_ai_type": self.illust_ai_type,
+                "illust_book_style": 0,
+                "restriction_attributes": []
+            }
+        })
+        .to_string()
+    }
+}
+
+impl LocalMetadataSource {
+    pub fn contains(&self, illust_id: u32) -> bool {
+        self.bodies.contains_key(&illust_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+
+    fn insert(&mut self, work: LocalWork) {
+        if let Some(frames) = &work.ugoira_frames {
+            let body = json!({ "ugoira_metadata": { "frames": frames } }).to_string();
+            self.ugoira.insert(work.id, body);
+        }
+        self.bodies.insert(work.id, work.to_body());
+    }
+
+    /// gallery-dl の `--write-metadata` で保存されたJSONを読み込む
+    ///
+    /// `{ファイル名}.json` と拡張子を除いた `{名前}.json` のどちらにも対応する
+    pub fn load_gallery_dl_sidecars(
+        &mut self,
+        details: &[FileDetail],
+        library: &LibraryPaths,
+    ) -> usize {
+        let mut loaded = 0;
+        for detail in details {
+            if self.contains(detail.illust_id) {
+                continue;
+            }
+
+            let dir = library.resolve_dir(&detail.save_dir);
+            let stem = Path::new(&detail.file_name)
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let candidates = [
+                dir.join(format!("{}.json", detail.file_name)),
+                dir.join(format!("{}.json", stem)),
+            ];
+            for path in candidates {
+                let Ok(text) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                let work = serde_json::from_str::<Value>(&text)
+                    .with_location()
+                    .and_then(|value| gallery_dl_work(&value, detail.illust_id));
+                match work {
+                    Ok(work) => {
+                        self.insert(work);
+                        loaded += 1;
+                        break;
+                    }
+                    Err(e) => {
+                        log_error(format!("サイドカーを読み込めません: {:?} | {}", path, e));
+                    }
+                }
+            }
+        }
+        loaded
+    }
+
+    /// PixivUtil2 の db.sqlite から読み込む（サイドカーで読み込んだ作品は上書きしない）
+    ///
+    /// 投稿日時やサイズは保存されていないため、ダウンロード日時とファイル数で代用する
+    pub async fn load_pixivutil_db<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        details: &[FileDetail],
+    ) -> Result<usize> {
+        let mut page_counts: HashMap<u32, u64> = HashMap::new();
+        let mut ugoira_ids = HashSet::new();
+        for detail in details {
+            let count = page_counts.entry(detail.illust_id).or_default();
+            *count = (*count).max(detail.suffix as u64 + 1);
+            if detail.extension == "zip" {
+                ugoira_ids.insert(detail.illust_id);
+            }
+        }
+
+        let mut conn = SqliteConnectOptions::new()
+            .filename(path.as_ref())
+            .read_only(true)
+            .connect()
+            .await
+            .with_location()?;
+
+        // バージョンによって列が無いことがあるため SELECT * から拾う
+        let mut members: HashMap<i64, (String, String)> = HashMap::new();
+        for row in sqlx::query("SELECT * FROM pixiv_master_member")
+            .fetch_all(&mut conn)
+            .await
+            .with_location()?
+        {
+            let member_id: i64 = row.try_get("member_id").with_location()?;
+            let name: String = row.try_get("name").unwrap_or_default();
+            let account: Option<String> = row.try_get("member_token").ok().flatten();
+            members.insert(member_id, (name, account.unwrap_or_default()));
+        }
+
+        let translations = load_pixivutil_translations(&mut conn).await;
+        let tags = load_pixivutil_tags(&mut conn).await;
+
+        let mut loaded = 0;
+        for row in sqlx::query("SELECT * FROM pixiv_master_image")
+            .fetch_all(&mut conn)
+            .await
+            .with_location()?
+        {
+            let image_id: i64 = row.try_get("image_id").with_location()?;
+            let Ok(illust_id) = u32::try_from(image_id) else {
+                continue;
+            };
+            let Some(page_count) = page_counts.get(&illust_id) else {
+                continue;
+            };
+            if self.contains(illust_id) {
+                continue;
+            }
+
+            let member_id: i64 = row.try_get("member_id").with_location()?;
+            let Some((user_name, user_account)) = members.get(&member_id) else {
+                log_error(format!(
+                    "作者が見つかりません: {} (member_id: {})",
+                    illust_id, member_id
+                ));
+                continue;
+            };
+
+            // フレーム情報は zip に同梱の animation.json から読む
+            let illust_type = if ugoira_ids.contains(&illust_id) {
+                "ugoira"
+            } else {
+                "illust"
+            };
+
+            self.insert(LocalWork {
+                id: illust_id,
+                title: row.try_get("title").unwrap_or_default(),
+                illust_type: illust_type.to_string(),
+                caption: row
+                    .try_get::<Option<String>, _>("caption")
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default(),
+                create_date: row
+                    .try_get::<Option<String>, _>("created_date")
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default(),
+                user_id: member_id as u64,
+                user_name: user_name.clone(),
+                user_account: user_account.clone(),
+                tags: tags
+                    .get(&image_id)
+                    .into_iter()
+                    .flatten()
+                    .map(|tag| (tag.clone(), translations.get(tag).cloned()))
+                    .collect(),
+                tools: Vec::new(),
+                page_count: *page_count,
+                width: 0,
+                height: 0,
+                total_view: 0,
+                total_bookmarks: 0,
+                x_restrict: 0,
+                illust_ai_type: 0,
+                series: Value::Null,
+                ugoira_frames: None,
+            });
+            loaded += 1;
+        }
+
+        Ok(loaded)
+    }
+}
+
+/// 作品ごとのタグ（古いDBにはテーブルが無いので空扱い）
+async fn load_pixivutil_tags(conn: &mut SqliteConnection) -> HashMap<i64, Vec<String>> {
+    let rows: Vec<(i64, String)> =
+        match sqlx::query_as("SELECT image_id, tag_id FROM pixiv_image_to_tag ORDER BY rowid")
+            .fetch_all(&mut *conn)
+            .await
+        {
+            Ok(rows) => rows,
+            Err(e) => {
+                log_error(format!("PixivUtil2 のタグを読み込めません: {}", e));
+                Vec::new()
+            }
+        };
+
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    for (image_id, tag) in rows {
+        tags.entry(image_id).or_default().push(tag);
+    }
+    tags
+}
+
+/// タグの英訳（古いDBにはテーブルが無いので空扱い）
+async fn load_pixivutil_translations(conn: &mut SqliteConnection) -> HashMap<String, String> {
+    match sqlx::query_as(
+        "SELECT tag_id, translation FROM pixiv_tag_translation WHERE translation_type = 'en'",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    {
+        Ok(rows) => rows.into_iter().collect(),
+        Err(e) => {
+            log_error(format!("PixivUtil2 のタグ翻訳を読み込めません: {}", e));
+            HashMap::new()
+        }
+    }
+}
+
+/// gallery-dl のメタデータ（API の作品情報にファイル単位の項目を足したもの）を読む
+fn gallery_dl_work(value: &Value, illust_id: u32) -> Result<LocalWork> {
+    let text = |v: &Value| v.as_str().unwrap_or_default().to_string();
+    let number = |key: &str| value[key].as_u64().unwrap_or_default();
+
+    if value["id"].as_u64() != Some(illust_id as u64) {
+        bail!("作品IDが一致しません: {}", value["id"]);
+    }
+    let user = &value["user"];
+    let Some(user_id) = user["id"].as_u64() else {
+        bail!("作者情報がありません");
+    };
+
+    // 既定では名前だけの配列、raw 指定時は API と同じオブジェクト
+    let tags = value["tags"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|tag| match tag {
+            Value::String(name) => Some((name.clone(), None)),
+            Value::Object(_) => tag["name"].as_str().map(|name| {
+                (
+                    name.to_string(),
+                    tag["translated_name"].as_str().map(str::to_string),
+                )
+            }),
+            _ => None,
+        })
+        .collect();
+
+    let create_date = value["create_date"]
+        .as_str()
+        .or_else(|| value["date"].as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    Ok(LocalWork {
+        id: illust_id,
+        title: text(&value["title"]),
+        illust_type: value["type"]
+            .as_str()
+            .unwrap_or(if value["frames"].is_array() {
+                "ugoira"
+            } else {
+                "illust"
+            })
+            .to_string(),
+        caption: text(&value["caption"]),
+        create_date,
+        user_id,
+        user_name: text(&user["name"]),
+        user_account: text(&user["account"]),
+        tags,
+        tools: value["tools"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|t| t.as_str().map(str::to_string))
+            .collect(),
+        page_count: number("page_count").max(1),
+        width: number("width"),
+        height: number("height"),
+        total_view: number("total_view"),
+        total_bookmarks: number("total_bookmarks"),
+        x_restrict: number("x_restrict"),
+        illust_ai_type: number("illust_ai_type"),
+        series: value["series"].clone(),
+        // うごイラを保存した場合のみフレーム情報が付く
+        ugoira_frames: value["frames"].is_array().then(|| value["frames"].clone()),
+    })
+}
+
+#[async_trait]
+impl IllustSource for LocalMetadataSource {
+    async fn illust_detail(&self, illust_id: u32) -> Result<(u16, String)> {
+        match self.bodies.get(&illust_id) {
+            Some(body) => Ok((200, body.clone())),
+            None => Ok(not_found_response()),
+        }
+    }
+
+    async fn ugoira_metadata(&self, illust_id: u32) -> Result<(u16, String)> {
+        match self.ugoira.get(&illust_id) {
+            Some(body) => Ok((200, body.clone())),
+            None => Ok(not_found_response()),
+        }
+    }
+}
@@ -1 +1,5 @@
+pub mod fixture;
+pub mod local_metadata;
 pub mod pixiv;
+pub mod rate_limiter;
+pub mod token_store;
@@ -1,24 +1,367 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::{Client, Proxy};
+use serde_json::Value;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use tauri::async_runtime;
 
-use pixieve_rs::pixiv::client::PixivClient;
+use crate::api::token_store::{self, AuthUser, StoredAuth, LEGACY_TOKEN_KEY};
+use crate::constants::{
+    API_BASE_URL, AUTH_TOKEN_URL, CLIENT_ID, CLIENT_SECRET, TOKEN_REFRESH_MARGIN_SEC, USER_AGENT,
+};
+use crate::errors::fetch::is_auth_error;
+use crate::models::settings::AuthStatus;
+use crate::service::common::format_unix_timestamp;
+use crate::util::ResultWithLocationExt;
 
 #[async_trait]
 pub trait PixivClientProvider: Send + Sync {
     async fn refresh_client(&self) -> Result<()>;
-    async fn get_client(&self) -> Result<PixivClient>;
+    async fn get_client(&self) -> Result<Arc<dyn IllustSource>>;
+    /// 新しいリフレッシュトークンで認証し、暗号化して保存する
+    async fn login(&self, refresh_token: String, passphrase: String) -> Result<AuthStatus>;
+    /// 保存済みの認証情報を復号して認証する
+    async fn unlock(&self, passphrase: String) -> Result<AuthStatus>;
+    fn auth_status(&self) -> AuthStatus;
+}
+
+/// 作品詳細の取得元
+#[async_trait]
+pub trait IllustSource: Send + Sync {
+    /// 作品詳細を取得し、ステータスと本文をそのまま返す
+    async fn illust_detail(&self, illust_id: u32) -> Result<(u16, String)>;
+
+    /// うごイラのフレーム情報を取得する（持たないソースは 404）
+    async fn ugoira_metadata(&self, _illust_id: u32) -> Result<(u16, String)> {
+        Ok(not_found_response())
+    }
+}
+
+/// API の 404 と同じ形の応答
+pub fn not_found_response() -> (u16, String) {
+    (
+        404,
+        r#"{"error":{"user_message":"","message":"Not Found","reason":"","user_message_details":{}}}"#
+            .to_string(),
+    )
+}
+
+/// 接続先の設定（未設定の項目は pixiv 本番を使う）
+#[derive(Debug, Clone)]
+pub struct PixivEndpoint {
+    pub api_base_url: String,
+    pub auth_token_url: String,
+    pub proxy_url: Option<String>,
+}
+
+impl PixivEndpoint {
+    pub fn from_env() -> Self {
+        let var = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        Self {
+            api_base_url: var("API_BASE_URL")
+                .unwrap_or_else(|| API_BASE_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            auth_token_url: var("AUTH_TOKEN_URL").unwrap_or_else(|| AUTH_TOKEN_URL.to_string()),
+            proxy_url: var("PROXY_URL"),
+        }
+    }
+
+    /// プロキシ設定を反映したHTTPクライアント（http/https/socks5 に対応）
+    pub fn build_http_client(&self) -> Result<Client> {
+        let mut builder = Client::builder().user_agent(USER_AGENT);
+        if let Some(proxy_url) = &self.proxy_url {
+            builder = builder.proxy(Proxy::all(proxy_url).with_location()?);
+        }
+        Ok(builder.build().with_location()?)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PixivApiClient {
+    http: Client,
+    endpoint: PixivEndpoint,
+    access_token: String,
+    refresh_token: String,
+    expires_at: i64,
+    user: Option<AuthUser>,
+}
+
+impl PixivApiClient {
+    /// リフレッシュトークンからアクセストークンを取得する
+    pub async fn authorize(endpoint: PixivEndpoint, refresh_token: &str) -> Result<Self> {
+        let http = endpoint.build_http_client()?;
+
+        let res = http
+            .post(&endpoint.auth_token_url)
+            .form(&[
+                ("client_id", CLIENT_ID),
+                ("client_secret", CLIENT_SECRET),
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+                ("include_policy", "true"),
+            ])
+            .send()
+            .await
+            .with_location()?;
+
+        let text = res.text().await.with_location()?;
+        let json: Value = serde_json::from_str(&text).with_location()?;
+        let access_token = json
+            .get("access_token")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("failed: {}", text))?
+            .to_string();
+
+        // リフレッシュトークンはローテーションされることがある
+        let refresh_token = json
+            .get("refresh_token")
+            .and_then(|v| v.as_str())
+            .unwrap_or(refresh_token)
+            .to_string();
+        let expires_in = json
+            .get("expires_in")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(3600);
+        let user = json
+            .get("user")
+            .cloned()
+            .and_then(|v| serde_json::from_value::<AuthUser>(v).ok());
+
+        Ok(Self {
+            http,
+            endpoint,
+            access_token,
+            refresh_token,
+            expires_at: Utc::now().timestamp() + expires_in,
+            user,
+        })
+    }
+
+    fn is_fresh(&self) -> bool {
+        self.expires_at - Utc::now().timestamp() > TOKEN_REFRESH_MARGIN_SEC
+    }
+
+    async fn get_by_illust_id(&self, path: &str, illust_id: u32) -> Result<(u16, String)> {
+        let res = self
+            .http
+            .get(format!("{}{}", self.endpoint.api_base_url, path))
+            .query(&[("illust_id", illust_id)])
+            .bearer_auth(&self.access_token)
+            .header("App-OS", "android")
+            .send()
+            .await
+            .with_location()?;
+
+        let status = res.status().as_u16();
+        let body = res.text().await.with_location()?;
+
+        Ok((status, body))
+    }
+}
+
+#[async_trait]
+impl IllustSource for PixivApiClient {
+    async fn illust_detail(&self, illust_id: u32) -> Result<(u16, String)> {
+        self.get_by_illust_id("/v1/illust/detail", illust_id).await
+    }
+
+    async fn ugoira_metadata(&self, illust_id: u32) -> Result<(u16, String)> {
+        self.get_by_illust_id("/v1/ugoira/metadata", illust_id)
+            .await
+    }
+}
+
+#[derive(Default)]
+struct AuthState {
+    refresh_token: Option<String>,
+    /// 旧設定（.env の REFRESH_TOKEN）。暗号化して保存するまで持つ
+    legacy_token: Option<String>,
+    passphrase: Option<String>,
+    client: Option<Arc<PixivApiClient>>,
+    last_refreshed_at: Option<i64>,
+    last_error: Option<String>,
+}
+
+struct AuthInner {
+    token_path: PathBuf,
+    env_path: PathBuf,
+    state: Mutex<AuthState>,
+    // 同時に複数のリフレッシュが走らないようにする
+    refresh_lock: tokio::sync::Mutex<()>,
+}
+
+impl AuthInner {
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, AuthState>> {
+        self.state
+            .lock()
+            .map_err(|e| anyhow!("Mutex is poisoned: {}", e))
+    }
+
+    fn fresh_client(&self) -> Result<Option<Arc<PixivApiClient>>> {
+        Ok(self.lock()?.client.clone().filter(|c| c.is_fresh()))
+    }
+
+    /// 期限切れが近ければ更新してからクライアントを返す
+    async fn current_client(&self) -> Result<Arc<PixivApiClient>> {
+        match self.fresh_client()? {
+            Some(client) => Ok(client),
+            None => self.refresh(None).await,
+        }
+    }
+
+    /// アクセストークンを更新する
+    ///
+    /// `stale` を指定した場合、既に別の呼び出しで更新済みならそのクライアントを返す
+    async fn refresh(&self, stale: Option<&Arc<PixivApiClient>>) -> Result<Arc<PixivApiClient>> {
+        let _guard = self.refresh_lock.lock().await;
+
+        if let Some(stale) = stale {
+            if let Some(current) = self.fresh_client()? {
+                if !Arc::ptr_eq(&current, stale) {
+                    return Ok(current);
+                }
+            }
+        }
+
+        // 旧設定のトークンは初回に暗号化して保存し、以降は使わない
+        let refresh_token = {
+            let state = self.lock()?;
+            let Some(refresh_token) = state.refresh_token.clone().or(state.legacy_token.clone())
+            else {
+                bail!("API is unavailable. Please authorize first.");
+            };
+            // 更新後のトークンは暗号化して保存するため、パスフレーズが要る
+            if state.passphrase.is_none() {
+                bail!("Passphrase is required to store the refresh token. Please unlock first.");
+            }
+            refresh_token
+        };
+
+        match PixivApiClient::authorize(PixivEndpoint::from_env(), &refresh_token).await {
+            Ok(client) => {
+                let client = Arc::new(client);
+                {
+                    let mut state = self.lock()?;
+                    state.refresh_token = Some(client.refresh_token.clone());
+                    state.client = Some(client.clone());
+                    state.last_refreshed_at = Some(Utc::now().timestamp());
+                    state.last_error = None;
+                }
+                self.persist()?;
+                Ok(client)
+            }
+            Err(e) => {
+                self.lock()?.last_error = Some(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// 認証情報を暗号化して保存し、旧設定のトークンを .env から消す
+    fn persist(&self) -> Result<()> {
+        let mut state = self.lock()?;
+        let (Some(passphrase), Some(refresh_token)) = (&state.passphrase, &state.refresh_token)
+        else {
+            bail!("Passphrase is required to store the refresh token. Please unlock first.");
+        };
+        let auth = StoredAuth {
+            refresh_token: refresh_token.clone(),
+            user: state.client.as_ref().and_then(|c| c.user.clone()),
+        };
+        token_store::save(&self.token_path, passphrase, &auth)?;
+
+        if state.legacy_token.take().is_some() {
+            token_store::remove_legacy_token(&self.env_path)?;
+        }
+        Ok(())
+    }
+
+    fn status(&self) -> AuthStatus {
+        let state = match self.lock() {
+            Ok(state) => state,
+            Err(e) => {
+                return AuthStatus {
+                    last_error: Some(e.to_string()),
+                    ..Default::default()
+                }
+            }
+        };
+        let user = state.client.as_ref().and_then(|c| c.user.as_ref());
+
+        AuthStatus {
+            authorized: state.client.is_some(),
+            locked: state.passphrase.is_none()
+                && (self.token_path.exists() || state.legacy_token.is_some()),
+            user_name: user.map(|u| u.name.clone()),
+            user_account: user.map(|u| u.account.clone()),
+            expires_at: state
+                .client
+                .as_ref()
+                .map(|c| format_unix_timestamp(c.expires_at)),
+            last_refreshed_at: state.last_refreshed_at.map(format_unix_timestamp),
+            last_error: state.last_error.clone(),
+        }
+    }
+}
+
+/// 認証状態を共有し、必要に応じてトークンを更新する作品詳細ソース
+struct AuthorizedSource {
+    inner: Arc<AuthInner>,
+}
+
+#[async_trait]
+impl IllustSource for AuthorizedSource {
+    async fn illust_detail(&self, illust_id: u32) -> Result<(u16, String)> {
+        let client = self.inner.current_client().await?;
+        let (status, body) = client.illust_detail(illust_id).await?;
+        if !is_auth_error(status, &body) {
+            return Ok((status, body));
+        }
+
+        // アクセストークンが失効していたら更新して一度だけやり直す
+        let client = self.inner.refresh(Some(&client)).await?;
+        client.illust_detail(illust_id).await
+    }
+
+    async fn ugoira_metadata(&self, illust_id: u32) -> Result<(u16, String)> {
+        let client = self.inner.current_client().await?;
+        let (status, body) = client.ugoira_metadata(illust_id).await?;
+        if !is_auth_error(status, &body) {
+            return Ok((status, body));
+        }
+
+        let client = self.inner.refresh(Some(&client)).await?;
+        client.ugoira_metadata(illust_id).await
+    }
 }
 
 pub struct RealPixivClientProvider {
-    inner: Arc<Mutex<Option<PixivClient>>>,
+    inner: Arc<AuthInner>,
 }
 
 impl RealPixivClientProvider {
-    pub fn new() -> Self {
+    pub fn new(token_path: PathBuf, env_path: PathBuf) -> Self {
+        let legacy_token = std::env::var(LEGACY_TOKEN_KEY)
+            .ok()
+            .filter(|t| !t.trim().is_empty());
         Self {
-            inner: Arc::new(Mutex::new(None)),
+            inner: Arc::new(AuthInner {
+                token_path,
+                env_path,
+                state: Mutex::new(AuthState {
+                    legacy_token,
+                    ..Default::default()
+                }),
+                refresh_lock: tokio::sync::Mutex::new(()),
+            }),
         }
     }
 }
@@ -26,37 +369,46 @@ impl RealPixivClientProvider {
 #[async_trait]
 impl PixivClientProvider for RealPixivClientProvider {
     async fn refresh_client(&self) -> Result<()> {
-        async_runtime::spawn_blocking({
-            let inner_clone = self.inner.clone();
-            move || {
-                let mut locked_client = inner_clone
-                    .lock()
-                    .map_err(|e| anyhow!("Mutex is poisoned: {}", e))?;
+        self.inner.refresh(None).await?;
+
+        Ok(())
+    }
+
+    async fn get_client(&self) -> Result<Arc<dyn IllustSource>> {
+        self.inner.current_client().await?;
 
-                let mut client = match locked_client.take() {
-                    Some(c) => c,
-                    None => PixivClient::new()?,
-                };
+        Ok(Arc::new(AuthorizedSource {
+            inner: self.inner.clone(),
+        }))
+    }
 
-                let refresh_token = std::env::var("REFRESH_TOKEN")?;
-                *client.refresh_token_mut() = refresh_token;
-                client.refresh_auth()?;
+    async fn login(&self, refresh_token: String, passphrase: String) -> Result<AuthStatus> {
+        {
+            let mut state = self.inner.lock()?;
+            state.refresh_token = Some(refresh_token);
+            state.passphrase = Some(passphrase);
+        }
+        self.inner.refresh(None).await?;
 
-                locked_client.replace(client);
+        Ok(self.inner.status())
+    }
 
-                Ok::<_, anyhow::Error>(())
+    async fn unlock(&self, passphrase: String) -> Result<AuthStatus> {
+        let stored = token_store::load(&self.inner.token_path, &passphrase)?;
+        {
+            let mut state = self.inner.lock()?;
+            if let Some(stored) = stored {
+                state.refresh_token = Some(stored.refresh_token);
             }
-        })
-        .await??;
+            state.passphrase = Some(passphrase);
+        }
+        // 旧設定から移行する場合もここで暗号化して保存される
+        self.inner.refresh(None).await?;
 
-        Ok(())
+        Ok(self.inner.status())
     }
 
-    async fn get_client(&self) -> Result<PixivClient> {
-        let locked_client = self.inner.lock().unwrap();
-        locked_client
-            .as_ref()
-            .cloned()
-            .ok_or_else(|| anyhow!("API is unavailable. Please authorize first."))
+    fn auth_status(&self) -> AuthStatus {
+        self.inner.status()
     }
 }
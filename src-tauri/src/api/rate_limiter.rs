@@ -0,0 +1,132 @@
+use rand::Rng;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::constants::{
+    FETCH_BACKOFF_BASE_MS, FETCH_CONCURRENCY, FETCH_MAX_DELAY_MS, FETCH_MAX_RETRIES,
+};
+use crate::models::fetch::FetchFailureKind;
+use crate::service::fetch_control::FetchControl;
+
+/// ワーカー間で共有するトークンバケット
+///
+/// 現在の間隔ごとに1トークン補充され、応答に合わせて間隔を調整する
+pub struct RateLimiter {
+    base_delay: Duration,
+    max_delay: Duration,
+    capacity: f64,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    current_delay: Duration,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(base_delay: Duration, capacity: u32) -> Self {
+        let max_delay = Duration::from_millis(FETCH_MAX_DELAY_MS).max(base_delay);
+        Self {
+            base_delay,
+            max_delay,
+            capacity: capacity.max(1) as f64,
+            // 開始直後にまとめて送らないよう1トークンから始める
+            state: Mutex::new(BucketState {
+                current_delay: base_delay,
+                tokens: 1.0,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// INTERVAL_MILL_SEC を基準間隔、同時実行数をバケット容量とする
+    pub fn from_env() -> Self {
+        let interval = std::env::var("INTERVAL_MILL_SEC")
+            .ok()
+            .and_then(|val| val.parse::<u64>().ok())
+            .unwrap_or(1000);
+        Self::new(Duration::from_millis(interval), fetch_concurrency())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BucketState> {
+        // 状態は数値だけなので poison されても続行できる
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn current_delay(&self) -> Duration {
+        self.lock().current_delay
+    }
+
+    /// トークンを1つ取得する。キャンセルされたら true
+    pub async fn acquire(&self, control: &FetchControl) -> bool {
+        loop {
+            let Some(wait) = self.try_take() else {
+                return false;
+            };
+            if control.sleep(wait).await {
+                return true;
+            }
+        }
+    }
+
+    /// 取得できれば None、できなければ次のトークンまでの待ち時間
+    fn try_take(&self) -> Option<Duration> {
+        let mut state = self.lock();
+        if state.current_delay.is_zero() {
+            return None;
+        }
+
+        let now = Instant::now();
+        let refill =
+            now.duration_since(state.last_refill).as_secs_f64() / state.current_delay.as_secs_f64();
+        state.tokens = (state.tokens + refill).min(self.capacity);
+        state.last_refill = now;
+
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            None
+        } else {
+            Some(state.current_delay.mul_f64(1.0 - state.tokens))
+        }
+    }
+
+    /// 成功したら基準間隔へ少しずつ戻す
+    pub fn on_success(&self) {
+        let mut state = self.lock();
+        state.current_delay = (state.current_delay * 3 / 4).max(self.base_delay);
+    }
+
+    /// 制限を受けたら間隔を倍にし、溜まったトークンも捨てる
+    pub fn on_throttled(&self) {
+        let mut state = self.lock();
+        state.current_delay = (state.current_delay * 2).min(self.max_delay);
+        state.tokens = 0.0;
+    }
+
+    /// 再試行するかどうか（認証エラーは再試行しても直らない）
+    pub fn should_retry(&self, kind: FetchFailureKind, attempt: u32) -> bool {
+        attempt < FETCH_MAX_RETRIES
+            && matches!(
+                kind,
+                FetchFailureKind::RateLimited | FetchFailureKind::Transport
+            )
+    }
+
+    /// 指数バックオフ + ジッター
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = FETCH_BACKOFF_BASE_MS.saturating_mul(1 << attempt.min(16));
+        let ceiling = exp.min(self.max_delay.as_millis() as u64);
+        let jitter = rand::rng().random_range(0..=ceiling / 2);
+        Duration::from_millis(ceiling / 2 + jitter).max(self.current_delay())
+    }
+}
+
+/// 同時に投げるリクエスト数（FETCH_CONCURRENCY、未設定なら既定値）
+pub fn fetch_concurrency() -> u32 {
+    std::env::var("FETCH_CONCURRENCY")
+        .ok()
+        .and_then(|val| val.trim().parse::<u32>().ok())
+        .filter(|val| *val > 0)
+        .unwrap_or(FETCH_CONCURRENCY)
+}
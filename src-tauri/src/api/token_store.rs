@@ -0,0 +1,111 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs;
+use std::path::Path;
+
+use crate::constants::TOKEN_KDF_ROUNDS;
+use crate::util::ResultWithLocationExt;
+
+/// 旧設定で .env に平文で置いていたリフレッシュトークン
+pub const LEGACY_TOKEN_KEY: &str = "REFRESH_TOKEN";
+
+/// 暗号化して保存する認証情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredAuth {
+    pub refresh_token: String,
+    pub user: Option<AuthUser>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthUser {
+    pub id: String,
+    pub name: String,
+    pub account: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedFile {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, TOKEN_KDF_ROUNDS, &mut key);
+    key
+}
+
+/// パスフレーズから導出した鍵（AES-256-GCM）で暗号化して保存する
+pub fn save(path: &Path, passphrase: &str, auth: &StoredAuth) -> Result<()> {
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    rand::rng().fill(&mut salt);
+    rand::rng().fill(&mut nonce);
+
+    let key = derive_key(passphrase, &salt);
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| anyhow!("{}", e))?;
+    let plaintext = serde_json::to_vec(auth).with_location()?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+        .map_err(|_| anyhow!("認証情報の暗号化に失敗しました"))?;
+
+    let file = EncryptedFile {
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_location()?;
+    }
+    fs::write(path, serde_json::to_vec(&file).with_location()?).with_location()?;
+
+    Ok(())
+}
+
+/// 保存済みの認証情報を復号する（ファイルが無ければ None）
+pub fn load(path: &Path, passphrase: &str) -> Result<Option<StoredAuth>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let file: EncryptedFile =
+        serde_json::from_slice(&fs::read(path).with_location()?).with_location()?;
+    let salt = STANDARD.decode(file.salt).with_location()?;
+    let nonce = STANDARD.decode(file.nonce).with_location()?;
+    let ciphertext = STANDARD.decode(file.ciphertext).with_location()?;
+
+    let key = derive_key(passphrase, &salt);
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| anyhow!("{}", e))?;
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| anyhow!("パスフレーズが違うか、認証情報が壊れています"))?;
+
+    Ok(Some(serde_json::from_slice(&plaintext).with_location()?))
+}
+
+/// 旧設定（.env の REFRESH_TOKEN）を消す（暗号化して保存した後に呼ぶ）
+pub fn remove_legacy_token(env_path: &Path) -> Result<()> {
+    std::env::remove_var(LEGACY_TOKEN_KEY);
+    if !env_path.exists() {
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(env_path).with_location()?;
+    let lines: Vec<&str> = content
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            line.split('=').next().map(str::trim) != Some(LEGACY_TOKEN_KEY)
+        })
+        .collect();
+    fs::write(env_path, lines.join("\n")).with_location()?;
+
+    Ok(())
+}
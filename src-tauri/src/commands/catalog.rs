@@ -1,32 +1,80 @@
-use tauri::{command, State};
+use tauri::{command, Emitter, Manager, State};
 
-use crate::util::log_error;
+use crate::constants::{JOURNAL_HOLD_DIR, QUARANTINE_DIR};
+use crate::util::{log_error, ResultWithLocationExt};
 use crate::{
     models::{
-        catalog::{AssociateInfo, EditTag},
+        catalog::{
+            AssociateInfo, DuplicateFile, DuplicateGroup, DuplicateResolveStats, EditTag,
+            FileHealth, FileLocation, MoveFailure, MoveProgress, MoveProgressSink,
+            NearDuplicateCluster, OperationEntry, OperationKind, QuarantineStats,
+            UgoiraConvertStats, UgoiraFormat,
+        },
         common::AppState,
     },
     service::{
         catalog::{
-            process_edit_tags, process_get_associated_info, process_label_character_name,
-            process_move_files,
+            process_delete_files, process_edit_tags, process_get_associated_info,
+            process_label_character_name, process_move_files,
+        },
+        duplicate::{process_get_duplicate_report, process_resolve_duplicates},
+        health::{process_fix_file_extension, process_quarantine_files, process_verify_files},
+        journal::{
+            begin_label_operation, begin_operation, finish_operation,
+            process_get_operation_history, process_redo_operation, process_undo_last_operation,
         },
-        common::{execute_named_queries, hash_params, parse_file_info},
+        library::load_library_paths,
+        near_duplicate::{process_find_near_duplicates, process_resolve_near_duplicates},
+        ugoira::process_convert_ugoira,
     },
 };
 
+/// ファイル移動の進捗は画面へイベントで送る
+impl<R: tauri::Runtime> MoveProgressSink for tauri::Window<R> {
+    fn report(&self, progress: &MoveProgress) -> anyhow::Result<()> {
+        self.emit("move_progress", serde_json::json!(progress))
+            .with_location()?;
+        Ok(())
+    }
+}
+
 #[command]
 pub async fn move_files(
     file_names: Vec<String>,
     target_folder: &str,
     move_linked_files: bool,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+    window: tauri::Window,
+) -> Result<Vec<MoveFailure>, String> {
     let mut pool = &state.pool;
-    process_move_files(&mut pool, file_names, target_folder, move_linked_files)
+    let pending = begin_operation(pool, &file_names)
         .await
         .map_err(log_error)?;
-    Ok(())
+
+    let mut conn = pool.acquire().await.map_err(log_error)?;
+    let library = load_library_paths(&mut conn).await.map_err(log_error)?;
+    drop(conn);
+    let stored_folder = library.to_stored_dir(target_folder);
+    let result = process_move_files(
+        &mut pool,
+        file_names,
+        &stored_folder,
+        move_linked_files,
+        Some(&window),
+    )
+    .await;
+
+    // 元に戻せるよう履歴に残す（途中で失敗しても移動済みの分は残す）
+    finish_operation(
+        pool,
+        pending,
+        OperationKind::MoveFiles,
+        Some(target_folder),
+        vec![],
+    )
+    .await
+    .map_err(log_error)?;
+    result.map_err(log_error)
 }
 
 #[command]
@@ -36,26 +84,56 @@ pub async fn label_character_name(
     update_linked_files: bool,
     collect_dir: Option<String>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+    window: tauri::Window,
+) -> Result<Vec<MoveFailure>, String> {
     let mut pool = &state.pool;
-    process_label_character_name(
-        &mut pool,
-        &file_names,
+    let pending = begin_label_operation(pool, &file_names, character_name.as_deref())
+        .await
+        .map_err(log_error)?;
+
+    let mut conn = pool.acquire().await.map_err(log_error)?;
+    let library = load_library_paths(&mut conn).await.map_err(log_error)?;
+    drop(conn);
+    let collect_dir = collect_dir.map(|dir| library.to_stored_dir(&dir));
+    let result = async {
+        process_label_character_name(
+            &mut pool,
+            &file_names,
+            character_name.as_deref(),
+            update_linked_files,
+            collect_dir.as_deref(),
+        )
+        .await?;
+
+        // ファイル移動など副作用はコミット後に
+        match collect_dir {
+            Some(dir) => {
+                process_move_files(
+                    &mut pool,
+                    file_names,
+                    &dir,
+                    update_linked_files,
+                    Some(&window),
+                )
+                .await
+            }
+            None => Ok(vec![]),
+        }
+    }
+    .await;
+
+    // 名付けと移動をまとめて1つの操作として残す（途中で失敗しても反映済みの分は残す）
+    finish_operation(
+        pool,
+        pending,
+        OperationKind::LabelCharacter,
         character_name.as_deref(),
-        update_linked_files,
-        collect_dir.as_deref(),
+        vec![],
     )
     .await
     .map_err(log_error)?;
 
-    // ファイル移動など副作用はコミット後に
-    if let Some(dir) = collect_dir {
-        process_move_files(&mut pool, file_names, &dir, update_linked_files)
-            .await
-            .map_err(log_error)?;
-    }
-
-    Ok(())
+    result.map_err(log_error)
 }
 #[command]
 pub async fn add_remove_tags(
@@ -64,11 +142,19 @@ pub async fn add_remove_tags(
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let pool = &state.pool;
+    let file_names: Vec<String> = edit_tags.iter().map(|e| e.file_name.clone()).collect();
+    let pending = begin_operation(pool, &file_names)
+        .await
+        .map_err(log_error)?;
 
     process_edit_tags(pool, edit_tags, update_linked_files)
         .await
         .map_err(log_error)?;
 
+    finish_operation(pool, pending, OperationKind::EditTags, None, vec![])
+        .await
+        .map_err(log_error)?;
+
     Ok(())
 }
 
@@ -80,6 +166,10 @@ pub async fn overwrite_tags(
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let pool = &state.pool;
+    let pending = begin_operation(pool, &file_names)
+        .await
+        .map_err(log_error)?;
+    let detail = tags.join(", ");
 
     let edit_tags: Vec<EditTag> = file_names
         .into_iter()
@@ -93,6 +183,16 @@ pub async fn overwrite_tags(
         .await
         .map_err(log_error)?;
 
+    finish_operation(
+        pool,
+        pending,
+        OperationKind::OverwriteTags,
+        Some(&detail),
+        vec![],
+    )
+    .await
+    .map_err(log_error)?;
+
     Ok(())
 }
 
@@ -100,47 +200,68 @@ pub async fn overwrite_tags(
 pub async fn delete_files(
     file_names: Vec<String>,
     state: State<'_, AppState>,
+    app: tauri::AppHandle,
 ) -> Result<(), String> {
     let pool = &state.pool;
-    let mut tx = pool.begin().await.map_err(log_error)?;
-
-    for file_name in file_names {
-        let file_info = parse_file_info(file_name.as_str()).map_err(log_error)?;
+    let hold_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(log_error)?
+        .join(JOURNAL_HOLD_DIR);
+    let pending = begin_operation(pool, &file_names)
+        .await
+        .map_err(log_error)?;
 
-        // 1. save_dir, cnum を取得
-        let (save_dir, cnum): (String, i32) = sqlx::query_as(
-            "SELECT save_dir, cnum FROM ILLUST_INFO WHERE illust_id = ? AND suffix = ?",
-        )
-        .bind(file_info.illust_id)
-        .bind(file_info.suffix)
-        .fetch_one(&mut *tx)
+    // 元に戻せる間は退避フォルダに置いておく
+    let held_files = process_delete_files(pool, file_names, &hold_dir)
         .await
         .map_err(log_error)?;
 
-        // 2. ファイル削除
-        let source_path = std::path::Path::new(&save_dir).join(&file_name);
-        trash::delete(source_path).map_err(log_error)?;
-
-        // 3. ILLUST_INFO の削除と TAG_INFO と ILLUST_DETAIL の後処理
-        let delete_sql = include_str!("../sql/catalog/delete_file_registration.sql");
-        execute_named_queries(
-            &mut *tx,
-            delete_sql,
-            &hash_params(&vec![
-                (":illust_id", file_info.illust_id.into()),
-                (":suffix", file_info.suffix.into()),
-                (":cnum", cnum.into()),
-            ])
-            .map_err(log_error)?,
-        )
+    finish_operation(pool, pending, OperationKind::DeleteFiles, None, held_files)
         .await
         .map_err(log_error)?;
-    }
 
-    tx.commit().await.map_err(log_error)?;
     Ok(())
 }
 
+#[command]
+pub async fn undo_last_operation(
+    state: State<'_, AppState>,
+    window: tauri::Window,
+) -> Result<Option<OperationEntry>, String> {
+    let pool = &state.pool;
+
+    let result = process_undo_last_operation(pool).await.map_err(log_error)?;
+
+    // DB変更を通知
+    window.emit("update_db", ()).unwrap();
+
+    Ok(result)
+}
+
+#[command]
+pub async fn redo_operation(
+    state: State<'_, AppState>,
+    window: tauri::Window,
+) -> Result<Option<OperationEntry>, String> {
+    let pool = &state.pool;
+
+    let result = process_redo_operation(pool).await.map_err(log_error)?;
+
+    // DB変更を通知
+    window.emit("update_db", ()).unwrap();
+
+    Ok(result)
+}
+
+#[command]
+pub async fn get_operation_history(
+    state: State<'_, AppState>,
+) -> Result<Vec<OperationEntry>, String> {
+    let pool = &state.pool;
+    process_get_operation_history(pool).await.map_err(log_error)
+}
+
 #[command]
 pub async fn get_associated_info(
     file_names: Vec<String>,
@@ -151,3 +272,135 @@ pub async fn get_associated_info(
         .await
         .map_err(|e| e.to_string())?)
 }
+
+#[command]
+pub async fn convert_ugoira(
+    format: UgoiraFormat,
+    illust_ids: Vec<u32>,
+    state: State<'_, AppState>,
+    window: tauri::Window,
+) -> Result<UgoiraConvertStats, String> {
+    let pool = &state.pool;
+
+    // 未変換の zip をアニメーション画像に変換
+    let result = process_convert_ugoira(pool, format, illust_ids)
+        .await
+        .map_err(log_error)?;
+
+    // DB変更を通知
+    window.emit("update_db", ()).unwrap();
+
+    Ok(result)
+}
+
+#[command]
+pub async fn get_duplicate_report(
+    state: State<'_, AppState>,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let pool = &state.pool;
+    process_get_duplicate_report(pool).await.map_err(log_error)
+}
+
+#[command]
+pub async fn resolve_duplicates(
+    hash: String,
+    keep: DuplicateFile,
+    state: State<'_, AppState>,
+    window: tauri::Window,
+) -> Result<DuplicateResolveStats, String> {
+    let pool = &state.pool;
+
+    // 選んだ1件を残して他を削除
+    let result = process_resolve_duplicates(pool, hash, keep)
+        .await
+        .map_err(log_error)?;
+
+    // DB変更を通知
+    window.emit("update_db", ()).unwrap();
+
+    Ok(result)
+}
+
+#[command]
+pub async fn find_near_duplicates(
+    threshold: u32,
+    state: State<'_, AppState>,
+) -> Result<Vec<NearDuplicateCluster>, String> {
+    let pool = &state.pool;
+    process_find_near_duplicates(pool, threshold)
+        .await
+        .map_err(log_error)
+}
+
+#[command]
+pub async fn resolve_near_duplicates(
+    keep: DuplicateFile,
+    remove: Vec<DuplicateFile>,
+    state: State<'_, AppState>,
+    window: tauri::Window,
+) -> Result<DuplicateResolveStats, String> {
+    let pool = &state.pool;
+
+    // 選んだ1件を残して指定したファイルを削除
+    let result = process_resolve_near_duplicates(pool, keep, remove)
+        .await
+        .map_err(log_error)?;
+
+    // DB変更を通知
+    window.emit("update_db", ()).unwrap();
+
+    Ok(result)
+}
+
+#[command]
+pub async fn verify_files(
+    folders: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<FileHealth>, String> {
+    let pool = &state.pool;
+    process_verify_files(pool, folders).await.map_err(log_error)
+}
+
+#[command]
+pub async fn fix_file_extension(
+    file: FileLocation,
+    state: State<'_, AppState>,
+    window: tauri::Window,
+) -> Result<FileLocation, String> {
+    let pool = &state.pool;
+
+    // 中身の形式に合わせて拡張子を付け直す
+    let result = process_fix_file_extension(pool, file)
+        .await
+        .map_err(log_error)?;
+
+    // DB変更を通知
+    window.emit("update_db", ()).unwrap();
+
+    Ok(result)
+}
+
+#[command]
+pub async fn quarantine_files(
+    files: Vec<FileLocation>,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    window: tauri::Window,
+) -> Result<QuarantineStats, String> {
+    let pool = &state.pool;
+    let quarantine_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(log_error)?
+        .join(QUARANTINE_DIR);
+
+    // 壊れたファイルを退避して登録から外す
+    let result = process_quarantine_files(pool, files, &quarantine_dir)
+        .await
+        .map_err(log_error)?;
+
+    // DB変更を通知
+    window.emit("update_db", ()).unwrap();
+
+    Ok(result)
+}
@@ -1,16 +1,20 @@
 use tauri::{command, Emitter, State};
 
 use crate::constants;
-use crate::models::collect::FileSummary;
+use crate::models::collect::{FileSummary, SyncResult};
 use crate::models::search::TagInfo;
 use crate::service::collect::{
-    apply_file_moves, collect_character_info, collect_illust_detail, get_collect_summary,
-    mark_illust_move_targets, prepare_collect_ui_work, process_sync_db, reflesh_collect_work,
+    collect_character_info, collect_dir, collect_illust_detail, get_collect_root,
+    get_collect_summary, mark_illust_move_targets, prepare_collect_ui_work, process_sync_db,
+    reflesh_collect_work,
 };
+use crate::service::common::normalize_dir;
+use crate::service::file_move::process_file_moves;
+use crate::service::library::{load_library_paths, process_relocate_library};
 use crate::util::log_error;
 use crate::{
     models::{
-        collect::{CollectSummary, TagAssignment},
+        collect::{CollectResult, CollectSummary, RelocateStats, TagAssignment},
         common::AppState,
     },
     service::collect::sort_collect_work,
@@ -58,24 +62,16 @@ pub async fn assign_collect(
     }
 
     // root を取得（なければ None）
-    let root: Option<String> = sqlx::query_scalar("SELECT value FROM COMMON_MST WHERE key = ?")
-        .bind(constants::COLLECT_ROOT)
-        .fetch_optional(&mut *tx)
-        .await
-        .map_err(log_error)?;
+    let root = get_collect_root(&mut *tx).await.map_err(log_error)?;
 
+    let library = load_library_paths(&mut tx).await.map_err(log_error)?;
     let collect_dir = root.map(|r| {
-        let mut parts = vec![r];
-
-        if let Some(series) = assignment.series.clone() {
-            parts.push(series);
-        }
-
-        if let Some(character) = assignment.character.clone() {
-            parts.push(character);
-        }
-
-        parts.join("\\")
+        collect_dir(
+            &library,
+            &r,
+            assignment.series.as_deref(),
+            assignment.character.as_deref(),
+        )
     });
 
     let entity_key = assignment
@@ -182,7 +178,7 @@ pub async fn load_assignments(state: State<'_, AppState>) -> Result<Vec<CollectS
 pub async fn perform_collect(
     state: State<'_, AppState>,
     window: tauri::Window,
-) -> Result<Vec<CollectSummary>, String> {
+) -> Result<CollectResult, String> {
     let pool = &state.pool;
     let mut tx = pool.begin().await.map_err(log_error)?;
 
@@ -202,7 +198,10 @@ pub async fn perform_collect(
 
     tx.commit().await.map_err(log_error)?;
 
-    apply_file_moves(moves);
+    // ファイルを移せたものだけ save_dir を書き換える
+    let failed_moves = process_file_moves(pool, moves, Some(&window))
+        .await
+        .map_err(log_error)?;
 
     let mut tx = pool.begin().await.map_err(log_error)?;
 
@@ -218,7 +217,11 @@ pub async fn perform_collect(
     window.emit("update_db", ()).unwrap();
 
     // 結果を返却
-    get_collect_summary(pool).await.map_err(|e| e.to_string())
+    let summary = get_collect_summary(pool).await.map_err(|e| e.to_string())?;
+    Ok(CollectResult {
+        summary,
+        failed_moves,
+    })
 }
 
 #[command]
@@ -227,7 +230,7 @@ pub async fn set_root(root: String, state: State<'_, AppState>) -> Result<(), St
 
     sqlx::query("INSERT OR REPLACE INTO COMMON_MST (key, value) VALUES (?, ?)")
         .bind(constants::COLLECT_ROOT)
-        .bind(root.clone())
+        .bind(normalize_dir(&root))
         .execute(pool)
         .await
         .map_err(log_error)?;
@@ -235,6 +238,29 @@ pub async fn set_root(root: String, state: State<'_, AppState>) -> Result<(), St
     Ok(())
 }
 
+#[command]
+pub async fn relocate_library(
+    old_prefix: String,
+    new_prefix: String,
+    dry_run: bool,
+    store_relative: bool,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+) -> Result<RelocateStats, String> {
+    let pool = &state.pool;
+
+    let stats = process_relocate_library(pool, &old_prefix, &new_prefix, dry_run, store_relative)
+        .await
+        .map_err(log_error)?;
+
+    if !dry_run {
+        // DB変更を通知
+        window.emit("update_db", ()).unwrap();
+    }
+
+    Ok(stats)
+}
+
 #[command]
 pub async fn get_root(state: State<'_, AppState>) -> Result<Option<String>, String> {
     let pool = &state.pool;
@@ -263,7 +289,7 @@ pub async fn get_available_unique_tags(state: State<'_, AppState>) -> Result<Vec
 }
 
 #[command]
-pub async fn sync_db(root: String, state: State<'_, AppState>) -> Result<Vec<FileSummary>, String> {
+pub async fn sync_db(root: String, state: State<'_, AppState>) -> Result<SyncResult, String> {
     let mut pool = &state.pool;
     let res = process_sync_db(root, &mut pool).await.map_err(log_error)?;
 
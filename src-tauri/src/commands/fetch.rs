@@ -1,18 +1,36 @@
 use futures::future::join_all;
+use std::collections::HashSet;
 use std::path::Path;
 use std::vec::Vec;
 use tauri::{command, Emitter, State};
 use walkdir::WalkDir;
 
+use crate::models::catalog::FileHealthStatus;
 use crate::models::common::AppState;
-use crate::models::fetch::{FileCounts, FileDetail, ProcessStats};
+use crate::models::fetch::{
+    FetchJobSummary, FileCounts, FileDetail, ProcessStats, ProgressSink, RefreshChange, RefreshRun,
+    TagProgress,
+};
 
 use crate::models::fetch::FolderCount;
 use crate::service::fetch::{
-    extract_dir_detail, extract_missing_files, prepare_illust_fetch_work,
-    process_fetch_illust_detail,
+    extract_dir_detail, extract_missing_files, get_refresh_changes, get_refresh_runs,
+    get_resumable_fetch_job, prepare_illust_fetch_work, process_fetch_illust_detail,
+    process_import_local_metadata, process_refetch_illust_detail, process_refresh_stale_details,
+    process_reparse_cached_details, process_resume_fetch_job,
 };
-use crate::util::log_error;
+use crate::service::health::process_check_file_details;
+use crate::service::library::load_library_paths;
+use crate::util::{log_error, ResultWithLocationExt};
+
+/// フェッチの進捗は画面へイベントで送る
+impl<R: tauri::Runtime> ProgressSink for tauri::Window<R> {
+    fn report(&self, progress: &TagProgress) -> anyhow::Result<()> {
+        self.emit("tag_progress", serde_json::json!(progress))
+            .with_location()?;
+        Ok(())
+    }
+}
 
 #[command]
 pub async fn count_files_in_dir(
@@ -21,6 +39,9 @@ pub async fn count_files_in_dir(
 ) -> Result<FileCounts, String> {
     let mut pool = &state.pool;
 
+    // 実行中のジョブがあればワークテーブルを作り直せないので先に断る
+    state.fetch_control.ensure_idle().map_err(log_error)?;
+
     // フォルダ数
     let folder_counts: Vec<FolderCount> = folders
         .iter()
@@ -54,9 +75,29 @@ pub async fn count_files_in_dir(
         .sum();
 
     // ファイル詳細に変換
-    let tasks = folders.iter().map(|folder| extract_dir_detail(folder));
+    let mut conn = pool.acquire().await.map_err(log_error)?;
+    let library = load_library_paths(&mut conn).await.map_err(log_error)?;
+    drop(conn);
+    let tasks = folders
+        .iter()
+        .map(|folder| extract_dir_detail(folder, &library));
     let results: Vec<Vec<FileDetail>> = join_all(tasks).await;
-    let file_details: Vec<FileDetail> = results.into_iter().flatten().collect();
+    let mut file_details: Vec<FileDetail> = results.into_iter().flatten().collect();
+
+    // 中身を検査し、壊れたファイルは取り込み対象から外す
+    let health = process_check_file_details(pool, &file_details)
+        .await
+        .map_err(log_error)?;
+    let broken: HashSet<(&str, &str)> = health
+        .iter()
+        .filter(|h| h.status.is_broken())
+        .map(|h| (h.save_dir.as_str(), h.file_name.as_str()))
+        .collect();
+    file_details.retain(|d| !broken.contains(&(d.save_dir.as_str(), d.file_name.as_str())));
+    let unhealthy = health
+        .iter()
+        .filter(|h| h.status != FileHealthStatus::Ok)
+        .count() as i32;
 
     // ワークテーブルに保存
     prepare_illust_fetch_work(&mut pool, &file_details)
@@ -74,7 +115,8 @@ pub async fn count_files_in_dir(
         .and_then(|val| val.parse::<u32>().ok())
         .unwrap_or(1000);
 
-    let estimate_process_time = (interval + 200) * unique_count / 1000;
+    // 並列実行時はリクエスト間隔がそのままスループットになる
+    let estimate_process_time = (interval as u64 * unique_count as u64 / 1000) as u32;
     let hours = estimate_process_time / 3600;
     let minutes = (estimate_process_time % 3600) / 60;
     let seconds = estimate_process_time % 60;
@@ -84,6 +126,7 @@ pub async fn count_files_in_dir(
         folders: folder_counts,
         total: total_files,
         process_time: formatted_process_time,
+        unhealthy,
     })
 }
 
@@ -101,10 +144,14 @@ pub async fn capture_illust_detail(
     let mut pool = &state.pool;
 
     // 取得実行
-    let result: ProcessStats =
-        process_fetch_illust_detail(&mut pool, &pixiv_client, window.clone())
-            .await
-            .map_err(log_error)?;
+    let result: ProcessStats = process_fetch_illust_detail(
+        &mut pool,
+        pixiv_client.as_ref(),
+        &state.fetch_control,
+        &window,
+    )
+    .await
+    .map_err(log_error)?;
 
     // DB変更を通知
     window.emit("update_db", ()).unwrap();
@@ -125,7 +172,10 @@ pub async fn recapture_illust_detail(
 
     let mut pool = &state.pool;
 
-    // 失敗ファイルを抽出
+    // 実行中のジョブのワークテーブルを消さないよう先に確かめる
+    state.fetch_control.ensure_idle().map_err(log_error)?;
+
+    // 失敗ファイルを抽出（一時的な失敗のみ）
     let file_details = extract_missing_files(pool).await.map_err(log_error)?;
 
     // ワークテーブルに保存
@@ -134,10 +184,128 @@ pub async fn recapture_illust_detail(
         .map_err(log_error)?;
 
     // 再取得実行
-    let result: ProcessStats =
-        process_fetch_illust_detail(&mut pool, &pixiv_client, window.clone())
-            .await
-            .map_err(log_error)?;
+    let result: ProcessStats = process_refetch_illust_detail(
+        &mut pool,
+        pixiv_client.as_ref(),
+        &state.fetch_control,
+        &window,
+    )
+    .await
+    .map_err(log_error)?;
+
+    // DB変更を通知
+    window.emit("update_db", ()).unwrap();
+
+    Ok(result)
+}
+
+#[command]
+pub async fn resume_fetch_job(
+    state: State<'_, AppState>,
+    window: tauri::Window,
+) -> Result<ProcessStats, String> {
+    let pixiv_client = state
+        .pixiv_client_provider
+        .get_client()
+        .await
+        .map_err(log_error)?;
+
+    let mut pool = &state.pool;
+
+    // 中断したジョブを再開
+    let result: ProcessStats = process_resume_fetch_job(
+        &mut pool,
+        pixiv_client.as_ref(),
+        &state.fetch_control,
+        &window,
+    )
+    .await
+    .map_err(log_error)?;
+
+    // DB変更を通知
+    window.emit("update_db", ()).unwrap();
+
+    Ok(result)
+}
+
+#[command]
+pub async fn reparse_cached_details(
+    state: State<'_, AppState>,
+    window: tauri::Window,
+) -> Result<ProcessStats, String> {
+    let pool = &state.pool;
+
+    // キャッシュから詳細情報を再構築
+    let result = process_reparse_cached_details(pool)
+        .await
+        .map_err(log_error)?;
+
+    // DB変更を通知
+    window.emit("update_db", ()).unwrap();
+
+    Ok(result)
+}
+
+#[command]
+pub async fn import_local_metadata(
+    folders: Vec<String>,
+    pixivutil_db: Option<String>,
+    state: State<'_, AppState>,
+    window: tauri::Window,
+) -> Result<ProcessStats, String> {
+    let pool = &state.pool;
+
+    // ファイル詳細に変換
+    let mut conn = pool.acquire().await.map_err(log_error)?;
+    let library = load_library_paths(&mut conn).await.map_err(log_error)?;
+    drop(conn);
+    let tasks = folders
+        .iter()
+        .map(|folder| extract_dir_detail(folder, &library));
+    let results: Vec<Vec<FileDetail>> = join_all(tasks).await;
+    let file_details: Vec<FileDetail> = results.into_iter().flatten().collect();
+
+    // ダウンローダーのメタデータから登録
+    let result = process_import_local_metadata(
+        pool,
+        file_details,
+        pixivutil_db,
+        &state.fetch_control,
+        &window,
+    )
+    .await
+    .map_err(log_error)?;
+
+    // DB変更を通知
+    window.emit("update_db", ()).unwrap();
+
+    Ok(result)
+}
+
+#[command]
+pub async fn refresh_stale_details(
+    days: u32,
+    state: State<'_, AppState>,
+    window: tauri::Window,
+) -> Result<ProcessStats, String> {
+    let pixiv_client = state
+        .pixiv_client_provider
+        .get_client()
+        .await
+        .map_err(log_error)?;
+
+    let pool = &state.pool;
+
+    // 取得から一定期間たった作品を取り直す
+    let result = process_refresh_stale_details(
+        pool,
+        days,
+        pixiv_client.as_ref(),
+        &state.fetch_control,
+        &window,
+    )
+    .await
+    .map_err(log_error)?;
 
     // DB変更を通知
     window.emit("update_db", ()).unwrap();
@@ -145,6 +313,45 @@ pub async fn recapture_illust_detail(
     Ok(result)
 }
 
+#[command]
+pub async fn get_refresh_history(state: State<'_, AppState>) -> Result<Vec<RefreshRun>, String> {
+    let pool = &state.pool;
+
+    get_refresh_runs(pool).await.map_err(log_error)
+}
+
+#[command]
+pub async fn get_refresh_run_changes(
+    run_id: i64,
+    state: State<'_, AppState>,
+) -> Result<Vec<RefreshChange>, String> {
+    let pool = &state.pool;
+
+    get_refresh_changes(pool, run_id).await.map_err(log_error)
+}
+
+#[command]
+pub async fn get_fetch_job(state: State<'_, AppState>) -> Result<Option<FetchJobSummary>, String> {
+    let pool = &state.pool;
+
+    get_resumable_fetch_job(pool).await.map_err(log_error)
+}
+
+#[command]
+pub async fn pause_fetch(state: State<'_, AppState>) -> Result<(), String> {
+    state.fetch_control.pause().map_err(log_error)
+}
+
+#[command]
+pub async fn resume_fetch(state: State<'_, AppState>) -> Result<(), String> {
+    state.fetch_control.resume().map_err(log_error)
+}
+
+#[command]
+pub async fn cancel_fetch(state: State<'_, AppState>) -> Result<(), String> {
+    state.fetch_control.cancel().map_err(log_error)
+}
+
 #[command]
 pub async fn init_pixiv_client(state: State<'_, AppState>) -> Result<(), String> {
     state
@@ -1,5 +1,6 @@
 use tauri::{command, State};
 
+use crate::constants::IMAGE_SEARCH_MAX_DISTANCE;
 use crate::models::{common::AppState, search::*};
 use crate::service::search::*;
 use crate::util::log_error;
@@ -53,11 +54,12 @@ pub async fn search_by_criteria(
     tags: Vec<String>,
     character: Option<String>,
     author_id: Option<u32>,
+    author_name: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<Vec<SearchResult>, String> {
     let pool = &state.pool;
 
-    let results = process_search_by_criteria(tags, character, author_id, pool)
+    let results = process_search_by_criteria(tags, character, author_id, author_name, pool)
         .await
         .map_err(log_error)?;
 
@@ -76,16 +78,61 @@ pub async fn search_by_id(
     Ok(results)
 }
 
+#[command]
+pub async fn search_by_image(
+    path: String,
+    max_distance: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SearchResult>, String> {
+    let pool = &state.pool;
+
+    let max_distance = max_distance.unwrap_or(IMAGE_SEARCH_MAX_DISTANCE);
+    let results = process_search_by_image(path, max_distance, pool)
+        .await
+        .map_err(log_error)?;
+
+    Ok(results)
+}
+
+#[command]
+pub async fn get_author_history(
+    author_id: u32,
+    state: State<'_, AppState>,
+) -> Result<Vec<AuthorNameHistory>, String> {
+    let pool = &state.pool;
+
+    let history = process_get_author_history(author_id, pool)
+        .await
+        .map_err(log_error)?;
+
+    Ok(history)
+}
+
+#[command]
+pub async fn get_illust_meta(
+    illust_id: u32,
+    state: State<'_, AppState>,
+) -> Result<Option<IllustMeta>, String> {
+    let pool = &state.pool;
+
+    let meta = process_get_illust_meta(illust_id, pool)
+        .await
+        .map_err(log_error)?;
+
+    Ok(meta)
+}
+
 #[command]
 pub async fn filter_dropdowns(
     tags: Vec<String>,
     character: Option<String>,
     author_id: Option<u32>,
+    author_name: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<(Vec<TagInfo>, Vec<CharacterInfo>, Vec<AuthorInfo>), String> {
     let pool = &state.pool;
 
-    let results = process_filter_dropdowns(tags, character, author_id, pool)
+    let results = process_filter_dropdowns(tags, character, author_id, author_name, pool)
         .await
         .map_err(log_error)?;
 
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 use std::fs;
-use tauri::command;
+use tauri::{command, State};
 
-use crate::models::settings::EnvConfig;
+use crate::models::common::AppState;
+use crate::models::settings::{AuthStatus, EnvConfig, FileNamePreview};
+use crate::service::file_pattern::{self, preview_file_names};
 use crate::service::setting::{
     from_map, get_config_path, process_pixiv_authorization, to_env_string,
 };
@@ -35,7 +37,63 @@ pub async fn save_environment_variables(
 }
 
 #[command]
-pub async fn pixiv_authorization(app: tauri::AppHandle) -> Result<String, String> {
+pub async fn pixiv_authorization(
+    passphrase: String,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<AuthStatus, String> {
     let refresh_token = process_pixiv_authorization(app).await.map_err(log_error)?;
-    Ok(refresh_token)
+
+    // トークンはフロントへ返さず、暗号化して保持する
+    state
+        .pixiv_client_provider
+        .login(refresh_token, passphrase)
+        .await
+        .map_err(log_error)
+}
+
+#[command]
+pub async fn unlock_auth(
+    passphrase: String,
+    state: State<'_, AppState>,
+) -> Result<AuthStatus, String> {
+    state
+        .pixiv_client_provider
+        .unlock(passphrase)
+        .await
+        .map_err(log_error)
+}
+
+#[command]
+pub async fn get_auth_status(state: State<'_, AppState>) -> Result<AuthStatus, String> {
+    Ok(state.pixiv_client_provider.auth_status())
+}
+
+#[command]
+pub async fn get_file_name_patterns(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let mut conn = state.pool.acquire().await.map_err(log_error)?;
+
+    file_pattern::get_file_name_patterns(&mut conn)
+        .await
+        .map_err(log_error)
+}
+
+#[command]
+pub async fn set_file_name_patterns(
+    patterns: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let mut conn = state.pool.acquire().await.map_err(log_error)?;
+
+    file_pattern::save_file_name_patterns(&mut conn, patterns)
+        .await
+        .map_err(log_error)
+}
+
+#[command]
+pub async fn preview_file_name_patterns(
+    patterns: Vec<String>,
+    file_names: Vec<String>,
+) -> Result<Vec<FileNamePreview>, String> {
+    preview_file_names(&patterns, &file_names).map_err(log_error)
 }
@@ -1,8 +1,46 @@
 pub const COLLECT_ROOT: &str = "collect_root";
-pub const UNCATEGORIZED_DIR: &str = "\\uncategorized";
+pub const UNCATEGORIZED_DIR: &str = "uncategorized";
+/// "1" なら root 配下の保存先を root からの相対パスで持つ
+pub const RELATIVE_PATHS: &str = "relative_paths";
+pub const FILE_NAME_PATTERNS: &str = "file_name_patterns";
+
+/// 既定のファイル名パターン（名前付きキャプチャ id / page / ext とその他の付加情報）
+pub const DEFAULT_FILE_NAME_PATTERNS: &[&str] = &[
+    r"(?P<id>\d+)_p(?P<page>\d+)\.(?P<ext>jpe?g|png|gif|webp)",
+    r"(?P<id>\d+)_p(?P<page>\d+)_(?P<size>master\d+|square\d+|custom\d+)\.(?P<ext>jpe?g|png|gif|webp)",
+    r"(?P<artist>.+?) - (?P<id>\d+)_p(?P<page>\d+)\.(?P<ext>jpe?g|png|gif|webp)",
+    // うごイラの zip とその変換結果
+    r"(?P<id>\d+)_ugoira\.(?P<ext>zip|webp|gif|png)",
+    r"(?P<id>\d+)_p(?P<page>0)\.(?P<ext>zip)",
+];
 
 pub const CLIENT_ID: &str = "MOBrBDS8blbauoSck0ZfDbtuzpyT";
 pub const CLIENT_SECRET: &str = "lsACyCD94FhDUtGTXi3QzcFE2uU1hqtDaKeqrdwj";
 pub const REDIRECT_URI: &str = "https://app-api.pixiv.net/web/v1/users/auth/pixiv/callback";
 pub const LOGIN_URL: &str = "https://app-api.pixiv.net/web/v1/login";
 pub const AUTH_TOKEN_URL: &str = "https://oauth.secure.pixiv.net/auth/token";
+
+/// 画像検索で一致とみなす dHash の距離と最大件数
+pub const IMAGE_SEARCH_MAX_DISTANCE: u32 = 10;
+pub const IMAGE_SEARCH_LIMIT: usize = 100;
+
+/// 壊れたファイルの退避先（アプリデータ配下）
+pub const QUARANTINE_DIR: &str = "quarantine";
+
+/// 削除したファイルを元に戻せる間置いておくフォルダ（アプリデータ配下）
+pub const JOURNAL_HOLD_DIR: &str = "journal_hold";
+/// 残しておく操作履歴の件数（古いものの削除ファイルはゴミ箱へ送る）
+pub const JOURNAL_LIMIT: i64 = 50;
+
+pub const FETCH_MAX_RETRIES: u32 = 3;
+pub const FETCH_BACKOFF_BASE_MS: u64 = 2_000;
+pub const FETCH_MAX_DELAY_MS: u64 = 60_000;
+pub const FETCH_CONCURRENCY: u32 = 4;
+pub const FETCH_WRITE_BATCH: usize = 32;
+
+pub const API_BASE_URL: &str = "https://app-api.pixiv.net";
+pub const USER_AGENT: &str = "PixivAndroidApp/5.0.234 (Android 11; Pixel 5)";
+
+pub const TOKEN_FILE_NAME: &str = "auth.bin";
+pub const TOKEN_REFRESH_MARGIN_SEC: i64 = 300;
+pub const TOKEN_KDF_ROUNDS: u32 = 600_000;
@@ -0,0 +1,53 @@
+use std::fmt;
+
+use crate::models::fetch::FetchFailureKind;
+
+#[derive(Debug)]
+pub struct FetchError {
+    pub kind: FetchFailureKind,
+    pub status: Option<u16>,
+    pub message: String,
+    /// 再試行も含めたリクエスト回数
+    pub attempts: u32,
+}
+
+impl FetchError {
+    pub fn new<T: fmt::Display>(kind: FetchFailureKind, status: Option<u16>, message: T) -> Self {
+        Self {
+            kind,
+            status,
+            message: message.to_string(),
+            attempts: 1,
+        }
+    }
+
+    /// HTTPステータスとレスポンス本文から失敗種別を判定する
+    pub fn from_response(status: u16, body: &str) -> Self {
+        let kind = match status {
+            _ if is_auth_error(status, body) => FetchFailureKind::Auth,
+            400 | 404 => FetchFailureKind::NotFound,
+            // pixiv はレート制限を 403 + "Rate Limit" で返す
+            403 if body.contains("Rate Limit") => FetchFailureKind::RateLimited,
+            403 => FetchFailureKind::Restricted,
+            429 => FetchFailureKind::RateLimited,
+            _ => FetchFailureKind::Transport,
+        };
+        Self::new(kind, Some(status), body)
+    }
+}
+
+/// アクセストークンの失効・不正による失敗か
+pub fn is_auth_error(status: u16, body: &str) -> bool {
+    status == 401 || (status == 400 && (body.contains("invalid_grant") || body.contains("OAuth")))
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.status {
+            Some(status) => write!(f, "[{:?}:{}] {}", self.kind, status, self.message),
+            None => write!(f, "[{:?}] {}", self.kind, self.message),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
@@ -6,6 +6,7 @@ pub enum FileParseError {
     InvalidIllustId(String),
     InvalidSuffix(String),
     RegexCompileError(String),
+    InvalidPattern(String),
     FileNameNotFound(String),
     ParentDirNotFound(String),
 }
@@ -17,6 +18,7 @@ impl fmt::Display for FileParseError {
             Self::InvalidIllustId(s) => write!(f, "illust_id のパースに失敗: {}", s),
             Self::InvalidSuffix(s) => write!(f, "suffix のパースに失敗: {}", s),
             Self::RegexCompileError(s) => write!(f, "正規表現のコンパイルに失敗: {}", s),
+            Self::InvalidPattern(s) => write!(f, "ファイル名パターンが不正です: {}", s),
             Self::FileNameNotFound(s) => write!(f, "ファイル名が取得できません: {}", s),
             Self::ParentDirNotFound(s) => write!(f, "親ディレクトリの取得に失敗しました: {}", s),
         }
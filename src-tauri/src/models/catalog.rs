@@ -1,7 +1,11 @@
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use sqlx::{error::BoxDynError, sqlite::Sqlite, Decode, Type};
 use ts_rs::TS;
 
+use crate::service::common::format_unix_timestamp;
+
 #[derive(Serialize, Deserialize, Debug, Clone, TS)]
 #[ts(export)]
 pub struct EditTag {
@@ -29,3 +33,333 @@ pub struct AssociateSaveDir {
     pub save_dir: String,
     pub count: i32,
 }
+
+/// うごイラの変換形式（UGOIRA_INFO.format）
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, TS)]
+#[ts(export)]
+#[serde(rename_all = "lowercase")]
+pub enum UgoiraFormat {
+    WebP = 0,
+    Gif = 1,
+    Apng = 2,
+}
+
+impl UgoiraFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            UgoiraFormat::WebP => "webp",
+            UgoiraFormat::Gif => "gif",
+            UgoiraFormat::Apng => "png",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default, TS)]
+#[ts(export)]
+pub struct UgoiraConvertStats {
+    pub converted: u32,
+    pub failed: u32,
+    pub failed_files: Vec<String>,
+}
+
+/// 内容が同じファイルのまとまり
+#[derive(Serialize, Debug, Clone, TS)]
+#[ts(export)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    pub file_size: i64,
+    pub files: Vec<DuplicateFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS, FromRow)]
+#[ts(export)]
+pub struct DuplicateFile {
+    pub illust_id: i32,
+    pub suffix: i32,
+    pub save_dir: String,
+    pub file_name: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default, TS)]
+#[ts(export)]
+pub struct DuplicateResolveStats {
+    pub removed: u32,
+    /// 内容が変わっていた、またはゴミ箱へ移せなかったため残したファイル
+    pub skipped_files: Vec<String>,
+}
+
+/// 見た目が近い画像のまとまり（先頭が解像度・サイズの最も大きいもの）
+#[derive(Serialize, Debug, Clone, TS)]
+#[ts(export)]
+pub struct NearDuplicateCluster {
+    pub files: Vec<NearDuplicateFile>,
+}
+
+#[derive(Serialize, Debug, Clone, TS)]
+#[ts(export)]
+pub struct NearDuplicateFile {
+    pub illust_id: i32,
+    pub suffix: i32,
+    pub save_dir: String,
+    pub file_name: String,
+    pub width: u32,
+    pub height: u32,
+    pub file_size: i64,
+    /// 先頭のファイルとのハミング距離
+    pub distance: u32,
+}
+
+/// FILE_HEALTH.status
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum FileHealthStatus {
+    Ok = 0,
+    /// 画像としては読めるが拡張子と中身の形式が違う
+    Mislabeled = 1,
+    /// 形式は分かるが最後まで読めない（途中で切れたダウンロードなど）
+    Truncated = 2,
+    /// 画像ではない（HTML のエラーページなど）
+    NotImage = 3,
+}
+
+impl FileHealthStatus {
+    /// 取り込み対象から外す状態か
+    pub fn is_broken(self) -> bool {
+        matches!(
+            self,
+            FileHealthStatus::Truncated | FileHealthStatus::NotImage
+        )
+    }
+}
+
+impl Type<Sqlite> for FileHealthStatus {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <i64 as Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for FileHealthStatus {
+    fn decode(value: <Sqlite as sqlx::Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        match <i64 as Decode<Sqlite>>::decode(value)? {
+            0 => Ok(FileHealthStatus::Ok),
+            1 => Ok(FileHealthStatus::Mislabeled),
+            2 => Ok(FileHealthStatus::Truncated),
+            3 => Ok(FileHealthStatus::NotImage),
+            v => Err(format!("Invalid file health status: {}", v).into()),
+        }
+    }
+}
+
+/// ファイルの検査結果
+#[derive(Serialize, Debug, Clone, TS, FromRow)]
+#[ts(export)]
+pub struct FileHealth {
+    pub save_dir: String,
+    pub file_name: String,
+    pub status: FileHealthStatus,
+    /// 中身から判定した形式の拡張子
+    pub detected_format: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, TS)]
+#[ts(export)]
+pub struct FileLocation {
+    pub save_dir: String,
+    pub file_name: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default, TS)]
+#[ts(export)]
+pub struct QuarantineStats {
+    pub moved: u32,
+    /// 退避フォルダへ移せず、登録も残したファイル
+    pub failed_files: Vec<String>,
+}
+
+/// MOVE_JOURNAL.state（移動予定 → ファイル移動済み → DB 反映済み）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveState {
+    Planned = 0,
+    Moved = 1,
+    Committed = 2,
+}
+
+impl Type<Sqlite> for MoveState {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <i64 as Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for MoveState {
+    fn decode(value: <Sqlite as sqlx::Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        match <i64 as Decode<Sqlite>>::decode(value)? {
+            0 => Ok(MoveState::Planned),
+            1 => Ok(MoveState::Moved),
+            2 => Ok(MoveState::Committed),
+            v => Err(format!("Invalid move state: {}", v).into()),
+        }
+    }
+}
+
+/// 移動できなかったファイルとその理由
+#[derive(Serialize, Debug, Clone, TS)]
+#[ts(export)]
+pub struct MoveFailure {
+    pub file_name: String,
+    pub src_dir: String,
+    pub dest_dir: String,
+    pub reason: String,
+}
+
+/// ファイル移動の進捗
+#[derive(Serialize, Debug, Clone, TS)]
+#[ts(export)]
+pub struct MoveProgress {
+    pub current: u32,
+    pub total: u32,
+    pub file_name: String,
+}
+
+/// ファイル移動の進捗通知先
+pub trait MoveProgressSink: Send + Sync {
+    fn report(&self, progress: &MoveProgress) -> Result<()>;
+}
+
+/// OPERATION_JOURNAL.kind
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    MoveFiles = 0,
+    LabelCharacter = 1,
+    EditTags = 2,
+    OverwriteTags = 3,
+    DeleteFiles = 4,
+}
+
+impl Type<Sqlite> for OperationKind {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <i64 as Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for OperationKind {
+    fn decode(value: <Sqlite as sqlx::Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        match <i64 as Decode<Sqlite>>::decode(value)? {
+            0 => Ok(OperationKind::MoveFiles),
+            1 => Ok(OperationKind::LabelCharacter),
+            2 => Ok(OperationKind::EditTags),
+            3 => Ok(OperationKind::OverwriteTags),
+            4 => Ok(OperationKind::DeleteFiles),
+            v => Err(format!("Invalid operation kind: {}", v).into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct OperationEntryRaw {
+    pub id: i64,
+    pub kind: OperationKind,
+    pub file_count: u32,
+    pub detail: Option<String>,
+    pub created_at: i64,
+    pub undone: bool,
+}
+
+/// 操作履歴の1件
+#[derive(Serialize, Debug, Clone, TS)]
+#[ts(export)]
+pub struct OperationEntry {
+    pub id: i64,
+    pub kind: OperationKind,
+    pub file_count: u32,
+    /// 移動先のフォルダ、付けたキャラクター名など
+    pub detail: Option<String>,
+    pub created_at: String,
+    /// 取り消し済み（やり直しの対象）
+    pub undone: bool,
+}
+
+impl From<OperationEntryRaw> for OperationEntry {
+    fn from(raw: OperationEntryRaw) -> Self {
+        OperationEntry {
+            id: raw.id,
+            kind: raw.kind,
+            file_count: raw.file_count,
+            detail: raw.detail,
+            created_at: format_unix_timestamp(raw.created_at),
+            undone: raw.undone,
+        }
+    }
+}
+
+/// 操作の前後で取っておく対象作品の登録内容とファイルの場所
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct JournalState {
+    pub illust_ids: Vec<i32>,
+    pub illusts: Vec<JournalIllust>,
+    pub details: Vec<JournalDetail>,
+    pub tags: Vec<JournalTag>,
+    pub ugoira: Vec<JournalUgoira>,
+    pub files: Vec<JournalFile>,
+    /// 名付けで変わる CHARACTER_INFO のキー
+    #[serde(default)]
+    pub entity_keys: Vec<String>,
+    #[serde(default)]
+    pub characters: Vec<JournalCharacter>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, FromRow)]
+pub struct JournalIllust {
+    pub illust_id: i32,
+    pub suffix: i32,
+    pub cnum: i32,
+    pub extension: String,
+    pub save_dir: Option<String>,
+    pub file_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, FromRow)]
+pub struct JournalDetail {
+    pub illust_id: i32,
+    pub cnum: i32,
+    pub author_id: i64,
+    pub series: Option<String>,
+    pub character: Option<String>,
+    pub created_at: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, FromRow)]
+pub struct JournalTag {
+    pub illust_id: i32,
+    pub cnum: i32,
+    pub tag: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, FromRow)]
+pub struct JournalUgoira {
+    pub illust_id: i32,
+    pub zip_name: String,
+    pub format: i64,
+    pub converted_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, FromRow)]
+pub struct JournalCharacter {
+    pub entity_key: String,
+    pub series: Option<String>,
+    pub character: Option<String>,
+    pub collect_dir: Option<String>,
+}
+
+/// ファイルの置き場所（削除したファイルは held_path に退避している）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JournalFile {
+    pub file_name: String,
+    pub save_dir: Option<String>,
+    pub held_path: Option<String>,
+}
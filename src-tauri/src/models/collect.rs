@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 use ts_rs::TS;
 
+use crate::models::catalog::MoveFailure;
+
 #[derive(Serialize, Deserialize, TS)]
 #[ts(export)]
 pub struct TagAssignment {
@@ -22,13 +24,38 @@ pub struct CollectSummary {
     pub unsave: bool,
 }
 
+/// 振り分けの結果
+#[derive(Serialize, Debug, Clone, TS)]
+#[ts(export)]
+pub struct CollectResult {
+    pub summary: Vec<CollectSummary>,
+    pub failed_moves: Vec<MoveFailure>,
+}
+
+/// ライブラリの移動結果（dry_run なら書き換える予定の件数）
+#[derive(Serialize, Deserialize, Debug, Clone, Default, TS)]
+#[ts(export)]
+pub struct RelocateStats {
+    pub files: u32,
+    pub collect_dirs: u32,
+    pub root_updated: bool,
+    /// 書き換え後の場所に見つからないファイル数
+    pub missing_files: u32,
+    /// 相対パスで保存し直すファイル数
+    pub relative_files: u32,
+    /// 書き換え後と同じ場所の登録が既にあり、書き換えなかった行数
+    pub conflicts: u32,
+}
+
 #[derive(Debug)]
 pub struct TempFile {
     pub illust_id: u32,
     pub suffix: u8,
     pub extension: String,
+    pub file_name: String,
     pub save_dir: String,
     pub path: String,
+    pub variant: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, TS, FromRow)]
@@ -39,11 +66,24 @@ pub struct FileSummary {
     pub path: String,
 }
 
-#[derive(FromRow)]
+/// DB 同期の結果
+#[derive(Serialize, Deserialize, Debug, TS)]
+#[ts(export)]
+pub struct SyncResult {
+    /// 登録済みだが見つからないファイル
+    pub missing_files: Vec<FileSummary>,
+    /// 同じ作品・ページでも内容が違うため削除しなかったファイル
+    pub kept_files: Vec<String>,
+}
+
+#[derive(Debug, Clone, FromRow)]
 pub struct MoveIllustFiles {
     pub illust_id: i64,
     pub suffix: i32,
     pub extension: String,
+    pub file_name: String,
+    /// 変換済みうごイラの元 zip
+    pub zip_name: Option<String>,
     pub src_dir: String,
     pub dest_dir: String,
 }
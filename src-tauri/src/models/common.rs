@@ -1,11 +1,14 @@
 use sqlx::SqlitePool;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::api::pixiv::PixivClientProvider;
+use crate::service::fetch_control::FetchControl;
 
 pub struct AppState {
     pub pool: SqlitePool,
     pub pixiv_client_provider: Arc<dyn PixivClientProvider>,
+    pub fetch_control: FetchControl,
 }
 
 impl Drop for AppState {
@@ -19,7 +22,23 @@ pub struct FileInfo {
     pub illust_id: i32,
     pub suffix: i16,
     pub extension: String,
+    pub file_name: String,
     pub save_dir: Option<String>,
+    /// id / page / ext 以外の名前付きキャプチャ
+    pub extras: HashMap<String, String>,
+}
+
+impl FileInfo {
+    /// 同じ作品・ページの別ファイル（縮小版など）を見分ける値（キャプチャが無ければ空）
+    pub fn variant(&self) -> String {
+        let mut extras: Vec<String> = self
+            .extras
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        extras.sort();
+        extras.join(",")
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1,7 +1,11 @@
-use serde::Serialize;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use sqlx::{error::BoxDynError, sqlite::Sqlite, Decode, Type};
 use ts_rs::TS;
 
+use crate::service::common::format_unix_timestamp;
+
 #[derive(Serialize, TS)]
 #[ts(export)]
 pub struct ProcessStats {
@@ -9,8 +13,12 @@ pub struct ProcessStats {
     pub successed_ids: u32,
     pub failed_ids: u32,
     pub duplicated_files: u32,
+    pub remaining_ids: u32,
+    pub cancelled: bool,
     pub process_time: String,
     pub failed_file_paths: Vec<String>,
+    /// 同じ作品・ページでも内容が違うため削除しなかったファイル
+    pub kept_files: Vec<String>,
 }
 
 #[derive(Debug, Clone, FromRow)]
@@ -19,8 +27,12 @@ pub struct FileDetail {
     pub suffix: u8,
     pub save_dir: String,
     pub extension: String,
+    pub file_name: String,
     pub created_time: i64,
     pub file_size: i64,
+    /// パターンの追加キャプチャ（同じ作品・ページのファイルが複数あれば空のものを登録する）
+    #[sqlx(default)]
+    pub variant: String,
 }
 
 #[derive(Serialize, Debug, Clone, TS)]
@@ -32,6 +44,13 @@ pub struct TagProgress {
     pub total: u32,
     pub elapsed_time: String,
     pub remaining_time: String,
+    pub current_delay_ms: u32,
+    pub state: FetchRunState,
+}
+
+/// フェッチの進捗通知先
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, progress: &TagProgress) -> Result<()>;
 }
 
 #[derive(Serialize, Debug, Clone, TS)]
@@ -47,14 +66,264 @@ pub struct FileCounts {
     pub folders: Vec<FolderCount>,
     pub total: i32,
     pub process_time: String,
+    /// 検査で問題が見つかったファイル数（壊れたものは取り込まない）
+    pub unhealthy: i32,
 }
 
 #[derive(Debug, FromRow)]
 pub struct DeleteFileRow {
-    pub file_path: String,
-    pub keep_file_path: Option<String>,
+    pub keep_save_dir: Option<String>,
+    pub keep_file_name: Option<String>,
     pub illust_id: i64,
     pub suffix: i64,
     pub extension: String,
     pub save_dir: String,
+    pub file_name: String,
+}
+
+/// 作品詳細APIのレスポンスのうち、ILLUST_META に保存する項目
+#[derive(Debug, Deserialize)]
+pub struct IllustDetailResponse {
+    pub illust: IllustMetadata,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IllustMetadata {
+    pub id: u32,
+    pub title: String,
+    /// illust / manga / ugoira
+    #[serde(default, rename = "type")]
+    pub illust_type: String,
+    #[serde(default)]
+    pub caption: String,
+    pub page_count: u32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub total_bookmarks: u32,
+    #[serde(default)]
+    pub total_view: u32,
+    #[serde(default)]
+    pub x_restrict: u8,
+    #[serde(default)]
+    pub illust_ai_type: u8,
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default)]
+    pub series: Option<IllustSeries>,
+    #[serde(default)]
+    pub tags: Vec<IllustTag>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IllustTag {
+    pub name: String,
+    #[serde(default)]
+    pub translated_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IllustSeries {
+    pub id: u32,
+    pub title: String,
+}
+
+/// うごイラのフレーム情報APIのレスポンス
+#[derive(Debug, Deserialize)]
+pub struct UgoiraMetadataResponse {
+    pub ugoira_metadata: UgoiraMetadata,
+}
+
+/// PixivUtil2 の zip に同梱される animation.json も同じ形
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UgoiraMetadata {
+    pub frames: Vec<UgoiraFrame>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UgoiraFrame {
+    pub file: String,
+    /// 表示時間（ミリ秒）
+    pub delay: u32,
+}
+
+/// FETCH_FAILURE.reason
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FetchFailureKind {
+    NotFound = 0,
+    Restricted = 1,
+    Auth = 2,
+    RateLimited = 3,
+    Transport = 4,
+    Parse = 5,
+}
+
+impl FetchFailureKind {
+    pub const TRANSIENT: [FetchFailureKind; 3] = [
+        FetchFailureKind::Auth,
+        FetchFailureKind::RateLimited,
+        FetchFailureKind::Transport,
+    ];
+
+    /// 時間をおけば成功する可能性がある失敗か
+    pub fn is_transient(self) -> bool {
+        Self::TRANSIENT.contains(&self)
+    }
+}
+
+/// FETCH_JOB.status
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FetchJobStatus {
+    Running = 0,
+    Completed = 1,
+    Abandoned = 2,
+}
+
+/// FETCH_JOB.kind
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FetchJobKind {
+    /// API からの取得
+    Api = 0,
+    /// ローカルのメタデータからの取り込み（API では再開しない）
+    Import = 1,
+}
+
+/// FETCH_JOB_ITEM.status
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FetchItemStatus {
+    Pending = 0,
+    Done = 1,
+    Failed = 2,
+    Skipped = 3,
+}
+
+/// REFRESH_CHANGE.kind
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshChangeKind {
+    TagAdded = 0,
+    TagRemoved = 1,
+    Title = 2,
+    AuthorName = 3,
+    Unavailable = 4,
+}
+
+impl Type<Sqlite> for RefreshChangeKind {
+    fn type_info() -> <Sqlite as sqlx::Database>::TypeInfo {
+        <i64 as Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for RefreshChangeKind {
+    fn decode(value: <Sqlite as sqlx::Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        match <i64 as Decode<Sqlite>>::decode(value)? {
+            0 => Ok(RefreshChangeKind::TagAdded),
+            1 => Ok(RefreshChangeKind::TagRemoved),
+            2 => Ok(RefreshChangeKind::Title),
+            3 => Ok(RefreshChangeKind::AuthorName),
+            4 => Ok(RefreshChangeKind::Unavailable),
+            v => Err(format!("Invalid refresh change kind: {}", v).into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct RefreshRunRaw {
+    pub run_id: i64,
+    pub stale_days: u32,
+    pub target_count: u32,
+    pub success_count: u32,
+    pub fail_count: u32,
+    pub change_count: u32,
+    pub cancelled: bool,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+/// 再取得の実行履歴
+#[derive(Serialize, Debug, Clone, TS)]
+#[ts(export)]
+pub struct RefreshRun {
+    pub run_id: i64,
+    pub stale_days: u32,
+    pub target_count: u32,
+    pub success_count: u32,
+    pub fail_count: u32,
+    pub change_count: u32,
+    pub cancelled: bool,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
+
+impl From<RefreshRunRaw> for RefreshRun {
+    fn from(raw: RefreshRunRaw) -> Self {
+        RefreshRun {
+            run_id: raw.run_id,
+            stale_days: raw.stale_days,
+            target_count: raw.target_count,
+            success_count: raw.success_count,
+            fail_count: raw.fail_count,
+            change_count: raw.change_count,
+            cancelled: raw.cancelled,
+            started_at: format_unix_timestamp(raw.started_at),
+            finished_at: raw.finished_at.map(format_unix_timestamp),
+        }
+    }
+}
+
+/// 再取得で検知した変更
+#[derive(Serialize, Debug, Clone, FromRow, TS)]
+#[ts(export)]
+pub struct RefreshChange {
+    pub illust_id: u32,
+    pub kind: RefreshChangeKind,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct FetchJobSummaryRaw {
+    pub job_id: i64,
+    pub pending: u32,
+    pub done: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    pub created_at: i64,
+}
+
+#[derive(Serialize, Debug, Clone, TS)]
+#[ts(export)]
+pub struct FetchJobSummary {
+    pub job_id: i64,
+    pub total: u32,
+    pub pending: u32,
+    pub done: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    pub created_at: String,
+}
+
+impl From<FetchJobSummaryRaw> for FetchJobSummary {
+    fn from(raw: FetchJobSummaryRaw) -> Self {
+        FetchJobSummary {
+            job_id: raw.job_id,
+            total: raw.pending + raw.done + raw.failed + raw.skipped,
+            pending: raw.pending,
+            done: raw.done,
+            failed: raw.failed,
+            skipped: raw.skipped,
+            created_at: format_unix_timestamp(raw.created_at),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchRunState {
+    Idle,
+    Running,
+    Paused,
+    Cancelled,
 }
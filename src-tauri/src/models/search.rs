@@ -2,16 +2,84 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use ts_rs::TS;
 
+use crate::service::common::format_unix_timestamp;
+
 #[derive(Debug, Serialize, Deserialize, Clone, TS, FromRow)]
 #[ts(export)]
 pub struct SearchResult {
     pub illust_id: u32,
     pub file_name: String,
+    /// save_dir と file_name から組み立てた画像のパス
+    #[sqlx(default)]
     pub thumbnail_url: String,
     pub author_name: String,
     pub character: Option<String>,
     pub save_dir: String,
     pub tags: Option<String>,
+    /// tags と同じ並びで、翻訳があれば翻訳名、無ければ原文
+    #[sqlx(default)]
+    pub translated_tags: Option<String>,
+    /// 画像検索のときだけ、検索画像とのハミング距離
+    #[sqlx(default)]
+    pub distance: Option<u32>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct IllustMetaRaw {
+    pub illust_id: u32,
+    pub title: String,
+    pub caption: String,
+    pub page_count: u32,
+    pub width: u32,
+    pub height: u32,
+    pub total_bookmarks: u32,
+    pub total_view: u32,
+    pub x_restrict: u8,
+    pub illust_ai_type: u8,
+    pub tools: String,
+    pub series_id: Option<u32>,
+    pub series_title: Option<String>,
+    pub fetched_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct IllustMeta {
+    pub illust_id: u32,
+    pub title: String,
+    pub caption: String,
+    pub page_count: u32,
+    pub width: u32,
+    pub height: u32,
+    pub total_bookmarks: u32,
+    pub total_view: u32,
+    pub x_restrict: u8,
+    pub illust_ai_type: u8,
+    pub tools: Vec<String>,
+    pub series_id: Option<u32>,
+    pub series_title: Option<String>,
+    pub fetched_at: String,
+}
+
+impl From<IllustMetaRaw> for IllustMeta {
+    fn from(raw: IllustMetaRaw) -> Self {
+        Self {
+            illust_id: raw.illust_id,
+            title: raw.title,
+            caption: raw.caption,
+            page_count: raw.page_count,
+            width: raw.width,
+            height: raw.height,
+            total_bookmarks: raw.total_bookmarks,
+            total_view: raw.total_view,
+            x_restrict: raw.x_restrict,
+            illust_ai_type: raw.illust_ai_type,
+            tools: serde_json::from_str(&raw.tools).unwrap_or_default(),
+            series_id: raw.series_id,
+            series_title: raw.series_title,
+            fetched_at: format_unix_timestamp(raw.fetched_at),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, TS, FromRow)]
@@ -19,6 +87,9 @@ pub struct SearchResult {
 pub struct TagInfo {
     pub tag: String,
     pub count: u32,
+    #[sqlx(default)]
+    #[ts(optional)]
+    pub translated_name: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, TS, FromRow)]
@@ -28,6 +99,39 @@ pub struct AuthorInfo {
     pub author_name: String,
     pub author_account: String,
     pub count: Option<u32>,
+    /// 以前の名前（カンマ区切り）
+    #[sqlx(default)]
+    #[ts(optional)]
+    pub former_names: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AuthorNameHistoryRaw {
+    pub author_name: String,
+    pub author_account: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+/// 作者名の変更履歴
+#[derive(Debug, Serialize, Clone, TS)]
+#[ts(export)]
+pub struct AuthorNameHistory {
+    pub author_name: String,
+    pub author_account: String,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+impl From<AuthorNameHistoryRaw> for AuthorNameHistory {
+    fn from(raw: AuthorNameHistoryRaw) -> Self {
+        AuthorNameHistory {
+            author_name: raw.author_name,
+            author_account: raw.author_account,
+            first_seen: format_unix_timestamp(raw.first_seen),
+            last_seen: format_unix_timestamp(raw.last_seen),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, TS, FromRow)]
@@ -1,11 +1,45 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use ts_rs::TS;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default, TS)]
 #[ts(export)]
 #[serde(rename_all = "UPPERCASE")]
 pub struct EnvConfig {
-    refresh_token: String,
     interval_mill_sec: String,
     db_name: String,
+    #[serde(default)]
+    fetch_concurrency: String,
+    #[serde(default)]
+    api_base_url: String,
+    #[serde(default)]
+    auth_token_url: String,
+    #[serde(default)]
+    proxy_url: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default, TS)]
+#[ts(export)]
+pub struct AuthStatus {
+    pub authorized: bool,
+    /// 保存済みの認証情報があるがパスフレーズ未入力
+    pub locked: bool,
+    pub user_name: Option<String>,
+    pub user_account: Option<String>,
+    pub expires_at: Option<String>,
+    pub last_refreshed_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+/// ファイル名パターンの試し解析結果
+#[derive(Serialize, Debug, Clone, TS)]
+#[ts(export)]
+pub struct FileNamePreview {
+    pub file_name: String,
+    pub illust_id: Option<i32>,
+    pub suffix: Option<i16>,
+    pub extension: Option<String>,
+    pub extras: HashMap<String, String>,
+    /// 一致するパターンが無いときの理由
+    pub error: Option<String>,
 }
@@ -1,32 +1,51 @@
 use anyhow::{anyhow, Result};
-use sqlx::{error::BoxDynError, sqlite::SqliteArguments, Arguments, SqlitePool};
+use chrono::Utc;
+use sqlx::{SqliteConnection, SqlitePool};
 
-use std::{collections::HashSet, fs, path::Path};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
 
 use crate::{
     execute_queries,
     models::{
-        catalog::{AssociateCharacter, AssociateInfo, AssociateSaveDir, EditTag},
+        catalog::{
+            AssociateCharacter, AssociateInfo, AssociateSaveDir, EditTag, JournalFile, MoveFailure,
+            MoveProgressSink,
+        },
+        collect::MoveIllustFiles,
         common::BindValue,
     },
     service::common::{
         execute_multi_insert_query, execute_named_queries, hash_params, parse_file_info,
         remove_invalid_chars,
     },
+    service::file_move::{move_file, process_file_moves},
+    service::journal::revert_moves,
+    service::library::load_library_paths,
     util::ResultWithLocationExt,
 };
 
+/// 選んだファイル（move_linked_files なら同じ cnum のファイルも）を target_folder へ移す
+///
+/// 移動できなかったファイルは理由と一緒に返す
 pub async fn process_move_files(
     pool: &SqlitePool,
     file_names: Vec<String>,
     target_folder: &str,
     move_linked_files: bool,
-) -> Result<(), BoxDynError> {
-    let mut tx = pool.begin().await.with_location()?;
+    sink: Option<&dyn MoveProgressSink>,
+) -> Result<Vec<MoveFailure>> {
     let mut updates = HashSet::new();
+    let mut conn = pool.acquire().await.with_location()?;
+    let library = load_library_paths(&mut conn).await?;
+    drop(conn);
     // target_folderがない場合、作成
-    if !Path::new(target_folder).exists() {
-        fs::create_dir_all(target_folder).with_location()?;
+    let target_dir = library.resolve_dir(target_folder);
+    if !target_dir.exists() {
+        fs::create_dir_all(&target_dir).with_location()?;
     }
 
     // 更新用のデータを作成
@@ -37,7 +56,7 @@ pub async fn process_move_files(
             sqlx::query_scalar("SELECT cnum FROM ILLUST_INFO WHERE illust_id = ? AND suffix = ?")
                 .bind(file_info.illust_id)
                 .bind(file_info.suffix)
-                .fetch_one(&mut *tx)
+                .fetch_one(pool)
                 .await
                 .with_location()?;
 
@@ -48,66 +67,34 @@ pub async fn process_move_files(
         }
     }
 
-    // ILLUST_INFOを更新
+    // 実体ファイル情報を取得
+    let mut moves = vec![];
     for (id, suffix_opt, cnum_opt) in updates {
-        let mut update_sql = String::from("UPDATE ILLUST_INFO SET save_dir = ?");
-        let mut update_arguments = SqliteArguments::default();
-        update_arguments.add(&target_folder)?;
-
-        if let Some(ref cnum) = cnum_opt {
-            update_sql.push_str(" WHERE illust_id = ? AND cnum = ?");
-            update_arguments.add(&id)?;
-            update_arguments.add(cnum)?;
-        } else {
-            // suffix指定
-            update_sql.push_str(" WHERE illust_id = ? AND suffix = ?");
-            update_arguments.add(&id)?;
-            update_arguments.add(&suffix_opt)?;
-        }
-
-        // 実体ファイル情報を取得
-        let mut select_sql = String::from(
-            "SELECT (illust_id || '_p' || suffix || '.' || extension) as file_name, save_dir FROM ILLUST_INFO WHERE illust_id = ? AND ",
-        );
-
-        let mut select_arguments = SqliteArguments::default();
-        select_arguments.add(&id)?;
-
-        if let Some(ref cnum) = cnum_opt {
-            select_sql.push_str("cnum = ?");
-            select_arguments.add(cnum)?;
-        } else if let Some(ref suffix) = suffix_opt {
-            select_sql.push_str("suffix = ?");
-            select_arguments.add(suffix)?;
-        } else {
-            return Err(anyhow!("Either suffix or cnum is required").into());
-        }
-
-        let file_names_to_update: Vec<(String, String)> =
-            sqlx::query_as_with(&select_sql, select_arguments)
-                .fetch_all(&mut *tx)
-                .await
-                .with_location()?;
-
-        // ファイルを移動
-        for (file_name, save_dir) in file_names_to_update {
-            let source_path = std::path::Path::new(&save_dir).join(&file_name);
-            let target_path = std::path::Path::new(target_folder).join(&file_name);
-            if source_path == target_path {
-                continue;
-            }
-            std::fs::rename(&source_path, &target_path).with_location()?;
-        }
-
-        // DBを更新
-        sqlx::query_with(&update_sql, update_arguments)
-            .execute(&mut *tx)
-            .await
-            .with_location()?;
+        let (condition, value) = match (suffix_opt, cnum_opt) {
+            (_, Some(cnum)) => ("I.cnum = ?", cnum),
+            (Some(suffix), None) => ("I.suffix = ?", suffix as i32),
+            (None, None) => return Err(anyhow!("Either suffix or cnum is required")),
+        };
+
+        let rows: Vec<MoveIllustFiles> = sqlx::query_as(&format!(
+            "SELECT I.illust_id, I.suffix, I.extension, I.file_name, U.zip_name,
+                    I.save_dir AS src_dir, ? AS dest_dir
+             FROM ILLUST_INFO I
+             LEFT JOIN UGOIRA_INFO U ON U.illust_id = I.illust_id AND I.suffix = 0
+             WHERE I.illust_id = ? AND {condition}
+               AND I.save_dir IS NOT NULL AND I.file_name IS NOT NULL"
+        ))
+        .bind(target_folder)
+        .bind(id)
+        .bind(value)
+        .fetch_all(pool)
+        .await
+        .with_location()?;
+        moves.extend(rows);
     }
-    tx.commit().await.with_location()?;
 
-    Ok(())
+    // ファイルを移してから DB を更新
+    process_file_moves(pool, moves, sink).await
 }
 
 pub async fn process_label_character_name(
@@ -236,6 +223,96 @@ pub async fn process_edit_tags(
     Ok(())
 }
 
+/// ファイルを退避フォルダへ移して登録から外す
+///
+/// 元に戻せるようにゴミ箱ではなく退避フォルダへ移し、退避先を返す。
+/// 途中で失敗したら退避したファイルを元の場所へ戻す（登録はロールバックされる）
+pub async fn process_delete_files(
+    pool: &SqlitePool,
+    file_names: Vec<String>,
+    hold_dir: &Path,
+) -> Result<Vec<JournalFile>> {
+    fs::create_dir_all(hold_dir).with_location()?;
+
+    let mut moved = vec![];
+    let result = async {
+        let mut tx = pool.begin().await.with_location()?;
+        let held_files =
+            delete_file_registrations(&mut tx, file_names, hold_dir, &mut moved).await?;
+        tx.commit().await.with_location()?;
+        Ok(held_files)
+    }
+    .await;
+
+    if result.is_err() {
+        revert_moves(&moved);
+    }
+    result
+}
+
+async fn delete_file_registrations(
+    conn: &mut SqliteConnection,
+    file_names: Vec<String>,
+    hold_dir: &Path,
+    moved: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<Vec<JournalFile>> {
+    let mut held_files = vec![];
+    let library = load_library_paths(&mut *conn).await?;
+
+    for file_name in file_names {
+        let file_info = parse_file_info(file_name.as_str()).with_location()?;
+
+        // 1. save_dir, cnum, うごイラの元 zip を取得
+        let (save_dir, cnum, zip_name): (String, i32, Option<String>) = sqlx::query_as(
+            "SELECT I.save_dir, I.cnum, U.zip_name FROM ILLUST_INFO I
+             LEFT JOIN UGOIRA_INFO U ON U.illust_id = I.illust_id AND I.suffix = 0
+             WHERE I.illust_id = ? AND I.suffix = ?",
+        )
+        .bind(file_info.illust_id)
+        .bind(file_info.suffix)
+        .fetch_one(&mut *conn)
+        .await
+        .with_location()?;
+
+        // 2. ファイルを退避
+        let mut names = vec![file_name.clone()];
+        if let Some(zip_name) = zip_name {
+            if library.file_path(&save_dir, &zip_name).exists() {
+                names.push(zip_name);
+            }
+        }
+        for name in names {
+            let src = library.file_path(&save_dir, &name);
+            let held_path = hold_dir.join(format!("{}_{}", Utc::now().timestamp_millis(), name));
+            // 退避フォルダは別ドライブのことがある
+            move_file(&src, &held_path)?;
+            moved.push((src, held_path.clone()));
+            held_files.push(JournalFile {
+                file_name: name,
+                save_dir: None,
+                held_path: Some(held_path.to_string_lossy().to_string()),
+            });
+        }
+
+        // 3. ILLUST_INFO の削除と TAG_INFO と ILLUST_DETAIL の後処理
+        let delete_sql = include_str!("../sql/catalog/delete_file_registration.sql");
+        execute_named_queries(
+            &mut *conn,
+            delete_sql,
+            &hash_params(&vec![
+                (":illust_id", file_info.illust_id.into()),
+                (":suffix", file_info.suffix.into()),
+                (":cnum", cnum.into()),
+            ])
+            .with_location()?,
+        )
+        .await
+        .with_location()?;
+    }
+
+    Ok(held_files)
+}
+
 pub async fn process_get_associated_info(
     pool: &SqlitePool,
     file_names: Vec<String>,
@@ -274,12 +351,22 @@ pub async fn process_get_associated_info(
         .with_location()?;
 
     // save_dir 集計
+    let library = load_library_paths(&mut tx).await?;
     let sql =
         "SELECT save_dir, COUNT(DISTINCT key) AS count FROM tmp_associated_files GROUP BY save_dir";
     let save_dirs: Vec<AssociateSaveDir> = sqlx::query_as(sql)
         .fetch_all(&mut *tx)
         .await
-        .with_location()?;
+        .with_location()?
+        .into_iter()
+        .map(|mut dir: AssociateSaveDir| {
+            dir.save_dir = library
+                .resolve_dir(&dir.save_dir)
+                .to_string_lossy()
+                .to_string();
+            dir
+        })
+        .collect();
 
     tx.commit().await.with_location()?;
 
@@ -1,10 +1,7 @@
 use anyhow::Result;
-use regex::Regex;
 use sqlx::Acquire;
 use sqlx::SqliteConnection;
 use sqlx::SqlitePool;
-use std::fs;
-use std::path::Path;
 use std::path::PathBuf;
 use walkdir::WalkDir;
 
@@ -12,10 +9,35 @@ use crate::constants;
 use crate::execute_queries;
 use crate::models::collect::*;
 use crate::service::common::hash_params;
-use crate::service::common::{execute_named_queries, update_cnum};
-use crate::util::log_error;
+use crate::service::common::{execute_named_queries, normalize_dir, update_cnum};
+use crate::service::duplicate::is_same_content;
+use crate::service::file_pattern::current_patterns;
+use crate::service::library::{load_library_paths, LibraryPaths};
 use crate::util::ResultWithLocationExt;
 
+/// 振り分け先フォルダ（root/シリーズ/キャラクター）
+pub fn collect_dir(
+    library: &LibraryPaths,
+    root: &str,
+    series: Option<&str>,
+    character: Option<&str>,
+) -> String {
+    let mut path = PathBuf::from(normalize_dir(root));
+    path.extend(series);
+    path.extend(character);
+    library.to_stored_dir(&path.to_string_lossy())
+}
+
+pub async fn get_collect_root(conn: &mut SqliteConnection) -> Result<Option<String>> {
+    let root = sqlx::query_scalar("SELECT value FROM COMMON_MST WHERE key = ?")
+        .bind(constants::COLLECT_ROOT)
+        .fetch_optional(&mut *conn)
+        .await
+        .with_location()?;
+
+    Ok(root)
+}
+
 pub async fn prepare_collect_ui_work(conn: &mut SqliteConnection) -> Result<()> {
     let sql = include_str!("../sql/collect/prepare_collect_ui_work.sql");
     execute_named_queries(
@@ -26,6 +48,34 @@ pub async fn prepare_collect_ui_work(conn: &mut SqliteConnection) -> Result<()>
     .await
     .with_location()?;
 
+    // 振り分け先のパスは OS ごとの区切りで Rust 側で組み立てる
+    if let Some(root) = get_collect_root(&mut *conn).await? {
+        let library = load_library_paths(&mut *conn).await?;
+        let rows: Vec<(i64, Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT id, series, character FROM COLLECT_UI_WORK WHERE collect_type IN (1, 2)",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .with_location()?;
+
+        for (id, series, character) in rows {
+            sqlx::query("UPDATE COLLECT_UI_WORK SET collect_dir = ? WHERE id = ?")
+                .bind(collect_dir(
+                    &library,
+                    &root,
+                    series.as_deref(),
+                    character.as_deref(),
+                ))
+                .bind(id)
+                .execute(&mut *conn)
+                .await
+                .with_location()?;
+        }
+    }
+
+    let sql = include_str!("../sql/collect/count_collect_ui_work.sql");
+    execute_queries(&mut *conn, sql).await.with_location()?;
+
     Ok(())
 }
 
@@ -35,14 +85,19 @@ pub async fn reflesh_collect_work(conn: &mut SqliteConnection) -> Result<()> {
         .await
         .with_location()?;
 
+    let library = load_library_paths(&mut *conn).await?;
+    let uncategorized_dir = get_collect_root(&mut *conn)
+        .await?
+        .map(|root| collect_dir(&library, &root, Some(constants::UNCATEGORIZED_DIR), None));
+
     let sql = include_str!("../sql/collect/insert_collect_filter_work_character.sql");
     execute_named_queries(
         &mut *conn,
         sql,
-        &hash_params(&vec![
-            (":uncategorized_dir", constants::UNCATEGORIZED_DIR.into()),
-            (":collect_root", constants::COLLECT_ROOT.into()),
-        ])
+        &hash_params(&vec![(
+            ":uncategorized_dir",
+            uncategorized_dir.clone().into(),
+        )])
         .with_location()?,
     )
     .await
@@ -52,11 +107,7 @@ pub async fn reflesh_collect_work(conn: &mut SqliteConnection) -> Result<()> {
     execute_named_queries(
         &mut *conn,
         sql,
-        &hash_params(&vec![
-            (":uncategorized_dir", constants::UNCATEGORIZED_DIR.into()),
-            (":collect_root", constants::COLLECT_ROOT.into()),
-        ])
-        .with_location()?,
+        &hash_params(&vec![(":uncategorized_dir", uncategorized_dir.into())]).with_location()?,
     )
     .await
     .with_location()?;
@@ -78,11 +129,21 @@ pub async fn sort_collect_work(conn: &mut SqliteConnection) -> Result<()> {
 }
 
 pub async fn get_collect_summary(pool: &SqlitePool) -> Result<Vec<CollectSummary>> {
+    let mut conn = pool.acquire().await.with_location()?;
+    let library = load_library_paths(&mut conn).await?;
     let sql = include_str!("../sql/collect/get_collect_summary.sql");
-    let results = sqlx::query_as::<_, CollectSummary>(sql)
-        .fetch_all(pool)
+    let results: Vec<CollectSummary> = sqlx::query_as::<_, CollectSummary>(sql)
+        .fetch_all(&mut *conn)
         .await
-        .with_location()?;
+        .with_location()?
+        .into_iter()
+        .map(|mut summary| {
+            summary.collect_dir = summary
+                .collect_dir
+                .map(|dir| library.resolve_dir(&dir).to_string_lossy().to_string());
+            summary
+        })
+        .collect();
 
     Ok(results)
 }
@@ -107,6 +168,7 @@ pub async fn collect_illust_detail(conn: &mut SqliteConnection) -> Result<()> {
     Ok(())
 }
 
+/// 振り分け先へ移すファイル（移動と save_dir の更新は process_file_moves で行う）
 pub async fn mark_illust_move_targets(conn: &mut SqliteConnection) -> Result<Vec<MoveIllustFiles>> {
     let sql = include_str!("../sql/collect/prepare_tmp_move_candidates.sql");
     sqlx::query(sql).execute(&mut *conn).await.with_location()?;
@@ -116,86 +178,19 @@ pub async fn mark_illust_move_targets(conn: &mut SqliteConnection) -> Result<Vec
         .await
         .with_location()?;
 
-    // ファイルチェック
-    let mut ng_keys = Vec::new();
-    for row in &rows {
-        let filename = format!("{}_p{}.{}", row.illust_id, row.suffix, row.extension);
-        let src_path = Path::new(&row.src_dir).join(&filename);
-        let dest_path = Path::new(&row.dest_dir).join(&filename);
-
-        if !src_path.exists() {
-            log_error(format!("移動元にファイルが存在しません: {:?}", src_path));
-            ng_keys.push((row.illust_id, row.suffix));
-        } else if dest_path.exists() {
-            log_error(format!("移動先にファイルが存在します: {:?}", src_path));
-            ng_keys.push((row.illust_id, row.suffix));
-        }
-    }
-
-    // NGを削除
-    for (illust_id, suffix) in ng_keys {
-        sqlx::query("DELETE FROM tmp_move_candidates WHERE illust_id = ? AND suffix = ?")
-            .bind(illust_id)
-            .bind(suffix)
-            .execute(&mut *conn)
-            .await
-            .with_location()?;
-    }
-
-    // 一括UPDATE
-    let sql = include_str!("../sql/collect/update_from_move_candidates.sql");
-    sqlx::query(sql).execute(&mut *conn).await.with_location()?;
-
-    // 残ったOKを返す
-    let ok_rows: Vec<MoveIllustFiles> = sqlx::query_as("SELECT * FROM tmp_move_candidates")
-        .fetch_all(&mut *conn)
-        .await
-        .with_location()?;
-
-    Ok(ok_rows)
+    Ok(rows)
 }
 
-pub fn apply_file_moves(rows: Vec<MoveIllustFiles>) {
-    for row in rows {
-        let MoveIllustFiles {
-            illust_id,
-            suffix,
-            extension,
-            src_dir,
-            dest_dir,
-        } = row;
-
-        let filename = format!("{}_p{}.{}", illust_id, suffix, extension);
-        let src_path = Path::new(&src_dir).join(&filename);
-        let dest_path = Path::new(&dest_dir).join(&filename);
-
-        if let Some(parent) = dest_path.parent() {
-            if let Err(e) = fs::create_dir_all(parent) {
-                log_error(format!("ディレクトリ作成失敗: {:?} | {}", parent, e));
-                continue;
-            }
-        }
-
-        match fs::rename(&src_path, &dest_path) {
-            Ok(_) => {}
-            Err(e) => {
-                log_error(format!(
-                    "ファイル移動失敗: {:?} → {:?} | {}",
-                    src_path, dest_path, e
-                ));
-            }
-        }
-    }
-}
-
-pub async fn process_sync_db(root: String, pool: &SqlitePool) -> Result<Vec<FileSummary>> {
+pub async fn process_sync_db(root: String, pool: &SqlitePool) -> Result<SyncResult> {
     let mut tx = pool.begin().await.with_location()?;
     let conn = tx.acquire().await.with_location()?;
 
     let missing_files;
+    let mut kept_files = Vec::new();
+    let library = load_library_paths(&mut *conn).await?;
 
     {
-        let reg = Regex::new(r"^(\d+)_p(\d+)\.(jpg|png|jpeg)$").with_location()?;
+        let patterns = current_patterns();
         let mut paths_to_insert = Vec::new();
 
         // root以下の解析
@@ -204,19 +199,22 @@ pub async fn process_sync_db(root: String, pool: &SqlitePool) -> Result<Vec<File
             .filter_map(|e| e.ok())
             .filter(|e| e.file_type().is_file())
         {
-            if let Some(caps) = reg.captures(&entry.file_name().to_string_lossy()) {
+            if let Ok(info) = patterns.parse(&entry.file_name().to_string_lossy()) {
                 let path = entry.path();
                 let save_dir = path
                     .parent()
-                    .map(|p| p.to_string_lossy().to_string())
+                    .map(|p| library.to_stored_dir(&p.to_string_lossy()))
                     .unwrap_or_default();
 
+                let variant = info.variant();
                 let temp_file = TempFile {
-                    illust_id: caps[1].parse().with_location()?,
-                    suffix: caps[2].parse().with_location()?,
-                    extension: caps[3].to_string(),
+                    illust_id: info.illust_id as u32,
+                    suffix: info.suffix as u8,
+                    extension: info.extension,
+                    file_name: info.file_name,
                     save_dir,
                     path: path.to_string_lossy().to_string(),
+                    variant,
                 };
                 paths_to_insert.push(temp_file);
             }
@@ -228,16 +226,19 @@ pub async fn process_sync_db(root: String, pool: &SqlitePool) -> Result<Vec<File
             .await
             .with_location()?;
 
-        let sql = "INSERT INTO SYNC_DB_WORK (illust_id, suffix, extension, save_dir, path)
-         VALUES (?, ?, ?, ?, ?)";
+        let sql =
+            "INSERT INTO SYNC_DB_WORK (illust_id, suffix, extension, file_name, save_dir, path, variant)
+         VALUES (?, ?, ?, ?, ?, ?, ?)";
 
         for file in paths_to_insert {
             sqlx::query(sql)
                 .bind(file.illust_id)
                 .bind(file.suffix)
                 .bind(file.extension)
+                .bind(file.file_name)
                 .bind(file.save_dir)
                 .bind(file.path)
+                .bind(file.variant)
                 .execute(&mut *conn)
                 .await
                 .with_location()?;
@@ -247,13 +248,22 @@ pub async fn process_sync_db(root: String, pool: &SqlitePool) -> Result<Vec<File
         let sql = include_str!("../sql/collect/process_sync_db.sql");
         execute_queries(&mut *conn, sql).await.with_location()?;
 
-        // 重複ファイルをゴミ箱に
-        let rows: Vec<String> = sqlx::query_scalar("SELECT path FROM tmp_to_trash")
-            .fetch_all(&mut *tx)
-            .await
-            .with_location()?;
+        // 内容が同じ重複ファイルだけゴミ箱に（違えば再投稿として残す）
+        let rows: Vec<(String, String, String, String, String)> = sqlx::query_as(
+            "SELECT path, save_dir, file_name, keep_save_dir, keep_file_name FROM tmp_to_trash",
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .with_location()?;
+
+        for (p, save_dir, file_name, keep_save_dir, keep_file_name) in rows {
+            let target = (save_dir, file_name);
+            let keep = (keep_save_dir, keep_file_name);
+            if !is_same_content(&mut *tx, &keep, &target).await? {
+                kept_files.push(p);
+                continue;
+            }
 
-        for p in rows {
             let path = PathBuf::from(p);
             if path.exists() {
                 trash::delete(&path).with_location()?;
@@ -261,12 +271,23 @@ pub async fn process_sync_db(root: String, pool: &SqlitePool) -> Result<Vec<File
         }
 
         // 結果を返却
-        let sql = "SELECT illust_id, suffix, path FROM tmp_missing_files";
+        let rows: Vec<(u32, u8, Option<String>, String)> =
+            sqlx::query_as("SELECT illust_id, suffix, save_dir, file_name FROM tmp_missing_files")
+                .fetch_all(&mut *tx)
+                .await
+                .with_location()?;
 
-        missing_files = sqlx::query_as::<_, FileSummary>(sql)
-            .fetch_all(&mut *tx)
-            .await
-            .with_location()?;
+        missing_files = rows
+            .into_iter()
+            .map(|(illust_id, suffix, save_dir, file_name)| FileSummary {
+                illust_id,
+                suffix,
+                path: library
+                    .file_path(&save_dir.unwrap_or_default(), &file_name)
+                    .to_string_lossy()
+                    .to_string(),
+            })
+            .collect();
 
         // 管理番号を更新
         update_cnum(&mut *tx).await.with_location()?;
@@ -274,5 +295,8 @@ pub async fn process_sync_db(root: String, pool: &SqlitePool) -> Result<Vec<File
 
     tx.commit().await.with_location()?;
 
-    Ok(missing_files)
+    Ok(SyncResult {
+        missing_files,
+        kept_files,
+    })
 }
@@ -1,11 +1,18 @@
 use chrono::{DateTime, FixedOffset, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use regex::Regex;
 use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
-use std::{collections::HashMap, path::Path};
 
 use crate::errors::{db::ParameterError, parse::FileParseError};
 use crate::models::common::{BindValue, FileInfo};
+use crate::service::file_pattern::current_patterns;
+use crate::service::library::LibraryPaths;
 
 pub fn format_duration(ms: u64) -> String {
     let duration = Duration::from_millis(ms);
@@ -30,6 +37,18 @@ pub fn format_unix_timestamp(ts: i64) -> String {
     }
 }
 
+pub fn compress_text(text: &str) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(text.as_bytes())?;
+    encoder.finish()
+}
+
+pub fn decompress_text(bytes: &[u8]) -> std::io::Result<String> {
+    let mut text = String::new();
+    GzDecoder::new(bytes).read_to_string(&mut text)?;
+    Ok(text)
+}
+
 pub fn remove_invalid_chars(path: &str) -> String {
     // Windowsでファイル名に使えない文字のリスト
     let invalid_chars = ['\\', '/', ':', '*', '?', '"', '<', '>', '|', ' '];
@@ -39,7 +58,15 @@ pub fn remove_invalid_chars(path: &str) -> String {
         .collect()
 }
 
-pub fn parse_path_info(path: &Path) -> Result<FileInfo, FileParseError> {
+/// 保存先フォルダを OS の区切り文字にそろえ、重複や末尾の区切りを除いた形にする
+///
+/// DB の save_dir はこの形で保存し、文字列のまま比較できるようにする
+pub fn normalize_dir(dir: &str) -> String {
+    let path: PathBuf = Path::new(dir).components().collect();
+    path.to_string_lossy().to_string()
+}
+
+pub fn parse_path_info(path: &Path, library: &LibraryPaths) -> Result<FileInfo, FileParseError> {
     let filename = path
         .file_name()
         .and_then(|f| f.to_str())
@@ -50,43 +77,68 @@ pub fn parse_path_info(path: &Path) -> Result<FileInfo, FileParseError> {
     let save_dir = path
         .parent()
         .and_then(|p| p.to_str())
-        .ok_or_else(|| FileParseError::ParentDirNotFound(format!("{:?}", path)))?
-        .to_string();
+        .ok_or_else(|| FileParseError::ParentDirNotFound(format!("{:?}", path)))?;
 
-    file_info.save_dir = Some(save_dir);
+    file_info.save_dir = Some(library.to_stored_dir(save_dir));
 
     Ok(file_info)
 }
 
+/// 設定済みのファイル名パターンで解析する
 pub fn parse_file_info(file_name: &str) -> Result<FileInfo, FileParseError> {
-    let reg = Regex::new(r"^(\d+)_p(\d+)\.(jpg|png|jpeg)$")
-        .map_err(|e| FileParseError::RegexCompileError(e.to_string()))?;
+    current_patterns().parse(file_name)
+}
 
-    let caps = reg
-        .captures(file_name)
-        .ok_or_else(|| FileParseError::InvalidFormat(file_name.to_string()))?;
+pub async fn update_cnum(conn: &mut SqliteConnection) -> sqlx::Result<()> {
+    let sql = include_str!("../sql/update_cnum.sql");
+    execute_queries(&mut *conn, sql).await?;
 
-    let illust_id = caps[1]
-        .parse::<i32>()
-        .map_err(|_| FileParseError::InvalidIllustId(caps[1].to_string()))?;
+    Ok(())
+}
 
-    let suffix = caps[2]
-        .parse::<i16>()
-        .map_err(|_| FileParseError::InvalidSuffix(caps[2].to_string()))?;
+/// 既存DBに後から追加した列を足す（SQLite の ALTER TABLE には IF NOT EXISTS が無いため）
+pub async fn add_column_if_missing(
+    conn: &mut SqliteConnection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> sqlx::Result<()> {
+    let exists: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pragma_table_info(?) WHERE name = ?)")
+            .bind(table)
+            .bind(column)
+            .fetch_one(&mut *conn)
+            .await?;
 
-    let extension = caps[3].to_string();
+    if !exists {
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(&mut *conn)
+        .await?;
+    }
 
-    Ok(FileInfo {
-        illust_id,
-        suffix,
-        extension,
-        save_dir: None,
-    })
+    Ok(())
 }
 
-pub async fn update_cnum(conn: &mut SqliteConnection) -> sqlx::Result<()> {
-    let sql = include_str!("../sql/update_cnum.sql");
-    execute_queries(&mut *conn, sql).await?;
+/// 区切り文字が混在する旧データの保存先をそろえる
+pub async fn normalize_saved_dirs(conn: &mut SqliteConnection) -> sqlx::Result<()> {
+    let dirs: Vec<String> =
+        sqlx::query_scalar("SELECT DISTINCT save_dir FROM ILLUST_INFO WHERE save_dir IS NOT NULL")
+            .fetch_all(&mut *conn)
+            .await?;
+
+    for dir in dirs {
+        let normalized = normalize_dir(&dir);
+        if normalized != dir {
+            sqlx::query("UPDATE ILLUST_INFO SET save_dir = ? WHERE save_dir = ?")
+                .bind(&normalized)
+                .bind(&dir)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
 
     Ok(())
 }
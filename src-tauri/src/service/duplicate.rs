@@ -0,0 +1,348 @@
+use anyhow::{bail, Result};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::models::catalog::{DuplicateFile, DuplicateGroup, DuplicateResolveStats};
+use crate::service::common::{build_named_query, execute_named_queries, hash_params};
+use crate::service::library::{load_library_paths, LibraryPaths};
+use crate::util::{log_error, ResultWithLocationExt};
+
+/// 保存先フォルダとファイル名の組（FILE_HASH のキー）
+pub type FileKey = (String, String);
+
+#[derive(sqlx::FromRow)]
+struct DuplicateRow {
+    hash: String,
+    file_size: i64,
+    #[sqlx(flatten)]
+    file: DuplicateFile,
+}
+
+/// ファイル内容の SHA-256（16進表記）
+pub fn hash_file(path: &Path) -> Result<String> {
+    let mut reader = BufReader::new(File::open(path).with_location()?);
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf).with_location()?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// キャッシュの有効判定に使うサイズと更新日時（ミリ秒）
+pub(crate) fn file_stamp(path: &Path) -> Option<(i64, i64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_millis() as i64;
+    Some((metadata.len() as i64, modified))
+}
+
+/// 指定ファイルのハッシュを返す
+///
+/// サイズと更新日時が前回と同じならキャッシュを使い、変わったファイルだけ読み直す。
+/// 存在しないファイルは結果に含めない
+pub async fn ensure_file_hashes(
+    conn: &mut SqliteConnection,
+    files: &[FileKey],
+) -> Result<HashMap<FileKey, String>> {
+    let library = load_library_paths(&mut *conn).await?;
+    let mut hashes = HashMap::new();
+    let mut stale: Vec<(FileKey, PathBuf, i64, i64)> = Vec::new();
+
+    for (save_dir, file_name) in files {
+        let path = library.file_path(save_dir, file_name);
+        let Some((file_size, modified_at)) = file_stamp(&path) else {
+            sqlx::query("DELETE FROM FILE_HASH WHERE save_dir = ? AND file_name = ?")
+                .bind(save_dir)
+                .bind(file_name)
+                .execute(&mut *conn)
+                .await
+                .with_location()?;
+            continue;
+        };
+
+        let cached: Option<String> = sqlx::query_scalar(
+            "SELECT hash FROM FILE_HASH
+              WHERE save_dir = ? AND file_name = ? AND file_size = ? AND modified_at = ?",
+        )
+        .bind(save_dir)
+        .bind(file_name)
+        .bind(file_size)
+        .bind(modified_at)
+        .fetch_optional(&mut *conn)
+        .await
+        .with_location()?;
+
+        let key = (save_dir.clone(), file_name.clone());
+        match cached {
+            Some(hash) => {
+                hashes.insert(key, hash);
+            }
+            None => stale.push((key, path, file_size, modified_at)),
+        }
+    }
+
+    // 読み直しは重いのでブロッキング用のスレッドで並列に行う
+    let computed = tauri::async_runtime::spawn_blocking(move || {
+        stale
+            .into_par_iter()
+            .map(|(key, path, file_size, modified_at)| {
+                let hash = hash_file(&path);
+                (key, file_size, modified_at, hash)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .with_location()?;
+
+    for (key, file_size, modified_at, hash) in computed {
+        let hash = match hash {
+            Ok(hash) => hash,
+            Err(e) => {
+                log_error(format!("ハッシュ計算失敗: {}/{} | {}", key.0, key.1, e));
+                continue;
+            }
+        };
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO FILE_HASH (save_dir, file_name, file_size, modified_at, hash)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&key.0)
+        .bind(&key.1)
+        .bind(file_size)
+        .bind(modified_at)
+        .bind(&hash)
+        .execute(&mut *conn)
+        .await
+        .with_location()?;
+
+        hashes.insert(key, hash);
+    }
+
+    Ok(hashes)
+}
+
+/// 2つのファイルの内容が同じか（どちらかが読めなければ false）
+pub async fn is_same_content(
+    conn: &mut SqliteConnection,
+    a: &FileKey,
+    b: &FileKey,
+) -> Result<bool> {
+    let hashes = ensure_file_hashes(&mut *conn, &[a.clone(), b.clone()]).await?;
+    Ok(match (hashes.get(a), hashes.get(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    })
+}
+
+async fn get_duplicate_rows(conn: &mut SqliteConnection, hash: &str) -> Result<Vec<DuplicateRow>> {
+    let sql = include_str!("../sql/catalog/get_duplicate_files.sql");
+    let rows = build_named_query(
+        sql,
+        &hash_params(&vec![(":hash", hash.to_string().into())]).with_location()?,
+    )
+    .with_location()?
+    .build_query_as::<DuplicateRow>()
+    .fetch_all(&mut *conn)
+    .await
+    .with_location()?;
+
+    Ok(rows)
+}
+
+/// 登録済みファイルのうち、名前が違っても内容が同じものをまとめて返す
+pub async fn process_get_duplicate_report(pool: &SqlitePool) -> Result<Vec<DuplicateGroup>> {
+    let mut conn = pool.acquire().await.with_location()?;
+
+    let files: Vec<FileKey> =
+        sqlx::query_as("SELECT save_dir, file_name FROM ILLUST_INFO WHERE save_dir IS NOT NULL")
+            .fetch_all(&mut *conn)
+            .await
+            .with_location()?;
+    ensure_file_hashes(&mut conn, &files).await?;
+
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    for row in get_duplicate_rows(&mut conn, "").await? {
+        match groups.last_mut() {
+            Some(group) if group.hash == row.hash => group.files.push(row.file),
+            _ => groups.push(DuplicateGroup {
+                hash: row.hash,
+                file_size: row.file_size,
+                files: vec![row.file],
+            }),
+        }
+    }
+
+    Ok(groups)
+}
+
+/// 重複グループのうち keep だけを残し、他のファイルの登録を外してゴミ箱へ移す
+///
+/// レポート作成後に内容が変わったファイルは消さずに残す。
+/// ゴミ箱へは登録の解除を commit してから移す
+pub async fn process_resolve_duplicates(
+    pool: &SqlitePool,
+    hash: String,
+    keep: DuplicateFile,
+) -> Result<DuplicateResolveStats> {
+    let mut tx = pool.begin().await.with_location()?;
+
+    let group = get_duplicate_rows(&mut tx, &hash).await?;
+    if !group.iter().any(|row| row.file == keep) {
+        bail!("残すファイルが重複グループにありません: {}", keep.file_name);
+    }
+
+    let keys: Vec<FileKey> = group
+        .iter()
+        .map(|row| (row.file.save_dir.clone(), row.file.file_name.clone()))
+        .collect();
+    let current = ensure_file_hashes(&mut tx, &keys).await?;
+    if current.get(&(keep.save_dir.clone(), keep.file_name.clone())) != Some(&hash) {
+        bail!("残すファイルの内容が変わっています: {}", keep.file_name);
+    }
+
+    let mut stats = DuplicateResolveStats::default();
+    let mut removed = Vec::new();
+    for DuplicateRow { file, .. } in group {
+        if file == keep {
+            continue;
+        }
+
+        let key = (file.save_dir.clone(), file.file_name.clone());
+        if current.get(&key) != Some(&hash) {
+            stats.skipped_files.push(file.file_name);
+            continue;
+        }
+
+        forget_registered_file(&mut tx, &file).await?;
+        removed.push(file);
+    }
+
+    let library = load_library_paths(&mut tx).await?;
+    tx.commit().await.with_location()?;
+
+    trash_files(removed, &library, &mut stats);
+
+    Ok(stats)
+}
+
+/// 登録を外してハッシュのキャッシュも消す（ファイルは commit 後に trash_files で消す）
+pub(crate) async fn forget_registered_file(
+    conn: &mut SqliteConnection,
+    file: &DuplicateFile,
+) -> Result<()> {
+    unregister_file(&mut *conn, file.illust_id, file.suffix).await?;
+    forget_file_caches(&mut *conn, &file.save_dir, &file.file_name).await?;
+
+    Ok(())
+}
+
+/// ファイルをゴミ箱へ移す。移せなかったファイルは skipped_files に載せて続ける
+pub(crate) fn trash_files(
+    files: Vec<DuplicateFile>,
+    library: &LibraryPaths,
+    stats: &mut DuplicateResolveStats,
+) {
+    for file in files {
+        match trash::delete(library.file_path(&file.save_dir, &file.file_name)) {
+            Ok(()) => stats.removed += 1,
+            Err(e) => {
+                log_error(format!("ゴミ箱への移動失敗: {} | {}", file.file_name, e));
+                stats.skipped_files.push(file.file_name);
+            }
+        }
+    }
+}
+
+/// ILLUST_INFO の登録を外し、残りが無ければタグ・詳細も消す
+pub(crate) async fn unregister_file(
+    conn: &mut SqliteConnection,
+    illust_id: i32,
+    suffix: i32,
+) -> Result<()> {
+    let cnum: i32 =
+        sqlx::query_scalar("SELECT cnum FROM ILLUST_INFO WHERE illust_id = ? AND suffix = ?")
+            .bind(illust_id)
+            .bind(suffix)
+            .fetch_one(&mut *conn)
+            .await
+            .with_location()?;
+
+    let delete_sql = include_str!("../sql/catalog/delete_file_registration.sql");
+    execute_named_queries(
+        &mut *conn,
+        delete_sql,
+        &hash_params(&vec![
+            (":illust_id", illust_id.into()),
+            (":suffix", suffix.into()),
+            (":cnum", cnum.into()),
+        ])
+        .with_location()?,
+    )
+    .await
+    .with_location()?;
+
+    Ok(())
+}
+
+/// ファイル単位のキャッシュ（ハッシュ・検査結果）
+const FILE_CACHE_TABLES: [&str; 3] = ["FILE_HASH", "IMAGE_HASH", "FILE_HEALTH"];
+
+pub(crate) async fn forget_file_caches(
+    conn: &mut SqliteConnection,
+    save_dir: &str,
+    file_name: &str,
+) -> Result<()> {
+    for table in FILE_CACHE_TABLES {
+        sqlx::query(&format!(
+            "DELETE FROM {} WHERE save_dir = ? AND file_name = ?",
+            table
+        ))
+        .bind(save_dir)
+        .bind(file_name)
+        .execute(&mut *conn)
+        .await
+        .with_location()?;
+    }
+
+    Ok(())
+}
+
+/// 名前を変えたファイルのキャッシュを引き継ぐ（中身は変わらない）
+pub(crate) async fn rename_file_caches(
+    conn: &mut SqliteConnection,
+    save_dir: &str,
+    file_name: &str,
+    new_file_name: &str,
+) -> Result<()> {
+    for table in FILE_CACHE_TABLES {
+        sqlx::query(&format!(
+            "UPDATE {} SET file_name = ? WHERE save_dir = ? AND file_name = ?",
+            table
+        ))
+        .bind(new_file_name)
+        .bind(save_dir)
+        .bind(file_name)
+        .execute(&mut *conn)
+        .await
+        .with_location()?;
+    }
+
+    Ok(())
+}
@@ -1,43 +1,134 @@
-use anyhow::Result;
-use pixieve_rs::pixiv::client::PixivClient;
-use pixieve_rs::pixiv::request_builder::PixivRequestBuilder;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
 use pixieve_rs::pixiv::result::illustration_proxy::IllustrationProxy;
 use rayon::prelude::*;
 use serde_json;
 use sqlx::{Acquire, SqliteConnection, SqlitePool};
+use std::collections::HashSet;
 use std::fs;
+use std::future::Future;
 use std::path::{Path, PathBuf};
-use std::time::{Instant, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::{Duration, Instant, UNIX_EPOCH};
 use std::vec::Vec;
-use tauri::Emitter;
+use tokio::sync::mpsc;
 
+use crate::api::local_metadata::LocalMetadataSource;
+use crate::api::pixiv::IllustSource;
+use crate::api::rate_limiter::{fetch_concurrency, RateLimiter};
+use crate::constants::{FETCH_MAX_RETRIES, FETCH_WRITE_BATCH};
+use crate::errors::fetch::FetchError;
 use crate::execute_queries;
-use crate::models::fetch::{DeleteFileRow, FileDetail, ProcessStats, TagProgress};
+use crate::models::fetch::{
+    DeleteFileRow, FetchFailureKind, FetchItemStatus, FetchJobKind, FetchJobStatus,
+    FetchJobSummary, FetchJobSummaryRaw, FetchRunState, FileDetail, IllustDetailResponse,
+    IllustMetadata, IllustTag, ProcessStats, ProgressSink, RefreshChange, RefreshChangeKind,
+    RefreshRun, RefreshRunRaw, TagProgress, UgoiraFrame, UgoiraMetadata, UgoiraMetadataResponse,
+};
 use crate::service::common::{
-    execute_multi_insert_query, format_duration, parse_path_info, remove_invalid_chars, update_cnum,
+    build_named_query, compress_text, decompress_text, execute_multi_insert_query,
+    execute_named_queries, format_duration, hash_params, parse_path_info, remove_invalid_chars,
+    update_cnum,
 };
+use crate::service::duplicate::is_same_content;
+use crate::service::fetch_control::FetchControl;
+use crate::service::library::{load_library_paths, LibraryPaths};
 use crate::util::log_error;
 use crate::util::ResultWithLocationExt;
 
+/// 作品詳細APIの取得結果
+struct FetchedIllust {
+    proxy: IllustrationProxy,
+    meta: IllustMetadata,
+    body: String,
+    /// うごイラのフレーム情報（別APIで取得）
+    ugoira: Option<UgoiraMetadata>,
+}
+
+/// フェッチ対象の種別
+enum FetchTarget {
+    /// ワークテーブルの未取得分
+    New,
+    /// 取得失敗分の再取得
+    Retry,
+    /// 中断したジョブの再開
+    Resume(i64),
+    /// ローカルのメタデータからの取り込み（ワークテーブルの未取得分）
+    Import,
+}
+
+/// 取得結果の書き込み先
+#[derive(Clone, Copy)]
+enum FetchWrite {
+    /// 新規登録（ジョブID）
+    Job(i64),
+    /// ローカルのメタデータからの新規登録（ジョブID）
+    Import(i64),
+    /// 取得済み作品の更新（実行ID）
+    Refresh(i64),
+}
+
 async fn fetch_illustration_detail(
-    pixiv_client: &PixivClient,
+    source: &dyn IllustSource,
     illust_id: u32,
-) -> Result<IllustrationProxy> {
-    let pixiv_client = pixiv_client.clone();
-
-    let illustration = tauri::async_runtime::spawn_blocking(move || {
-        let request = PixivRequestBuilder::request_illustration(illust_id.try_into().unwrap());
-        let response = pixiv_client.execute_with_auth(request).with_location()?;
-        let illustration = response.json::<IllustrationProxy>().with_location()?;
-        anyhow::Ok(illustration)
+) -> std::result::Result<FetchedIllust, FetchError> {
+    // 失敗種別を判定できるよう本文まで読んでから判定する
+    let (status, body) = source
+        .illust_detail(illust_id)
+        .await
+        .map_err(|e| FetchError::new(FetchFailureKind::Transport, None, e))?;
+    if !response_is_success(status) {
+        return Err(FetchError::from_response(status, &body));
+    }
+
+    parse_illust_detail(status, &body)
+}
+
+fn response_is_success(status: u16) -> bool {
+    (200..300).contains(&status)
+}
+
+fn parse_illust_detail(status: u16, body: &str) -> std::result::Result<FetchedIllust, FetchError> {
+    let parse_error =
+        |e: serde_json::Error| FetchError::new(FetchFailureKind::Parse, Some(status), e);
+
+    let proxy = serde_json::from_str::<IllustrationProxy>(body).map_err(parse_error)?;
+    let meta = serde_json::from_str::<IllustDetailResponse>(body)
+        .map_err(parse_error)?
+        .illust;
+
+    Ok(FetchedIllust {
+        proxy,
+        meta,
+        body: body.to_string(),
+        ugoira: None,
     })
-    .await??;
+}
+
+async fn fetch_ugoira_metadata(
+    source: &dyn IllustSource,
+    illust_id: u32,
+) -> std::result::Result<UgoiraMetadata, FetchError> {
+    let (status, body) = source
+        .ugoira_metadata(illust_id)
+        .await
+        .map_err(|e| FetchError::new(FetchFailureKind::Transport, None, e))?;
+    if !response_is_success(status) {
+        return Err(FetchError::from_response(status, &body));
+    }
 
-    Ok(illustration)
+    serde_json::from_str::<UgoiraMetadataResponse>(&body)
+        .map(|resp| resp.ugoira_metadata)
+        .map_err(|e| FetchError::new(FetchFailureKind::Parse, Some(status), e))
 }
 
-pub async fn extract_dir_detail<P: AsRef<Path>>(folder: P) -> Vec<FileDetail> {
+pub async fn extract_dir_detail<P: AsRef<Path>>(
+    folder: P,
+    library: &LibraryPaths,
+) -> Vec<FileDetail> {
     let folder_path = folder.as_ref().to_owned();
+    let library = library.clone();
     let result = tauri::async_runtime::spawn_blocking(move || {
         let mut all_file_paths: Vec<PathBuf> = Vec::new();
 
@@ -54,7 +145,7 @@ pub async fn extract_dir_detail<P: AsRef<Path>>(folder: P) -> Vec<FileDetail> {
         all_file_paths
             .into_par_iter()
             .filter_map(|path| {
-                let file_info = match parse_path_info(&path) {
+                let file_info = match parse_path_info(&path, &library) {
                     Ok(info) => info,
                     Err(e) => {
                         log_error(format!(
@@ -66,14 +157,17 @@ pub async fn extract_dir_detail<P: AsRef<Path>>(folder: P) -> Vec<FileDetail> {
                 };
 
                 let (created_time, file_size) = get_file_metadata(&path);
+                let variant = file_info.variant();
 
                 Some(FileDetail {
                     illust_id: file_info.illust_id as u32,
                     suffix: file_info.suffix as u8,
                     extension: file_info.extension,
+                    file_name: file_info.file_name,
                     save_dir: file_info.save_dir.unwrap_or_default(),
                     created_time,
                     file_size,
+                    variant,
                 })
             })
             .collect::<Vec<FileDetail>>()
@@ -91,8 +185,124 @@ pub async fn extract_dir_detail<P: AsRef<Path>>(folder: P) -> Vec<FileDetail> {
 
 pub async fn process_fetch_illust_detail(
     pool: &SqlitePool,
-    pixiv_client: &PixivClient,
-    window: tauri::Window,
+    source: &dyn IllustSource,
+    control: &FetchControl,
+    sink: &dyn ProgressSink,
+) -> Result<ProcessStats> {
+    run_fetch_job(pool, source, control, sink, FetchTarget::New).await
+}
+
+pub async fn process_refetch_illust_detail(
+    pool: &SqlitePool,
+    source: &dyn IllustSource,
+    control: &FetchControl,
+    sink: &dyn ProgressSink,
+) -> Result<ProcessStats> {
+    run_fetch_job(pool, source, control, sink, FetchTarget::Retry).await
+}
+
+/// gallery-dl のサイドカーや PixivUtil2 のDBから詳細情報を登録する（通信なし）
+///
+/// メタデータが見つかったファイルのみ登録し、残りは通常のフェッチに任せる
+pub async fn process_import_local_metadata(
+    pool: &SqlitePool,
+    file_details: Vec<FileDetail>,
+    pixivutil_db: Option<String>,
+    control: &FetchControl,
+    sink: &dyn ProgressSink,
+) -> Result<ProcessStats> {
+    // 実行中のジョブのワークテーブルを消さないよう先に確かめる
+    control.ensure_idle()?;
+
+    let mut conn = pool.acquire().await.with_location()?;
+    let library = load_library_paths(&mut conn).await?;
+    drop(conn);
+
+    // サイドカーの読み込みは同期IOなので別スレッドで行う
+    let (mut source, file_details) = tauri::async_runtime::spawn_blocking(move || {
+        let mut source = LocalMetadataSource::default();
+        source.load_gallery_dl_sidecars(&file_details, &library);
+        (source, file_details)
+    })
+    .await
+    .with_location()?;
+
+    if let Some(db_path) = pixivutil_db.filter(|p| !p.trim().is_empty()) {
+        source.load_pixivutil_db(db_path, &file_details).await?;
+    }
+
+    let file_details: Vec<FileDetail> = file_details
+        .into_iter()
+        .filter(|f| source.contains(f.illust_id))
+        .collect();
+    if file_details.is_empty() {
+        return Err(anyhow!("取り込めるメタデータが見つかりません"));
+    }
+
+    prepare_illust_fetch_work(pool, &file_details).await?;
+
+    run_fetch_job(pool, &source, control, sink, FetchTarget::Import).await
+}
+
+pub async fn process_resume_fetch_job(
+    pool: &SqlitePool,
+    source: &dyn IllustSource,
+    control: &FetchControl,
+    sink: &dyn ProgressSink,
+) -> Result<ProcessStats> {
+    let job = get_resumable_fetch_job(pool)
+        .await?
+        .ok_or_else(|| anyhow!("再開できるフェッチジョブがありません"))?;
+
+    run_fetch_job(pool, source, control, sink, FetchTarget::Resume(job.job_id)).await
+}
+
+pub async fn get_resumable_fetch_job(pool: &SqlitePool) -> Result<Option<FetchJobSummary>> {
+    let sql = include_str!("../sql/fetch/get_resumable_fetch_job.sql");
+
+    let raw: Option<FetchJobSummaryRaw> = build_named_query(
+        sql,
+        &hash_params(&vec![
+            (":running", (FetchJobStatus::Running as i64).into()),
+            (":api", (FetchJobKind::Api as i64).into()),
+            (":pending", (FetchItemStatus::Pending as i64).into()),
+            (":done", (FetchItemStatus::Done as i64).into()),
+            (":failed", (FetchItemStatus::Failed as i64).into()),
+            (":skipped", (FetchItemStatus::Skipped as i64).into()),
+        ])
+        .with_location()?,
+    )
+    .with_location()?
+    .build_query_as()
+    .fetch_optional(pool)
+    .await
+    .with_location()?;
+
+    Ok(raw.map(FetchJobSummary::from))
+}
+
+async fn run_fetch_job(
+    pool: &SqlitePool,
+    source: &dyn IllustSource,
+    control: &FetchControl,
+    sink: &dyn ProgressSink,
+    target: FetchTarget,
+) -> Result<ProcessStats> {
+    control.start()?;
+
+    let result = run_fetch_job_inner(pool, source, control, sink, target).await;
+
+    control.finish();
+
+    result
+}
+
+async fn run_fetch_job_inner(
+    pool: &SqlitePool,
+    source: &dyn IllustSource,
+    control: &FetchControl,
+    sink: &dyn ProgressSink,
+    target: FetchTarget,
 ) -> Result<ProcessStats> {
     let start = Instant::now();
 
@@ -102,21 +312,74 @@ pub async fn process_fetch_illust_detail(
     let sql = include_str!("../sql/fetch/prepare_fetch_work.sql");
     execute_queries(&mut *conn, sql).await.with_location()?;
 
+    // 再取得・再開時はフェッチ対象を差し替える
+    match target {
+        FetchTarget::New | FetchTarget::Import => {}
+        FetchTarget::Retry => {
+            let sql = include_str!("../sql/fetch/prepare_retry_fetch_ids.sql");
+            execute_queries(&mut *conn, sql).await.with_location()?;
+        }
+        FetchTarget::Resume(job_id) => {
+            let sql = include_str!("../sql/fetch/prepare_resume_fetch_ids.sql");
+            execute_named_queries(
+                &mut *conn,
+                sql,
+                &hash_params(&vec![
+                    (":job_id", job_id.into()),
+                    (":pending", (FetchItemStatus::Pending as i64).into()),
+                ])
+                .with_location()?,
+            )
+            .await
+            .with_location()?;
+        }
+    }
+
     let mut tx = conn.begin().await.with_location()?;
 
     // フェッチなしでインサートするファイルを処理
     insert_illust_info_no_fetch(&mut *tx).await?;
 
+    // ジョブを準備（再開時は既存ジョブを引き継ぐ）
+    let job_id = match target {
+        FetchTarget::Resume(job_id) => {
+            skip_unavailable_job_items(&mut *tx, job_id).await?;
+            job_id
+        }
+        FetchTarget::New | FetchTarget::Retry => {
+            create_fetch_job(&mut *tx, FetchJobKind::Api).await?
+        }
+        FetchTarget::Import => create_fetch_job(&mut *tx, FetchJobKind::Import).await?,
+    };
+
     tx.commit().await.with_location()?;
 
+    // フェッチ対象を取得
+    let fetch_ids: Vec<u32> = sqlx::query_scalar(
+        "SELECT illust_id FROM FETCH_JOB_ITEM WHERE job_id = ? AND status = ? ORDER BY illust_id",
+    )
+    .bind(job_id)
+    .bind(FetchItemStatus::Pending as i64)
+    .fetch_all(&mut *conn)
+    .await
+    .with_location()?;
+
+    let write = match target {
+        FetchTarget::Import => FetchWrite::Import(job_id),
+        _ => FetchWrite::Job(job_id),
+    };
+
     // メイン処理
-    let mut stats = core_fetch_illust_detail(&mut *conn, start, pixiv_client, window).await?;
+    let mut stats =
+        core_fetch_illust_detail(&mut *conn, start, fetch_ids, write, source, control, sink)
+            .await?;
 
     let mut tx = conn.begin().await.with_location()?;
 
     // 重複ファイルを検知して削除
-    let cnt = delete_duplicate_files(&mut *tx).await?;
+    let (cnt, kept_files) = delete_duplicate_files(&mut *tx).await?;
     stats.duplicated_files = cnt;
+    stats.kept_files = kept_files;
 
     // 詳細を取得できたらMissingタグを削除
     delete_missing_tags(&mut *tx).await?;
@@ -124,6 +387,9 @@ pub async fn process_fetch_illust_detail(
     // 管理番号を更新
     update_cnum(&mut *tx).await.with_location()?;
 
+    // 未処理が無ければジョブを完了
+    complete_fetch_job(&mut *tx, job_id).await?;
+
     tx.commit().await.with_location()?;
 
     // 全体処理終了
@@ -137,117 +403,984 @@ pub async fn process_fetch_illust_detail(
 async fn core_fetch_illust_detail(
     conn: &mut SqliteConnection,
     start: Instant,
-    pixiv_client: &PixivClient,
-    window: tauri::Window,
+    fetch_ids: Vec<u32>,
+    write: FetchWrite,
+    source: &dyn IllustSource,
+    control: &FetchControl,
+    sink: &dyn ProgressSink,
 ) -> Result<ProcessStats> {
-    // 結果用の集計情報
-    let mut success_count = 0;
-    let mut fail_count = 0;
-    let mut failed_file_paths = Vec::new();
+    // 結果用の集計情報（ワーカーからも参照するため共有）
+    let success_count = AtomicU32::new(0);
+    let fail_count = AtomicU32::new(0);
+    let cancelled = AtomicBool::new(false);
 
     // フェッチ回数
-    let total: u64 = sqlx::query_scalar("SELECT COUNT(*) FROM tmp_fetch_ids;")
-        .fetch_one(&mut *conn)
+    let total = fetch_ids.len() as u64;
+
+    // 全ワーカーで共有するリクエスト枠（応答に合わせて間隔を調整する）
+    let concurrency = fetch_concurrency() as usize;
+    let limiter = match write {
+        // ローカルの読み込みに間隔は不要
+        FetchWrite::Import(_) => RateLimiter::new(Duration::ZERO, concurrency as u32),
+        FetchWrite::Job(_) | FetchWrite::Refresh(_) => RateLimiter::from_env(),
+    };
+
+    // 処理状況（スループットは間隔で決まるので残り時間も間隔で見積もる）
+    let make_progress = |state: FetchRunState| {
+        let success = success_count.load(Ordering::Relaxed);
+        let fail = fail_count.load(Ordering::Relaxed);
+        let delay = limiter.current_delay();
+        let elapsed = start.elapsed().as_millis() as u64;
+        let rest = total.saturating_sub((success + fail) as u64);
+        let remaining = rest * delay.as_millis() as u64;
+        TagProgress {
+            success,
+            fail,
+            current: success + fail,
+            total: total as u32,
+            elapsed_time: format_duration(elapsed),
+            remaining_time: format_duration(remaining),
+            current_delay_ms: delay.as_millis() as u32,
+            state,
+        }
+    };
+
+    // 取得結果は書き込みタスクへ渡す（詰まったらワーカー側が待つ）
+    let (result_tx, mut result_rx) = mpsc::channel(concurrency * 2);
+
+    // ワーカー: 同時に concurrency 件までリクエストを投げる
+    let producer = {
+        let limiter = &limiter;
+        let cancelled = &cancelled;
+        let make_progress = &make_progress;
+        async move {
+            let mut results = stream::iter(fetch_ids)
+                .map(|fetch_id| async move {
+                    // 一時停止中は再開まで待機
+                    if control.state() == FetchRunState::Paused {
+                        if let Err(e) = sink.report(&make_progress(FetchRunState::Paused)) {
+                            log_error(e);
+                        }
+                    }
+                    if control.wait_if_paused().await {
+                        return (fetch_id, None);
+                    }
+                    // フェッチ処理（一時的な失敗は再試行）
+                    let fetched = fetch_with_retry(limiter, control, || {
+                        fetch_illustration_detail(source, fetch_id)
+                    })
+                    .await;
+                    let fetched = match fetched {
+                        Some(Ok(fetched)) if fetched.meta.illust_type == "ugoira" => {
+                            attach_ugoira_metadata(source, fetch_id, limiter, control, fetched)
+                                .await
+                        }
+                        other => other,
+                    };
+                    (fetch_id, fetched)
+                })
+                .buffer_unordered(concurrency);
+
+            while let Some((fetch_id, fetched)) = results.next().await {
+                let Some(fetched) = fetched else {
+                    // 未処理分は Pending のまま残し、再開時に拾う
+                    cancelled.store(true, Ordering::Relaxed);
+                    continue;
+                };
+                // 書き込み側が失敗して閉じていたら打ち切る
+                if result_tx.send((fetch_id, fetched)).await.is_err() {
+                    break;
+                }
+            }
+            Ok::<(), anyhow::Error>(())
+        }
+    };
+
+    // 書き込みタスク: 届いた分をまとめて1トランザクションで登録する
+    let writer = async {
+        let mut failed_file_paths = Vec::new();
+        let mut batch = Vec::with_capacity(FETCH_WRITE_BATCH);
+        while result_rx.recv_many(&mut batch, FETCH_WRITE_BATCH).await > 0 {
+            let mut tx = conn.begin().await.with_location()?;
+            let mut success = 0;
+            let mut failed = Vec::new();
+            for (fetch_id, fetched) in batch.drain(..) {
+                let message = match write {
+                    FetchWrite::Job(job_id) => {
+                        register_fetch_result(&mut *tx, job_id, fetch_id, fetched, false).await?
+                    }
+                    FetchWrite::Import(job_id) => {
+                        register_fetch_result(&mut *tx, job_id, fetch_id, fetched, true).await?
+                    }
+                    FetchWrite::Refresh(run_id) => {
+                        register_refresh_result(&mut *tx, run_id, fetch_id, fetched).await?
+                    }
+                };
+                match message {
+                    None => success += 1,
+                    Some(message) => failed.push(message),
+                }
+            }
+            tx.commit().await.with_location()?;
+
+            // コミット後に件数を反映して処理状況を通知
+            success_count.fetch_add(success, Ordering::Relaxed);
+            fail_count.fetch_add(failed.len() as u32, Ordering::Relaxed);
+            failed_file_paths.append(&mut failed);
+            sink.report(&make_progress(control.state()))?;
+        }
+        Ok::<Vec<String>, anyhow::Error>(failed_file_paths)
+    };
+
+    let ((), failed_file_paths) = futures::try_join!(producer, writer)?;
+    let cancelled = cancelled.into_inner();
+
+    // 中断時は最終状態を通知
+    if cancelled {
+        sink.report(&make_progress(FetchRunState::Cancelled))?;
+    }
+
+    // 処理終了
+    let duration = start.elapsed();
+    let success_count = success_count.into_inner();
+    let fail_count = fail_count.into_inner();
+    let processed = success_count + fail_count;
+
+    Ok(ProcessStats {
+        total_ids: processed,
+        successed_ids: success_count,
+        failed_ids: fail_count,
+        duplicated_files: 0,
+        remaining_ids: (total as u32).saturating_sub(processed),
+        cancelled,
+        process_time: format_duration(duration.as_millis() as u64),
+        failed_file_paths,
+        kept_files: Vec::new(),
+    })
+}
+
+/// 1件分の取得結果を登録する。失敗時は結果に載せるメッセージを返す
+///
+/// imported はローカルのメタデータから組み直した結果（API のレスポンスではない）
+async fn register_fetch_result(
+    conn: &mut SqliteConnection,
+    job_id: i64,
+    fetch_id: u32,
+    fetched: std::result::Result<FetchedIllust, FetchError>,
+    imported: bool,
+) -> Result<Option<String>> {
+    // 取り込んだ作品は取得時期が分からないため最も古い扱いにし、次回の更新で取り直す
+    let seen_at = if imported { 0 } else { Utc::now().timestamp() };
+    // イラスト情報を登録
+    let cnum = insert_illust_info(&mut *conn, fetch_id).await?;
+    match fetched {
+        Ok(fetched) => {
+            let resp = &fetched.proxy;
+
+            // 詳細情報を登録
+            sqlx::query("INSERT OR REPLACE INTO ILLUST_DETAIL (illust_id, author_id, character, cnum, created_at) VALUES (?, ?, NULL, ?, strftime('%s', ?))")
+            .bind(resp.illust.id())
+            .bind(resp.illust.user().id())
+            .bind(cnum)
+            .bind(resp.illust.create_date())
+            .execute(&mut *conn).await.with_location()?;
+
+            // タグ情報を登録
+            register_illust_tags(&mut *conn, fetch_id, cnum, &fetched).await?;
+
+            // 作者・メタ情報を登録
+            register_illust_extras(&mut *conn, &fetched, seen_at).await?;
+
+            // 再解析用にレスポンスを保存（組み直した本文は既定値を含むため保存しない）
+            if !imported {
+                save_raw_response(&mut *conn, fetch_id, &fetched.body).await?;
+            }
+
+            // 過去の失敗記録を削除
+            sqlx::query("DELETE FROM FETCH_FAILURE WHERE illust_id = ?")
+                .bind(fetch_id)
+                .execute(&mut *conn)
+                .await
+                .with_location()?;
+
+            update_fetch_job_item(&mut *conn, job_id, fetch_id, FetchItemStatus::Done, None)
+                .await?;
+            Ok(None)
+        }
+        Err(err) => {
+            // 失敗時はデフォルト値で詳細情報を登録
+            sqlx::query(
+                "INSERT OR IGNORE INTO ILLUST_DETAIL (illust_id, author_id, character, cnum) VALUES (?1, 0, NULL, ?2)",
+            )
+            .bind(fetch_id).bind(cnum).execute(&mut *conn).await.with_location()?;
+
+            // 失敗時のタグ情報
+            sqlx::query(
+                "INSERT OR IGNORE INTO TAG_INFO (illust_id, cnum, tag) VALUES (?1, ?2, 'Missing')",
+            )
+            .bind(fetch_id)
+            .bind(cnum)
+            .execute(&mut *conn)
+            .await
+            .with_location()?;
+
+            // 失敗種別を記録
+            record_fetch_failure(&mut *conn, fetch_id, &err).await?;
+
+            update_fetch_job_item(
+                &mut *conn,
+                job_id,
+                fetch_id,
+                FetchItemStatus::Failed,
+                Some(&err.to_string()),
+            )
+            .await?;
+
+            // 失敗したIDを結果に追加
+            Ok(Some(format!("{}:{}", fetch_id, err)))
+        }
+    }
+}
+
+/// 一時的な失敗はバックオフを挟んで再試行する（キャンセル時は None）
+///
+/// 失敗時はリクエストした回数を FetchError に載せて返す
+async fn fetch_with_retry<T, F, Fut>(
+    limiter: &RateLimiter,
+    control: &FetchControl,
+    fetch: F,
+) -> Option<std::result::Result<T, FetchError>>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = std::result::Result<T, FetchError>>,
+{
+    let mut attempt = 0;
+    loop {
+        // 再試行も含めて共有の枠からリクエストする
+        if limiter.acquire(control).await {
+            return None;
+        }
+        match fetch().await {
+            Ok(resp) => {
+                limiter.on_success();
+                return Some(Ok(resp));
+            }
+            Err(mut err) => {
+                if err.kind == FetchFailureKind::RateLimited {
+                    limiter.on_throttled();
+                }
+                if !limiter.should_retry(err.kind, attempt) {
+                    err.attempts = attempt + 1;
+                    return Some(Err(err));
+                }
+                attempt += 1;
+                log_error(format!(
+                    "フェッチを再試行します ({}/{}): {}",
+                    attempt, FETCH_MAX_RETRIES, err
+                ));
+                if control.sleep(limiter.backoff(attempt)).await {
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// うごイラはフレーム情報も取得する（取れなくても作品詳細は登録する）
+async fn attach_ugoira_metadata(
+    source: &dyn IllustSource,
+    illust_id: u32,
+    limiter: &RateLimiter,
+    control: &FetchControl,
+    mut fetched: FetchedIllust,
+) -> Option<std::result::Result<FetchedIllust, FetchError>> {
+    match fetch_with_retry(limiter, control, || {
+        fetch_ugoira_metadata(source, illust_id)
+    })
+    .await
+    {
+        None => return None,
+        Some(Ok(metadata)) => fetched.ugoira = Some(metadata),
+        Some(Err(e)) => {
+            log_error(format!(
+                "うごイラのフレーム情報を取得できません: {} | {}",
+                illust_id, e
+            ));
+        }
+    }
+    Some(Ok(fetched))
+}
+
+async fn create_fetch_job(conn: &mut SqliteConnection, kind: FetchJobKind) -> Result<i64> {
+    let now = Utc::now().timestamp();
+
+    // 未完了のジョブを破棄
+    sqlx::query("UPDATE FETCH_JOB SET status = ?1, updated_at = ?2 WHERE status = ?3")
+        .bind(FetchJobStatus::Abandoned as i64)
+        .bind(now)
+        .bind(FetchJobStatus::Running as i64)
+        .execute(&mut *conn)
         .await
         .with_location()?;
 
-    let interval = std::env::var("INTERVAL_MILL_SEC")
-        .ok()
-        .and_then(|val| val.parse::<u64>().ok())
-        .unwrap_or(1000);
-    let total_duration_ms = total * interval;
+    let job_id = sqlx::query(
+        "INSERT INTO FETCH_JOB (status, kind, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)",
+    )
+    .bind(FetchJobStatus::Running as i64)
+    .bind(kind as i64)
+    .bind(now)
+    .execute(&mut *conn)
+    .await
+    .with_location()?
+    .last_insert_rowid();
+
+    let sql = include_str!("../sql/fetch/create_fetch_job.sql");
+    execute_named_queries(
+        &mut *conn,
+        sql,
+        &hash_params(&vec![
+            (":job_id", job_id.into()),
+            (":pending", (FetchItemStatus::Pending as i64).into()),
+            (":now", now.into()),
+        ])
+        .with_location()?,
+    )
+    .await
+    .with_location()?;
 
-    // フェッチ対象を取得
-    let tmp_fetch_ids: Vec<u32> = sqlx::query_scalar("SELECT illust_id FROM tmp_fetch_ids;")
-        .fetch_all(&mut *conn)
+    Ok(job_id)
+}
+
+async fn skip_unavailable_job_items(conn: &mut SqliteConnection, job_id: i64) -> Result<()> {
+    let sql = include_str!("../sql/fetch/skip_unavailable_job_items.sql");
+    execute_named_queries(
+        &mut *conn,
+        sql,
+        &hash_params(&vec![
+            (":job_id", job_id.into()),
+            (":pending", (FetchItemStatus::Pending as i64).into()),
+            (":skipped", (FetchItemStatus::Skipped as i64).into()),
+            (":now", Utc::now().timestamp().into()),
+        ])
+        .with_location()?,
+    )
+    .await
+    .with_location()?;
+
+    Ok(())
+}
+
+/// タグを登録する（翻訳名があれば併せて保存）
+async fn register_illust_tags(
+    conn: &mut SqliteConnection,
+    illust_id: u32,
+    cnum: i64,
+    fetched: &FetchedIllust,
+) -> Result<()> {
+    for tag in &fetched.meta.tags {
+        let name = remove_invalid_chars(&tag.name);
+        sqlx::query("INSERT OR REPLACE INTO TAG_INFO (illust_id, cnum, tag) VALUES (?1, ?2, ?3)")
+            .bind(illust_id)
+            .bind(cnum)
+            .bind(&name)
+            .execute(&mut *conn)
+            .await
+            .with_location()?;
+
+        register_tag_translation(&mut *conn, &name, tag).await?;
+    }
+
+    Ok(())
+}
+
+async fn register_tag_translation(
+    conn: &mut SqliteConnection,
+    name: &str,
+    tag: &IllustTag,
+) -> Result<()> {
+    if let Some(translated) = tag
+        .translated_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+    {
+        sqlx::query(
+            "INSERT OR REPLACE INTO TAG_TRANSLATION (tag, translated_name) VALUES (?1, ?2)",
+        )
+        .bind(name)
+        .bind(translated)
+        .execute(&mut *conn)
         .await
         .with_location()?;
+    }
 
-    for fetch_id in tmp_fetch_ids {
-        let mut tx = conn.begin().await.with_location()?;
-        // イラスト情報を登録
-        let cnum = insert_illust_info(&mut *tx, fetch_id).await?;
-        // フェッチ処理
-        match fetch_illustration_detail(pixiv_client, fetch_id).await {
-            Ok(resp) => {
-                // 詳細情報を登録
-                sqlx::query("INSERT OR REPLACE INTO ILLUST_DETAIL (illust_id, author_id, character, cnum, created_at) VALUES (?, ?, NULL, ?, strftime('%s', ?))")
-                .bind(resp.illust.id())
-                .bind(resp.illust.user().id())
-                .bind(cnum)
-                .bind(resp.illust.create_date())
-                .execute(&mut *tx).await.with_location()?;
-
-                // タグ情報を登録
-                for tag in resp.illust.tags() {
-                    sqlx::query("INSERT OR REPLACE INTO TAG_INFO (illust_id, cnum, tag) VALUES (?1, ?2, ?3)")
-                    .bind(fetch_id)
-                    .bind(cnum)
-                    .bind(remove_invalid_chars(tag.name()))
-                    .execute(&mut *tx).await.with_location()?;
-                }
+    Ok(())
+}
 
-                // 作者情報を登録
-                sqlx::query("INSERT OR REPLACE INTO AUTHOR_INFO (author_id, author_name, author_account) VALUES (?1, ?2, ?3)",)
-                        .bind(resp.illust.user().id())
-                        .bind(resp.illust.user().name())
-                        .bind(resp.illust.user().account())
-                .execute(&mut *tx).await.with_location()?;
-                success_count += 1;
-            }
+/// 作者情報と作品のメタ情報を登録する
+///
+/// `seen_at` はレスポンスを取得した日時（キャッシュからの再解析では取得当時）
+async fn register_illust_extras(
+    conn: &mut SqliteConnection,
+    fetched: &FetchedIllust,
+    seen_at: i64,
+) -> Result<()> {
+    let user = fetched.proxy.illust.user();
+
+    // 改名しても以前の名前で探せるよう履歴に残す
+    let sql = include_str!("../sql/fetch/upsert_author_history.sql");
+    sqlx::query(sql)
+        .bind(user.id())
+        .bind(user.name())
+        .bind(user.account())
+        .bind(seen_at)
+        .execute(&mut *conn)
+        .await
+        .with_location()?;
+
+    // 古いキャッシュで現在の名前を戻さないよう、最新の履歴を反映する
+    let sql = include_str!("../sql/fetch/sync_author_info.sql");
+    sqlx::query(sql)
+        .bind(user.id())
+        .execute(&mut *conn)
+        .await
+        .with_location()?;
+
+    upsert_illust_meta(&mut *conn, &fetched.meta, seen_at).await?;
+
+    // 再解析ではフレーム情報を取得しないため、取得できたときだけ置き換える
+    if let Some(ugoira) = &fetched.ugoira {
+        replace_ugoira_frames(&mut *conn, fetched.meta.id, &ugoira.frames).await?;
+    }
+
+    Ok(())
+}
+
+async fn replace_ugoira_frames(
+    conn: &mut SqliteConnection,
+    illust_id: u32,
+    frames: &[UgoiraFrame],
+) -> Result<()> {
+    sqlx::query("DELETE FROM UGOIRA_FRAME WHERE illust_id = ?")
+        .bind(illust_id)
+        .execute(&mut *conn)
+        .await
+        .with_location()?;
+
+    for (index, frame) in frames.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO UGOIRA_FRAME (illust_id, frame_index, file, delay) VALUES (?, ?, ?, ?)",
+        )
+        .bind(illust_id)
+        .bind(index as i64)
+        .bind(&frame.file)
+        .bind(frame.delay)
+        .execute(&mut *conn)
+        .await
+        .with_location()?;
+    }
+
+    Ok(())
+}
+
+async fn save_raw_response(conn: &mut SqliteConnection, illust_id: u32, body: &str) -> Result<()> {
+    sqlx::query(
+        "INSERT OR REPLACE INTO ILLUST_RAW_CACHE (illust_id, body, fetched_at) VALUES (?1, ?2, ?3)",
+    )
+    .bind(illust_id)
+    .bind(compress_text(body).with_location()?)
+    .bind(Utc::now().timestamp())
+    .execute(&mut *conn)
+    .await
+    .with_location()?;
+
+    Ok(())
+}
+
+fn parse_cached_body(compressed: &[u8]) -> std::result::Result<FetchedIllust, FetchError> {
+    decompress_text(compressed)
+        .map_err(|e| FetchError::new(FetchFailureKind::Parse, None, e))
+        .and_then(|body| parse_illust_detail(200, &body))
+}
+
+/// キャッシュ済みのレスポンスから詳細情報を作り直す（通信なし）
+pub async fn process_reparse_cached_details(pool: &SqlitePool) -> Result<ProcessStats> {
+    let start = Instant::now();
+
+    let mut success_count = 0;
+    let mut fail_count = 0;
+    let mut failed_file_paths = Vec::new();
+
+    let mut tx = pool.begin().await.with_location()?;
+
+    // 登録済みファイルのある作品のみ対象
+    let sql = include_str!("../sql/fetch/get_cached_illust_ids.sql");
+    let illust_ids: Vec<u32> = sqlx::query_scalar(sql)
+        .fetch_all(&mut *tx)
+        .await
+        .with_location()?;
+
+    for illust_id in illust_ids {
+        let (compressed, fetched_at): (Vec<u8>, i64) =
+            sqlx::query_as("SELECT body, fetched_at FROM ILLUST_RAW_CACHE WHERE illust_id = ?")
+                .bind(illust_id)
+                .fetch_one(&mut *tx)
+                .await
+                .with_location()?;
+
+        let fetched = match parse_cached_body(&compressed) {
+            Ok(fetched) => fetched,
             Err(err) => {
                 fail_count += 1;
-                // 失敗時はデフォルト値で詳細情報を登録
-                sqlx::query(
-                    "INSERT OR IGNORE INTO ILLUST_DETAIL (illust_id, author_id, character, cnum) VALUES (?1, 0, NULL, ?2)",
-                )
-                .bind(fetch_id).bind(cnum).execute(&mut *tx).await.with_location()?;
+                failed_file_paths.push(format!("{}:{}", illust_id, err));
+                continue;
+            }
+        };
 
-                // 失敗時のタグ情報
-                sqlx::query(
-                    "INSERT OR IGNORE INTO TAG_INFO (illust_id, cnum, tag) VALUES (?1, ?2, 'Missing')",
-                )
-                .bind(fetch_id).bind(cnum).execute(&mut *tx).await.with_location()?;
+        let cnums: Vec<i64> =
+            sqlx::query_scalar("SELECT DISTINCT cnum FROM ILLUST_INFO WHERE illust_id = ?")
+                .bind(illust_id)
+                .fetch_all(&mut *tx)
+                .await
+                .with_location()?;
 
-                // 失敗したIDを結果に追加
-                failed_file_paths.push(format!("{}:{}", fetch_id, err))
-            }
+        // キャラクター付けなどの編集を残すため、詳細は上書きではなく更新する
+        for cnum in cnums {
+            let sql = include_str!("../sql/fetch/upsert_illust_detail.sql");
+            sqlx::query(sql)
+                .bind(illust_id)
+                .bind(cnum)
+                .bind(fetched.proxy.illust.user().id())
+                .bind(fetched.proxy.illust.create_date())
+                .execute(&mut *tx)
+                .await
+                .with_location()?;
+
+            register_illust_tags(&mut *tx, illust_id, cnum, &fetched).await?;
         }
-        // 一件ずつコミット
-        tx.commit().await.with_location()?;
 
-        // 処理状況を通知
-        let elapsed = start.elapsed().as_millis() as u64;
-        let remaining = total_duration_ms.saturating_sub(elapsed);
-        let progress = TagProgress {
-            success: success_count,
-            fail: fail_count,
-            current: success_count + fail_count,
-            total: total as u32,
-            elapsed_time: format_duration(elapsed),
-            remaining_time: format_duration(remaining),
-        };
-        window
-            .emit("tag_progress", serde_json::json!(progress))
+        register_illust_extras(&mut *tx, &fetched, fetched_at).await?;
+
+        sqlx::query("DELETE FROM FETCH_FAILURE WHERE illust_id = ?")
+            .bind(illust_id)
+            .execute(&mut *tx)
+            .await
             .with_location()?;
 
-        // ボットアクセスなのでインターバルを挟む
-        std::thread::sleep(std::time::Duration::from_millis(interval));
+        success_count += 1;
     }
 
-    // 処理終了
-    let duration = start.elapsed();
+    // 詳細を取得できたらMissingタグを削除
+    delete_missing_tags(&mut *tx).await?;
+
+    tx.commit().await.with_location()?;
+
+    let processed = success_count + fail_count;
 
     Ok(ProcessStats {
-        total_ids: success_count + fail_count,
+        total_ids: processed,
         successed_ids: success_count,
         failed_ids: fail_count,
         duplicated_files: 0,
-        process_time: format_duration(duration.as_millis() as u64),
+        remaining_ids: 0,
+        cancelled: false,
+        process_time: format_duration(start.elapsed().as_millis() as u64),
         failed_file_paths,
+        kept_files: Vec::new(),
     })
 }
 
+/// 取得から `days` 日以上たった作品を古い順に取り直す
+pub async fn process_refresh_stale_details(
+    pool: &SqlitePool,
+    days: u32,
+    source: &dyn IllustSource,
+    control: &FetchControl,
+    sink: &dyn ProgressSink,
+) -> Result<ProcessStats> {
+    control.start()?;
+
+    let result = refresh_stale_details_inner(pool, days, source, control, sink).await;
+
+    control.finish();
+
+    result
+}
+
+async fn refresh_stale_details_inner(
+    pool: &SqlitePool,
+    days: u32,
+    source: &dyn IllustSource,
+    control: &FetchControl,
+    sink: &dyn ProgressSink,
+) -> Result<ProcessStats> {
+    let start = Instant::now();
+
+    let mut conn = pool.acquire().await.with_location()?;
+
+    // 対象を古い順に取得
+    let threshold = Utc::now().timestamp() - days as i64 * 86_400;
+    let sql = include_str!("../sql/fetch/get_stale_illust_ids.sql");
+    let params = hash_params(&vec![
+        (":threshold", threshold.into()),
+        (":transient_reasons", transient_reasons().into()),
+    ])
+    .with_location()?;
+    let fetch_ids: Vec<u32> = build_named_query(sql, &params)
+        .with_location()?
+        .build_query_scalar()
+        .fetch_all(&mut *conn)
+        .await
+        .with_location()?;
+
+    // 実行履歴を作成
+    let run_id = sqlx::query(
+        "INSERT INTO REFRESH_RUN (stale_days, target_count, started_at) VALUES (?1, ?2, ?3)",
+    )
+    .bind(days)
+    .bind(fetch_ids.len() as u32)
+    .bind(Utc::now().timestamp())
+    .execute(&mut *conn)
+    .await
+    .with_location()?
+    .last_insert_rowid();
+
+    // メイン処理
+    let mut stats = core_fetch_illust_detail(
+        &mut *conn,
+        start,
+        fetch_ids,
+        FetchWrite::Refresh(run_id),
+        source,
+        control,
+        sink,
+    )
+    .await?;
+
+    sqlx::query(
+        "UPDATE REFRESH_RUN SET success_count = ?2, fail_count = ?3, cancelled = ?4, finished_at = ?5 WHERE run_id = ?1",
+    )
+    .bind(run_id)
+    .bind(stats.successed_ids)
+    .bind(stats.failed_ids)
+    .bind(stats.cancelled)
+    .bind(Utc::now().timestamp())
+    .execute(&mut *conn)
+    .await
+    .with_location()?;
+
+    stats.process_time = format_duration(start.elapsed().as_millis() as u64);
+
+    Ok(stats)
+}
+
+/// 取得済み作品の取り直し結果を反映し、変更点を記録する
+///
+/// ユーザーが編集したタグを残すため、タグは前回の取得結果から増えた分だけ追加する
+async fn register_refresh_result(
+    conn: &mut SqliteConnection,
+    run_id: i64,
+    illust_id: u32,
+    fetched: std::result::Result<FetchedIllust, FetchError>,
+) -> Result<Option<String>> {
+    let fetched = match fetched {
+        Ok(fetched) => fetched,
+        Err(err) => {
+            record_fetch_failure(&mut *conn, illust_id, &err).await?;
+
+            // 削除・非公開になった作品は次回から対象外になる
+            if !err.kind.is_transient() {
+                record_refresh_change(
+                    &mut *conn,
+                    run_id,
+                    illust_id,
+                    RefreshChangeKind::Unavailable,
+                    None,
+                    Some(&err.to_string()),
+                )
+                .await?;
+            }
+            return Ok(Some(format!("{}:{}", illust_id, err)));
+        }
+    };
+
+    // 前回の取得結果（キャッシュが無い旧データは全タグを追加候補とする）
+    let cached: Option<Vec<u8>> =
+        sqlx::query_scalar("SELECT body FROM ILLUST_RAW_CACHE WHERE illust_id = ?")
+            .bind(illust_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .with_location()?;
+    let previous_tags: Option<HashSet<String>> = cached
+        .and_then(|body| parse_cached_body(&body).ok())
+        .map(|prev| {
+            prev.meta
+                .tags
+                .iter()
+                .map(|t| remove_invalid_chars(&t.name))
+                .collect()
+        });
+
+    let old_title: Option<String> =
+        sqlx::query_scalar("SELECT title FROM ILLUST_META WHERE illust_id = ?")
+            .bind(illust_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .with_location()?;
+    let user = fetched.proxy.illust.user();
+    let old_author_name: Option<String> =
+        sqlx::query_scalar("SELECT author_name FROM AUTHOR_INFO WHERE author_id = ?")
+            .bind(user.id())
+            .fetch_optional(&mut *conn)
+            .await
+            .with_location()?;
+
+    let cnums: Vec<i64> =
+        sqlx::query_scalar("SELECT DISTINCT cnum FROM ILLUST_INFO WHERE illust_id = ?")
+            .bind(illust_id)
+            .fetch_all(&mut *conn)
+            .await
+            .with_location()?;
+
+    // キャラクター付けなどの編集を残すため、詳細は上書きではなく更新する
+    for cnum in &cnums {
+        let sql = include_str!("../sql/fetch/upsert_illust_detail.sql");
+        sqlx::query(sql)
+            .bind(illust_id)
+            .bind(cnum)
+            .bind(user.id())
+            .bind(fetched.proxy.illust.create_date())
+            .execute(&mut *conn)
+            .await
+            .with_location()?;
+    }
+
+    // pixiv 側で増えたタグのみ追加する
+    let mut current_tags = HashSet::new();
+    for tag in &fetched.meta.tags {
+        let name = remove_invalid_chars(&tag.name);
+        register_tag_translation(&mut *conn, &name, tag).await?;
+
+        let is_new = previous_tags
+            .as_ref()
+            .map_or(true, |prev| !prev.contains(&name));
+        if is_new {
+            let mut inserted = 0;
+            for cnum in &cnums {
+                inserted += sqlx::query(
+                    "INSERT OR IGNORE INTO TAG_INFO (illust_id, cnum, tag) VALUES (?1, ?2, ?3)",
+                )
+                .bind(illust_id)
+                .bind(cnum)
+                .bind(&name)
+                .execute(&mut *conn)
+                .await
+                .with_location()?
+                .rows_affected();
+            }
+            if inserted > 0 {
+                record_refresh_change(
+                    &mut *conn,
+                    run_id,
+                    illust_id,
+                    RefreshChangeKind::TagAdded,
+                    None,
+                    Some(&name),
+                )
+                .await?;
+            }
+        }
+        current_tags.insert(name);
+    }
+
+    // pixiv 側で外れたタグは記録のみ（ユーザーが付けたものと区別できないため消さない）
+    if let Some(previous_tags) = &previous_tags {
+        let mut removed: Vec<&String> = previous_tags.difference(&current_tags).collect();
+        removed.sort();
+        for name in removed {
+            record_refresh_change(
+                &mut *conn,
+                run_id,
+                illust_id,
+                RefreshChangeKind::TagRemoved,
+                Some(name),
+                None,
+            )
+            .await?;
+        }
+    }
+
+    if let Some(old_title) = old_title.filter(|t| *t != fetched.meta.title) {
+        record_refresh_change(
+            &mut *conn,
+            run_id,
+            illust_id,
+            RefreshChangeKind::Title,
+            Some(&old_title),
+            Some(&fetched.meta.title),
+        )
+        .await?;
+    }
+
+    let author_name = user.name().to_string();
+    if let Some(old_name) = old_author_name.filter(|n| *n != author_name) {
+        record_refresh_change(
+            &mut *conn,
+            run_id,
+            illust_id,
+            RefreshChangeKind::AuthorName,
+            Some(&old_name),
+            Some(&author_name),
+        )
+        .await?;
+    }
+
+    // 作者・メタ情報を更新（取得日時もここで更新される）
+    register_illust_extras(&mut *conn, &fetched, Utc::now().timestamp()).await?;
+
+    save_raw_response(&mut *conn, illust_id, &fetched.body).await?;
+
+    sqlx::query("DELETE FROM FETCH_FAILURE WHERE illust_id = ?")
+        .bind(illust_id)
+        .execute(&mut *conn)
+        .await
+        .with_location()?;
+
+    Ok(None)
+}
+
+async fn record_refresh_change(
+    conn: &mut SqliteConnection,
+    run_id: i64,
+    illust_id: u32,
+    kind: RefreshChangeKind,
+    old_value: Option<&str>,
+    new_value: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO REFRESH_CHANGE (run_id, illust_id, kind, old_value, new_value) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )
+    .bind(run_id)
+    .bind(illust_id)
+    .bind(kind as i64)
+    .bind(old_value)
+    .bind(new_value)
+    .execute(&mut *conn)
+    .await
+    .with_location()?;
+
+    Ok(())
+}
+
+pub async fn get_refresh_runs(pool: &SqlitePool) -> Result<Vec<RefreshRun>> {
+    let sql = include_str!("../sql/fetch/get_refresh_runs.sql");
+    let runs: Vec<RefreshRunRaw> = sqlx::query_as(sql).fetch_all(pool).await.with_location()?;
+
+    Ok(runs.into_iter().map(RefreshRun::from).collect())
+}
+
+pub async fn get_refresh_changes(pool: &SqlitePool, run_id: i64) -> Result<Vec<RefreshChange>> {
+    let changes = sqlx::query_as(
+        "SELECT illust_id, kind, old_value, new_value FROM REFRESH_CHANGE WHERE run_id = ? ORDER BY illust_id, kind",
+    )
+    .bind(run_id)
+    .fetch_all(pool)
+    .await
+    .with_location()?;
+
+    Ok(changes)
+}
+
+async fn upsert_illust_meta(
+    conn: &mut SqliteConnection,
+    meta: &IllustMetadata,
+    fetched_at: i64,
+) -> Result<()> {
+    let sql = include_str!("../sql/fetch/upsert_illust_meta.sql");
+    sqlx::query(sql)
+        .bind(meta.id)
+        .bind(&meta.title)
+        .bind(&meta.caption)
+        .bind(meta.page_count)
+        .bind(meta.width)
+        .bind(meta.height)
+        .bind(meta.total_bookmarks)
+        .bind(meta.total_view)
+        .bind(meta.x_restrict)
+        .bind(meta.illust_ai_type)
+        .bind(serde_json::to_string(&meta.tools).with_location()?)
+        .bind(meta.series.as_ref().map(|s| s.id))
+        .bind(meta.series.as_ref().map(|s| s.title.as_str()))
+        .bind(fetched_at)
+        .execute(&mut *conn)
+        .await
+        .with_location()?;
+
+    Ok(())
+}
+
+async fn record_fetch_failure(
+    conn: &mut SqliteConnection,
+    illust_id: u32,
+    err: &FetchError,
+) -> Result<()> {
+    let sql = include_str!("../sql/fetch/upsert_fetch_failure.sql");
+    sqlx::query(sql)
+        .bind(illust_id)
+        .bind(err.kind as i64)
+        .bind(err.status)
+        .bind(err.attempts)
+        .bind(&err.message)
+        .bind(Utc::now().timestamp())
+        .execute(&mut *conn)
+        .await
+        .with_location()?;
+
+    Ok(())
+}
+
+async fn update_fetch_job_item(
+    conn: &mut SqliteConnection,
+    job_id: i64,
+    illust_id: u32,
+    status: FetchItemStatus,
+    message: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE FETCH_JOB_ITEM SET status = ?1, message = ?2, updated_at = ?3
+         WHERE job_id = ?4 AND illust_id = ?5",
+    )
+    .bind(status as i64)
+    .bind(message)
+    .bind(Utc::now().timestamp())
+    .bind(job_id)
+    .bind(illust_id)
+    .execute(&mut *conn)
+    .await
+    .with_location()?;
+
+    Ok(())
+}
+
+async fn complete_fetch_job(conn: &mut SqliteConnection, job_id: i64) -> Result<()> {
+    sqlx::query(
+        "UPDATE FETCH_JOB SET status = ?1, updated_at = ?2
+         WHERE job_id = ?3
+           AND NOT EXISTS (
+               SELECT 1 FROM FETCH_JOB_ITEM WHERE job_id = ?3 AND status = ?4
+           )",
+    )
+    .bind(FetchJobStatus::Completed as i64)
+    .bind(Utc::now().timestamp())
+    .bind(job_id)
+    .bind(FetchItemStatus::Pending as i64)
+    .execute(&mut *conn)
+    .await
+    .with_location()?;
+
+    Ok(())
+}
+
 pub async fn prepare_illust_fetch_work(
     pool: &SqlitePool,
     file_details: &[FileDetail],
@@ -267,16 +1400,18 @@ pub async fn prepare_illust_fetch_work(
                 f.illust_id.into(),
                 f.suffix.into(),
                 f.extension.clone().into(),
+                f.file_name.clone().into(),
                 f.save_dir.clone().into(),
                 f.created_time.into(),
                 f.file_size.into(),
+                f.variant.clone().into(),
             ]
         })
         .collect();
 
     execute_multi_insert_query(&mut *conn,
-        "INSERT INTO ILLUST_FETCH_WORK (illust_id, suffix, extension, save_dir, created_time, file_size)
-        VALUES [(?, ?, ?, ?, ?, ?)]",
+        "INSERT INTO ILLUST_FETCH_WORK (illust_id, suffix, extension, file_name, save_dir, created_time, file_size, variant)
+        VALUES [(?, ?, ?, ?, ?, ?, ?, ?)]",
         &rows,
     ).await.with_location()?;
 
@@ -313,28 +1448,38 @@ async fn insert_illust_info_no_fetch(conn: &mut SqliteConnection) -> Result<()>
     Ok(())
 }
 
-pub async fn delete_duplicate_files(conn: &mut SqliteConnection) -> anyhow::Result<u32> {
+/// 重複ファイルを削除し、削除件数と内容が違うため残したファイルを返す
+pub async fn delete_duplicate_files(
+    conn: &mut SqliteConnection,
+) -> anyhow::Result<(u32, Vec<String>)> {
     let rows: Vec<DeleteFileRow> = sqlx::query_as(
-        "SELECT file_path, keep_file_path, illust_id, suffix, extension, save_dir
-                          FROM tmp_delete_files",
+        "SELECT keep_save_dir, keep_file_name, illust_id, suffix, extension, save_dir, file_name
+           FROM tmp_delete_files",
     )
     .fetch_all(&mut *conn)
     .await
     .with_location()?;
 
+    let library = load_library_paths(&mut *conn).await?;
     let mut deleted = 0;
+    let mut kept_files = Vec::new();
 
     for row in rows {
+        let target = library.file_path(&row.save_dir, &row.file_name);
+
         // 優先ファイルが無い場合 → DB 更新
-        if let Some(ref keep) = row.keep_file_path {
-            if !std::path::Path::new(keep).exists() {
+        if let (Some(keep_save_dir), Some(keep_file_name)) =
+            (&row.keep_save_dir, &row.keep_file_name)
+        {
+            if !library.file_path(keep_save_dir, keep_file_name).exists() {
                 sqlx::query(
                     "UPDATE ILLUST_INFO
-                        SET save_dir = ?, extension = ?
+                        SET save_dir = ?, extension = ?, file_name = ?
                       WHERE illust_id = ? AND suffix = ?",
                 )
                 .bind(&row.save_dir)
                 .bind(&row.extension)
+                .bind(&row.file_name)
                 .bind(row.illust_id)
                 .bind(row.suffix)
                 .execute(&mut *conn)
@@ -344,36 +1489,59 @@ pub async fn delete_duplicate_files(conn: &mut SqliteConnection) -> anyhow::Resu
             }
         }
 
+        // 同じ作品・ページでも内容が違えば再投稿なので残す
+        if let (Some(keep_save_dir), Some(keep_file_name)) = (row.keep_save_dir, row.keep_file_name)
+        {
+            let keep = (keep_save_dir, keep_file_name);
+            let candidate = (row.save_dir.clone(), row.file_name.clone());
+            if !is_same_content(&mut *conn, &keep, &candidate).await? {
+                kept_files.push(target.to_string_lossy().to_string());
+                continue;
+            }
+        }
+
         // 削除対象のファイルが存在するなら削除
-        let target = std::path::Path::new(&row.file_path);
         if target.exists() {
-            trash::delete(target).with_location()?;
+            trash::delete(&target).with_location()?;
             deleted += 1;
         }
     }
 
-    Ok(deleted)
+    Ok((deleted, kept_files))
+}
+
+/// 時間をおけば成功しうる失敗種別（SQLのバインド用）
+fn transient_reasons() -> Vec<i64> {
+    FetchFailureKind::TRANSIENT
+        .iter()
+        .map(|k| *k as i64)
+        .collect()
 }
 
 pub async fn extract_missing_files(pool: &SqlitePool) -> Result<Vec<FileDetail>> {
     let sql = include_str!("../sql/fetch/extract_missing_files.sql");
 
-    let result = sqlx::query_as::<_, FileDetail>(sql)
-        .fetch_all(pool)
-        .await?
-        .into_iter()
-        .map(|mut r| {
-            let file_path = format!(
-                "{}/{}_p{}.{}",
-                r.save_dir, r.illust_id, r.suffix, r.extension
-            );
-            let path = Path::new(&file_path);
-            let (created_time, file_size) = get_file_metadata(path);
-            r.created_time = created_time;
-            r.file_size = file_size;
-            r
-        })
-        .collect();
+    let mut conn = pool.acquire().await.with_location()?;
+    let library = load_library_paths(&mut conn).await?;
+
+    let result: Vec<FileDetail> = build_named_query(
+        sql,
+        &hash_params(&vec![(":transient_reasons", transient_reasons().into())]).with_location()?,
+    )
+    .with_location()?
+    .build_query_as::<FileDetail>()
+    .fetch_all(&mut *conn)
+    .await
+    .with_location()?
+    .into_iter()
+    .map(|mut r| {
+        let (created_time, file_size) =
+            get_file_metadata(&library.file_path(&r.save_dir, &r.file_name));
+        r.created_time = created_time;
+        r.file_size = file_size;
+        r
+    })
+    .collect();
 
     Ok(result)
 }
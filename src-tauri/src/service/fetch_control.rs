@@ -0,0 +1,107 @@
+use anyhow::{bail, Result};
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::models::fetch::FetchRunState;
+
+/// 実行中のフェッチを一時停止・再開・キャンセルするための制御
+pub struct FetchControl {
+    state: watch::Sender<FetchRunState>,
+}
+
+impl FetchControl {
+    pub fn new() -> Self {
+        let (state, _) = watch::channel(FetchRunState::Idle);
+        Self { state }
+    }
+
+    pub fn state(&self) -> FetchRunState {
+        *self.state.borrow()
+    }
+
+    pub fn start(&self) -> Result<()> {
+        let started = self.state.send_if_modified(|s| match s {
+            FetchRunState::Idle | FetchRunState::Cancelled => {
+                *s = FetchRunState::Running;
+                true
+            }
+            _ => false,
+        });
+        if !started {
+            bail!("フェッチは既に実行中です");
+        }
+        Ok(())
+    }
+
+    /// 実行中（一時停止中を含む）なら失敗する（ワークテーブルを作り直す前の確認）
+    pub fn ensure_idle(&self) -> Result<()> {
+        if matches!(self.state(), FetchRunState::Running | FetchRunState::Paused) {
+            bail!("フェッチは既に実行中です");
+        }
+        Ok(())
+    }
+
+    pub fn finish(&self) {
+        self.state.send_replace(FetchRunState::Idle);
+    }
+
+    pub fn pause(&self) -> Result<()> {
+        self.transition(FetchRunState::Running, FetchRunState::Paused)
+    }
+
+    pub fn resume(&self) -> Result<()> {
+        self.transition(FetchRunState::Paused, FetchRunState::Running)
+    }
+
+    pub fn cancel(&self) -> Result<()> {
+        let cancelled = self.state.send_if_modified(|s| match s {
+            FetchRunState::Running | FetchRunState::Paused => {
+                *s = FetchRunState::Cancelled;
+                true
+            }
+            _ => false,
+        });
+        if !cancelled {
+            bail!("実行中のフェッチがありません");
+        }
+        Ok(())
+    }
+
+    /// 一時停止中なら再開かキャンセルまで待機する。キャンセルされたら true
+    pub async fn wait_if_paused(&self) -> bool {
+        let mut rx = self.state.subscribe();
+        let state = rx
+            .wait_for(|s| *s != FetchRunState::Paused)
+            .await
+            .map(|s| *s)
+            .unwrap_or(FetchRunState::Cancelled);
+        state == FetchRunState::Cancelled
+    }
+
+    /// インターバル分待機する。途中でキャンセルされたら即座に戻り true
+    pub async fn sleep(&self, duration: Duration) -> bool {
+        let mut rx = self.state.subscribe();
+        let _ =
+            tokio::time::timeout(duration, rx.wait_for(|s| *s == FetchRunState::Cancelled)).await;
+        self.state() == FetchRunState::Cancelled
+    }
+
+    fn transition(&self, from: FetchRunState, to: FetchRunState) -> Result<()> {
+        let changed = self.state.send_if_modified(|s| {
+            if *s == from {
+                *s = to;
+                true
+            } else {
+                false
+            }
+        });
+        if !changed {
+            bail!(
+                "フェッチの状態を {:?} から {:?} に変更できません",
+                self.state(),
+                to
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,410 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use sqlx::{prelude::FromRow, SqliteConnection, SqlitePool};
+use std::fs::{self, File, FileTimes};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::models::catalog::{MoveFailure, MoveProgress, MoveProgressSink, MoveState};
+use crate::models::collect::MoveIllustFiles;
+use crate::service::duplicate::hash_file;
+use crate::service::library::{load_library_paths, LibraryPaths};
+use crate::util::{log_error, ResultWithLocationExt};
+
+#[derive(FromRow)]
+struct MoveJournalRow {
+    id: i64,
+    illust_id: i64,
+    suffix: i32,
+    file_name: String,
+    zip_name: Option<String>,
+    src_dir: String,
+    dest_dir: String,
+    state: MoveState,
+}
+
+fn failure(library: &LibraryPaths, row: &MoveIllustFiles, reason: impl ToString) -> MoveFailure {
+    MoveFailure {
+        file_name: row.file_name.clone(),
+        src_dir: library
+            .resolve_dir(&row.src_dir)
+            .to_string_lossy()
+            .to_string(),
+        dest_dir: library
+            .resolve_dir(&row.dest_dir)
+            .to_string_lossy()
+            .to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// コピー途中のファイル（照合が済んでから本来の名前にする）
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+/// フォルダの変更（作成・名前変更）をディスクに書き出す
+fn sync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    File::open(dir)
+        .with_location()?
+        .sync_all()
+        .with_location()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// 別ドライブへコピーする
+///
+/// 更新日時を引き継いで fsync し、内容のハッシュが一致してから本来の名前にする
+pub fn copy_file_verified(src: &Path, dest: &Path) -> Result<()> {
+    let part = part_path(dest);
+    let result = copy_to_part(src, &part).and_then(|_| {
+        fs::rename(&part, dest).with_location()?;
+        if let Some(parent) = dest.parent() {
+            sync_dir(parent)?;
+        }
+        Ok(())
+    });
+    if result.is_err() && part.exists() {
+        if let Err(e) = fs::remove_file(&part) {
+            log_error(format!(
+                "コピー途中のファイルを消せません: {:?} | {}",
+                part, e
+            ));
+        }
+    }
+    result
+}
+
+fn copy_to_part(src: &Path, part: &Path) -> Result<()> {
+    let metadata = fs::metadata(src).with_location()?;
+    {
+        let mut reader = File::open(src).with_location()?;
+        let mut writer = File::create(part).with_location()?;
+        io::copy(&mut reader, &mut writer).with_location()?;
+
+        let mut times = FileTimes::new().set_modified(metadata.modified().with_location()?);
+        if let Ok(accessed) = metadata.accessed() {
+            times = times.set_accessed(accessed);
+        }
+        writer.set_times(times).with_location()?;
+        writer.sync_all().with_location()?;
+    }
+
+    if hash_file(src)? != hash_file(part)? {
+        bail!("コピーした内容が一致しません: {:?}", src);
+    }
+    Ok(())
+}
+
+/// rename できない別ドライブへの移動か（EXDEV / ERROR_NOT_SAME_DEVICE）
+pub fn is_cross_device(e: &io::Error) -> bool {
+    #[cfg(unix)]
+    const CROSS_DEVICE: i32 = 18;
+    #[cfg(windows)]
+    const CROSS_DEVICE: i32 = 17;
+    #[cfg(not(any(unix, windows)))]
+    const CROSS_DEVICE: i32 = -1;
+
+    e.raw_os_error() == Some(CROSS_DEVICE)
+}
+
+/// コピーして照合してから元を消す
+///
+/// 元を消せなければコピーを消して失敗にする（両方に残さない）
+pub fn move_by_copy<F>(src: &Path, dest: &Path, remove_src: F) -> Result<()>
+where
+    F: FnOnce(&Path) -> io::Result<()>,
+{
+    copy_file_verified(src, dest)?;
+    if let Err(e) = remove_src(src) {
+        if let Err(e) = fs::remove_file(dest) {
+            log_error(format!("コピーを消せません: {:?} | {}", dest, e));
+        }
+        bail!("移動元を消せません: {:?} | {}", src, e);
+    }
+    Ok(())
+}
+
+/// ファイルを移す（rename できない別ドライブへはコピーして照合してから元を消す）
+pub fn move_file(src: &Path, dest: &Path) -> Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).with_location()?;
+    }
+    if dest.exists() {
+        bail!("移動先に同じ名前のファイルがあります: {:?}", dest);
+    }
+    match fs::rename(src, dest) {
+        Ok(()) => Ok(()),
+        Err(e) if is_cross_device(&e) => move_by_copy(src, dest, |src| fs::remove_file(src)),
+        Err(e) => bail!("ファイルを移動できません: {:?} | {}", src, e),
+    }
+}
+
+/// 本体とうごイラの元 zip を移す（zip が移せなければ本体も戻す）
+fn move_with_zip(library: &LibraryPaths, row: &MoveIllustFiles) -> Result<()> {
+    let src = library.file_path(&row.src_dir, &row.file_name);
+    let dest = library.file_path(&row.dest_dir, &row.file_name);
+    move_file(&src, &dest)?;
+
+    if let Some(zip_name) = &row.zip_name {
+        let src_zip = library.file_path(&row.src_dir, zip_name);
+        if src_zip.exists() {
+            if let Err(e) = move_file(&src_zip, &library.file_path(&row.dest_dir, zip_name)) {
+                if let Err(e) = move_file(&dest, &src) {
+                    log_error(format!("ファイルを戻せません: {:?} | {}", dest, e));
+                }
+                bail!("zip を移動できません: {:?} | {}", src_zip, e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn set_state(pool: &SqlitePool, id: i64, state: MoveState) -> Result<()> {
+    sqlx::query("UPDATE MOVE_JOURNAL SET state = ? WHERE id = ?")
+        .bind(state as i64)
+        .bind(id)
+        .execute(pool)
+        .await
+        .with_location()?;
+    Ok(())
+}
+
+/// ファイルを移してから save_dir を書き換える
+///
+/// 移動予定 → 移動済み → 反映済み の順に MOVE_JOURNAL に残し、途中で止まっても
+/// 起動時の reconcile_file_moves で DB とファイルをそろえられるようにする。
+/// 移動できなかったファイルは理由と一緒に返す
+pub async fn process_file_moves(
+    pool: &SqlitePool,
+    moves: Vec<MoveIllustFiles>,
+    sink: Option<&dyn MoveProgressSink>,
+) -> Result<Vec<MoveFailure>> {
+    let mut failures = vec![];
+
+    // 1. 移動予定を記録
+    let mut planned = vec![];
+    let mut tx = pool.begin().await.with_location()?;
+    let library = load_library_paths(&mut tx).await?;
+    for row in moves {
+        let src = library.file_path(&row.src_dir, &row.file_name);
+        let dest = library.file_path(&row.dest_dir, &row.file_name);
+        if src == dest {
+            continue;
+        }
+        if !src.is_file() {
+            failures.push(failure(&library, &row, "移動元にファイルがありません"));
+            continue;
+        }
+        if dest.exists() {
+            failures.push(failure(
+                &library,
+                &row,
+                "移動先に同じ名前のファイルがあります",
+            ));
+            continue;
+        }
+
+        let id = sqlx::query(
+            "INSERT INTO MOVE_JOURNAL
+             (illust_id, suffix, file_name, zip_name, src_dir, dest_dir, state, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(row.illust_id)
+        .bind(row.suffix)
+        .bind(&row.file_name)
+        .bind(&row.zip_name)
+        .bind(&row.src_dir)
+        .bind(&row.dest_dir)
+        .bind(MoveState::Planned as i64)
+        .bind(Utc::now().timestamp())
+        .execute(&mut *tx)
+        .await
+        .with_location()?
+        .last_insert_rowid();
+        planned.push((id, row));
+    }
+    tx.commit().await.with_location()?;
+
+    // 2. ファイルを移動（照合が済むまで DB は書き換えない）
+    let mut moved = vec![];
+    let total = planned.len() as u32;
+    for (i, (id, row)) in planned.into_iter().enumerate() {
+        if let Some(sink) = sink {
+            let progress = MoveProgress {
+                current: i as u32 + 1,
+                total,
+                file_name: row.file_name.clone(),
+            };
+            if let Err(e) = sink.report(&progress) {
+                log_error(e);
+            }
+        }
+
+        // 別ドライブへのコピーは時間がかかるので実行スレッドを塞がない
+        let (paths, target) = (library.clone(), row.clone());
+        let result = tauri::async_runtime::spawn_blocking(move || move_with_zip(&paths, &target))
+            .await
+            .with_location()?;
+        match result {
+            Ok(()) => {
+                set_state(pool, id, MoveState::Moved).await?;
+                moved.push((id, row));
+            }
+            Err(e) => {
+                sqlx::query("DELETE FROM MOVE_JOURNAL WHERE id = ?")
+                    .bind(id)
+                    .execute(pool)
+                    .await
+                    .with_location()?;
+                failures.push(failure(&library, &row, e));
+            }
+        }
+    }
+
+    // 3. 移動できたものだけ DB に反映
+    let mut tx = pool.begin().await.with_location()?;
+    for (id, row) in &moved {
+        sqlx::query(
+            "UPDATE ILLUST_INFO SET save_dir = ?
+             WHERE illust_id = ? AND suffix = ? AND save_dir = ?",
+        )
+        .bind(&row.dest_dir)
+        .bind(row.illust_id)
+        .bind(row.suffix)
+        .bind(&row.src_dir)
+        .execute(&mut *tx)
+        .await
+        .with_location()?;
+        sqlx::query("UPDATE MOVE_JOURNAL SET state = ? WHERE id = ?")
+            .bind(MoveState::Committed as i64)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .with_location()?;
+    }
+    tx.commit().await.with_location()?;
+
+    sqlx::query("DELETE FROM MOVE_JOURNAL WHERE state = ?")
+        .bind(MoveState::Committed as i64)
+        .execute(pool)
+        .await
+        .with_location()?;
+
+    for f in &failures {
+        log_error(format!(
+            "ファイル移動失敗: {} {} → {} | {}",
+            f.file_name, f.src_dir, f.dest_dir, f.reason
+        ));
+    }
+
+    Ok(failures)
+}
+
+/// 片方にだけあるファイルを移す（既に移っていれば何もしない）
+fn settle_file(from: &Path, to: &Path) -> Result<()> {
+    if from.exists() && !to.exists() {
+        move_file(from, to)?;
+    }
+    Ok(())
+}
+
+/// コピーを終えて元を消す前に止まったか（両方にあり内容が同じ）
+fn copied_before_removal(src: &Path, dest: &Path) -> bool {
+    src.is_file()
+        && dest.is_file()
+        && matches!((hash_file(src), hash_file(dest)), (Ok(a), Ok(b)) if a == b)
+}
+
+/// 途中で止まった移動を片付ける（起動時）
+///
+/// 移動先にファイルがあれば移動を終わらせて DB に反映し、移動元に残っていれば取りやめる
+pub async fn reconcile_file_moves(conn: &mut SqliteConnection) -> Result<u32> {
+    let rows: Vec<MoveJournalRow> = sqlx::query_as(
+        "SELECT id, illust_id, suffix, file_name, zip_name, src_dir, dest_dir, state
+         FROM MOVE_JOURNAL ORDER BY id",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .with_location()?;
+
+    let library = load_library_paths(&mut *conn).await?;
+    let mut reconciled = 0;
+    for row in rows {
+        let src = library.file_path(&row.src_dir, &row.file_name);
+        let dest = library.file_path(&row.dest_dir, &row.file_name);
+        let src_zip = row
+            .zip_name
+            .as_ref()
+            .map(|z| library.file_path(&row.src_dir, z));
+        let dest_zip = row
+            .zip_name
+            .as_ref()
+            .map(|z| library.file_path(&row.dest_dir, z));
+
+        // コピー途中のファイルは使わない
+        let part = part_path(&dest);
+        if part.exists() {
+            if let Err(e) = fs::remove_file(&part) {
+                log_error(format!(
+                    "コピー途中のファイルを消せません: {:?} | {}",
+                    part, e
+                ));
+            }
+        }
+        if row.state != MoveState::Committed && copied_before_removal(&src, &dest) {
+            if let Err(e) = fs::remove_file(&src) {
+                log_error(format!("移動元を消せません: {:?} | {}", src, e));
+            }
+        }
+
+        if row.state == MoveState::Committed {
+            // DB には反映済み
+        } else if dest.is_file() && !src.exists() {
+            // 移動は終わっているので DB に反映する
+            if let (Some(src_zip), Some(dest_zip)) = (&src_zip, &dest_zip) {
+                if let Err(e) = settle_file(src_zip, dest_zip) {
+                    log_error(format!("zip を移動できません: {:?} | {}", src_zip, e));
+                }
+            }
+            sqlx::query(
+                "UPDATE ILLUST_INFO SET save_dir = ?
+                 WHERE illust_id = ? AND suffix = ? AND save_dir = ?",
+            )
+            .bind(&row.dest_dir)
+            .bind(row.illust_id)
+            .bind(row.suffix)
+            .bind(&row.src_dir)
+            .execute(&mut *conn)
+            .await
+            .with_location()?;
+        } else if src.is_file() {
+            // 移動前に止まったので元に戻す
+            if let (Some(src_zip), Some(dest_zip)) = (&src_zip, &dest_zip) {
+                if let Err(e) = settle_file(dest_zip, src_zip) {
+                    log_error(format!("zip を戻せません: {:?} | {}", dest_zip, e));
+                }
+            }
+        } else {
+            log_error(format!(
+                "移動中のファイルが見つかりません: {:?} → {:?}",
+                src, dest
+            ));
+        }
+
+        sqlx::query("DELETE FROM MOVE_JOURNAL WHERE id = ?")
+            .bind(row.id)
+            .execute(&mut *conn)
+            .await
+            .with_location()?;
+        reconciled += 1;
+    }
+
+    Ok(reconciled)
+}
@@ -0,0 +1,203 @@
+use anyhow::Result;
+use regex::Regex;
+use sqlx::SqliteConnection;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::constants::{DEFAULT_FILE_NAME_PATTERNS, FILE_NAME_PATTERNS};
+use crate::errors::parse::FileParseError;
+use crate::models::common::FileInfo;
+use crate::models::settings::FileNamePreview;
+use crate::util::{log_error, ResultWithLocationExt};
+
+/// ファイル名から作品ID・ページ番号を読み取るパターンの一覧
+///
+/// 各パターンはファイル名全体に一致させる。名前付きキャプチャ `id` は必須で、
+/// `page` が無ければ 0、`ext` が無ければ実際の拡張子を使う
+pub struct FileNamePatterns {
+    patterns: Vec<Regex>,
+}
+
+impl FileNamePatterns {
+    pub fn compile<S: AsRef<str>>(sources: &[S]) -> Result<Self, FileParseError> {
+        if sources.is_empty() {
+            return Err(FileParseError::InvalidPattern(
+                "パターンが1つもありません".to_string(),
+            ));
+        }
+
+        let mut patterns = Vec::with_capacity(sources.len());
+        for source in sources {
+            let source = source.as_ref();
+            let reg = Regex::new(&format!("^(?:{})$", source))
+                .map_err(|e| FileParseError::RegexCompileError(e.to_string()))?;
+            if !reg.capture_names().flatten().any(|name| name == "id") {
+                return Err(FileParseError::InvalidPattern(format!(
+                    "id のキャプチャがありません: {}",
+                    source
+                )));
+            }
+            patterns.push(reg);
+        }
+
+        Ok(Self { patterns })
+    }
+
+    pub fn defaults() -> Self {
+        Self::compile(DEFAULT_FILE_NAME_PATTERNS).expect("既定のファイル名パターンが不正です")
+    }
+
+    /// 最初に一致したパターンで解析する
+    pub fn parse(&self, file_name: &str) -> Result<FileInfo, FileParseError> {
+        let (reg, caps) = self
+            .patterns
+            .iter()
+            .find_map(|reg| reg.captures(file_name).map(|caps| (reg, caps)))
+            .ok_or_else(|| FileParseError::InvalidFormat(file_name.to_string()))?;
+
+        let id = caps
+            .name("id")
+            .ok_or_else(|| FileParseError::InvalidFormat(file_name.to_string()))?
+            .as_str();
+        let illust_id = id
+            .parse::<i32>()
+            .map_err(|_| FileParseError::InvalidIllustId(id.to_string()))?;
+
+        let suffix = match caps.name("page") {
+            Some(page) => page
+                .as_str()
+                .parse::<i16>()
+                .map_err(|_| FileParseError::InvalidSuffix(page.as_str().to_string()))?,
+            None => 0,
+        };
+
+        let extension = match caps.name("ext") {
+            Some(ext) => ext.as_str().to_string(),
+            None => Path::new(file_name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(str::to_string)
+                .ok_or_else(|| FileParseError::InvalidFormat(file_name.to_string()))?,
+        };
+
+        let extras = reg
+            .capture_names()
+            .flatten()
+            .filter(|name| !matches!(*name, "id" | "page" | "ext"))
+            .filter_map(|name| {
+                caps.name(name)
+                    .map(|m| (name.to_string(), m.as_str().to_string()))
+            })
+            .collect();
+
+        Ok(FileInfo {
+            illust_id,
+            suffix,
+            extension,
+            file_name: file_name.to_string(),
+            save_dir: None,
+            extras,
+        })
+    }
+}
+
+fn active() -> &'static RwLock<Arc<FileNamePatterns>> {
+    static ACTIVE: OnceLock<RwLock<Arc<FileNamePatterns>>> = OnceLock::new();
+    ACTIVE.get_or_init(|| RwLock::new(Arc::new(FileNamePatterns::defaults())))
+}
+
+/// 現在有効なパターン（解析の途中で設定が変わっても同じものを使えるよう Arc で返す）
+pub fn current_patterns() -> Arc<FileNamePatterns> {
+    active().read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn set_current_patterns(patterns: FileNamePatterns) {
+    *active().write().unwrap_or_else(|e| e.into_inner()) = Arc::new(patterns);
+}
+
+/// 保存済みのパターン（未設定なら既定値）
+pub async fn get_file_name_patterns(conn: &mut SqliteConnection) -> Result<Vec<String>> {
+    let value: Option<String> = sqlx::query_scalar("SELECT value FROM COMMON_MST WHERE key = ?")
+        .bind(FILE_NAME_PATTERNS)
+        .fetch_optional(&mut *conn)
+        .await
+        .with_location()?
+        .flatten();
+
+    match value {
+        Some(value) => serde_json::from_str(&value).with_location(),
+        None => Ok(DEFAULT_FILE_NAME_PATTERNS
+            .iter()
+            .map(|s| s.to_string())
+            .collect()),
+    }
+}
+
+/// 検証してから保存し、以降の解析に反映する
+pub async fn save_file_name_patterns(
+    conn: &mut SqliteConnection,
+    sources: Vec<String>,
+) -> Result<()> {
+    let sources: Vec<String> = sources
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    let patterns = FileNamePatterns::compile(&sources).with_location()?;
+
+    sqlx::query("INSERT OR REPLACE INTO COMMON_MST (key, value) VALUES (?, ?)")
+        .bind(FILE_NAME_PATTERNS)
+        .bind(serde_json::to_string(&sources).with_location()?)
+        .execute(&mut *conn)
+        .await
+        .with_location()?;
+
+    set_current_patterns(patterns);
+
+    Ok(())
+}
+
+/// 起動時にDBの設定を反映する（壊れていれば既定値のまま）
+pub async fn load_file_name_patterns(conn: &mut SqliteConnection) -> Result<()> {
+    let sources = get_file_name_patterns(&mut *conn).await?;
+    match FileNamePatterns::compile(&sources) {
+        Ok(patterns) => set_current_patterns(patterns),
+        Err(e) => {
+            log_error(format!("保存済みのファイル名パターンを使えません: {}", e));
+            set_current_patterns(FileNamePatterns::defaults());
+        }
+    }
+
+    Ok(())
+}
+
+/// 保存前の確認用に、指定したパターンで解析した結果を返す
+pub fn preview_file_names(
+    sources: &[String],
+    file_names: &[String],
+) -> Result<Vec<FileNamePreview>> {
+    let patterns = FileNamePatterns::compile(sources).with_location()?;
+
+    Ok(file_names
+        .iter()
+        .map(|name| match patterns.parse(name) {
+            Ok(info) => FileNamePreview {
+                file_name: name.clone(),
+                illust_id: Some(info.illust_id),
+                suffix: Some(info.suffix),
+                extension: Some(info.extension),
+                extras: info.extras,
+                error: None,
+            },
+            Err(e) => FileNamePreview {
+                file_name: name.clone(),
+                illust_id: None,
+                suffix: None,
+                extension: None,
+                extras: HashMap::new(),
+                error: Some(e.to_string()),
+            },
+        })
+        .collect())
+}
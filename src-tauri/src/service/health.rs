@@ -0,0 +1,434 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use image::ImageFormat;
+use rayon::prelude::*;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::models::catalog::{FileHealth, FileHealthStatus, FileLocation, QuarantineStats};
+use crate::models::fetch::FileDetail;
+use crate::service::duplicate::{
+    file_stamp, forget_file_caches, rename_file_caches, unregister_file, FileKey,
+};
+use crate::service::file_move::move_file;
+use crate::service::library::{load_library_paths, LibraryPaths};
+use crate::util::{log_error, ResultWithLocationExt};
+
+/// 検査する拡張子（うごイラの zip も含む）
+const CHECK_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "zip"];
+
+fn normalize_extension(extension: &str) -> String {
+    match extension.to_lowercase().as_str() {
+        "jpeg" => "jpg".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn is_check_target(file_name: &str) -> bool {
+    Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| CHECK_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// 末尾まで書き込まれているか（途中で切れたダウンロードの検知）
+fn has_trailer(format: ImageFormat, bytes: &[u8]) -> bool {
+    // 末尾の 0 埋めは無視する
+    let end = bytes.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    let bytes = &bytes[..end];
+    let tail = &bytes[bytes.len().saturating_sub(1024)..];
+    match format {
+        // 圧縮データ中の 0xFF は必ず 0xFF00 になるので EOI は末尾付近にしか現れない
+        ImageFormat::Jpeg => tail.windows(2).any(|w| w == [0xFF, 0xD9]),
+        ImageFormat::Png => tail.windows(4).any(|w| w == b"IEND"),
+        ImageFormat::Gif => bytes.last() == Some(&0x3B),
+        ImageFormat::WebP => {
+            bytes.len() >= 8
+                && u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize + 8
+                    <= bytes.len()
+        }
+        _ => true,
+    }
+}
+
+fn format_extension(format: ImageFormat) -> String {
+    match format {
+        ImageFormat::Jpeg => "jpg".to_string(),
+        other => other
+            .extensions_str()
+            .first()
+            .map(|e| e.to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// 中身を読み、マジックバイト・寸法・末尾まで読めるかを調べる
+pub fn inspect_file(library: &LibraryPaths, save_dir: &str, file_name: &str) -> Result<FileHealth> {
+    let bytes = fs::read(library.file_path(save_dir, file_name)).with_location()?;
+    let extension = Path::new(file_name)
+        .extension()
+        .map(|e| normalize_extension(&e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut health = FileHealth {
+        save_dir: save_dir.to_string(),
+        file_name: file_name.to_string(),
+        status: FileHealthStatus::Ok,
+        detected_format: None,
+        width: None,
+        height: None,
+        message: None,
+    };
+
+    if bytes.starts_with(b"PK\x03\x04") {
+        health.detected_format = Some("zip".to_string());
+        if let Err(e) = zip::ZipArchive::new(Cursor::new(&bytes)) {
+            health.status = FileHealthStatus::Truncated;
+            health.message = Some(e.to_string());
+        } else if extension != "zip" {
+            health.status = FileHealthStatus::Mislabeled;
+        }
+        return Ok(health);
+    }
+
+    let Ok(format) = image::guess_format(&bytes) else {
+        health.status = FileHealthStatus::NotImage;
+        let text = String::from_utf8_lossy(&bytes[..bytes.len().min(64)]).to_lowercase();
+        health.message = Some(if bytes.is_empty() {
+            "空のファイルです".to_string()
+        } else if text.trim_start().starts_with('<') {
+            "HTML などのテキストです".to_string()
+        } else {
+            "画像の形式を判別できません".to_string()
+        });
+        return Ok(health);
+    };
+    let detected = format_extension(format);
+    health.detected_format = Some(detected.clone());
+
+    // 寸法はヘッダだけで分かるので、途中で切れていても返す
+    if let Ok((width, height)) =
+        image::ImageReader::with_format(Cursor::new(&bytes), format).into_dimensions()
+    {
+        health.width = Some(width);
+        health.height = Some(height);
+    }
+
+    if !has_trailer(format, &bytes) {
+        health.status = FileHealthStatus::Truncated;
+        health.message = Some("ファイルの末尾がありません".to_string());
+        return Ok(health);
+    }
+    if let Err(e) = image::load_from_memory_with_format(&bytes, format) {
+        health.status = FileHealthStatus::Truncated;
+        health.message = Some(e.to_string());
+        return Ok(health);
+    }
+
+    if detected != extension {
+        health.status = FileHealthStatus::Mislabeled;
+        health.message = Some(format!("中身は {} です", detected));
+    }
+
+    Ok(health)
+}
+
+/// 指定ファイルの検査結果を返す（サイズと更新日時が前回と同じなら前回の結果を使う）
+pub async fn ensure_file_health(
+    conn: &mut SqliteConnection,
+    files: &[FileKey],
+) -> Result<Vec<FileHealth>> {
+    let library = load_library_paths(&mut *conn).await?;
+    let mut results = Vec::new();
+    let mut stale: Vec<(FileKey, i64, i64)> = Vec::new();
+
+    for (save_dir, file_name) in files {
+        if !is_check_target(file_name) {
+            continue;
+        }
+        let Some((file_size, modified_at)) = file_stamp(&library.file_path(save_dir, file_name))
+        else {
+            forget_file_caches(&mut *conn, save_dir, file_name).await?;
+            continue;
+        };
+
+        let cached: Option<FileHealth> = sqlx::query_as(
+            "SELECT save_dir, file_name, status, detected_format, width, height, message
+               FROM FILE_HEALTH
+              WHERE save_dir = ? AND file_name = ? AND file_size = ? AND modified_at = ?",
+        )
+        .bind(save_dir)
+        .bind(file_name)
+        .bind(file_size)
+        .bind(modified_at)
+        .fetch_optional(&mut *conn)
+        .await
+        .with_location()?;
+
+        match cached {
+            Some(health) => results.push(health),
+            None => stale.push((
+                (save_dir.clone(), file_name.clone()),
+                file_size,
+                modified_at,
+            )),
+        }
+    }
+
+    // 全体のデコードを伴うのでブロッキング用のスレッドで並列に行う
+    let inspected = tauri::async_runtime::spawn_blocking(move || {
+        stale
+            .into_par_iter()
+            .map(|(key, file_size, modified_at)| {
+                let health = inspect_file(&library, &key.0, &key.1);
+                (key, file_size, modified_at, health)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .with_location()?;
+
+    let checked_at = Utc::now().timestamp();
+    for (key, file_size, modified_at, health) in inspected {
+        let health = match health {
+            Ok(health) => health,
+            Err(e) => {
+                log_error(format!("ファイル検査失敗: {}/{} | {}", key.0, key.1, e));
+                continue;
+            }
+        };
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO FILE_HEALTH
+                 (save_dir, file_name, file_size, modified_at, status, detected_format,
+                  width, height, message, checked_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&health.save_dir)
+        .bind(&health.file_name)
+        .bind(file_size)
+        .bind(modified_at)
+        .bind(health.status as i64)
+        .bind(&health.detected_format)
+        .bind(health.width)
+        .bind(health.height)
+        .bind(&health.message)
+        .bind(checked_at)
+        .execute(&mut *conn)
+        .await
+        .with_location()?;
+
+        results.push(health);
+    }
+
+    Ok(results)
+}
+
+/// 取り込み前の検査（count_files_in_dir から呼ぶ）
+pub async fn process_check_file_details(
+    pool: &SqlitePool,
+    details: &[FileDetail],
+) -> Result<Vec<FileHealth>> {
+    let mut conn = pool.acquire().await.with_location()?;
+    let files: Vec<FileKey> = details
+        .iter()
+        .map(|d| (d.save_dir.clone(), d.file_name.clone()))
+        .collect();
+
+    ensure_file_health(&mut conn, &files).await
+}
+
+/// フォルダ内（未指定なら登録済み）のファイルを検査し、問題のあるものを返す
+pub async fn process_verify_files(
+    pool: &SqlitePool,
+    folders: Vec<String>,
+) -> Result<Vec<FileHealth>> {
+    let mut conn = pool.acquire().await.with_location()?;
+    let library = load_library_paths(&mut conn).await?;
+
+    let files: Vec<FileKey> = if folders.is_empty() {
+        sqlx::query_as("SELECT save_dir, file_name FROM ILLUST_INFO WHERE save_dir IS NOT NULL")
+            .fetch_all(&mut *conn)
+            .await
+            .with_location()?
+    } else {
+        folders
+            .iter()
+            .flat_map(|folder| WalkDir::new(folder).into_iter().filter_map(|e| e.ok()))
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| {
+                let save_dir = library.to_stored_dir(&e.path().parent()?.to_string_lossy());
+                Some((save_dir, e.file_name().to_string_lossy().to_string()))
+            })
+            .collect()
+    };
+
+    let mut results: Vec<FileHealth> = ensure_file_health(&mut conn, &files)
+        .await?
+        .into_iter()
+        .filter(|h| h.status != FileHealthStatus::Ok)
+        .collect();
+    results.sort_by(|a, b| (&a.save_dir, &a.file_name).cmp(&(&b.save_dir, &b.file_name)));
+
+    Ok(results)
+}
+
+/// 拡張子を中身の形式に合わせて付け直し、登録も更新する
+pub async fn process_fix_file_extension(
+    pool: &SqlitePool,
+    file: FileLocation,
+) -> Result<FileLocation> {
+    let detected: Option<Option<String>> = sqlx::query_scalar(
+        "SELECT detected_format FROM FILE_HEALTH
+          WHERE save_dir = ? AND file_name = ? AND status = ?",
+    )
+    .bind(&file.save_dir)
+    .bind(&file.file_name)
+    .bind(FileHealthStatus::Mislabeled as i64)
+    .fetch_optional(pool)
+    .await
+    .with_location()?;
+    let Some(Some(detected)) = detected else {
+        bail!("拡張子の誤りが検出されていません: {}", file.file_name);
+    };
+
+    let new_file_name = Path::new(&file.file_name)
+        .with_extension(&detected)
+        .to_string_lossy()
+        .to_string();
+    let mut conn = pool.acquire().await.with_location()?;
+    let library = load_library_paths(&mut conn).await?;
+    drop(conn);
+    let src = library.file_path(&file.save_dir, &file.file_name);
+    let dest = library.file_path(&file.save_dir, &new_file_name);
+    if dest.exists() {
+        bail!("同じ名前のファイルが既にあります: {:?}", dest);
+    }
+
+    fs::rename(&src, &dest).with_location()?;
+
+    let result = rename_registration(pool, &file, &new_file_name, &detected).await;
+    if let Err(e) = result {
+        // 登録を更新できなければ名前を戻す
+        if let Err(e) = fs::rename(&dest, &src) {
+            log_error(format!("ファイル名を戻せません: {:?} | {}", dest, e));
+        }
+        return Err(e);
+    }
+
+    Ok(FileLocation {
+        save_dir: file.save_dir,
+        file_name: new_file_name,
+    })
+}
+
+async fn rename_registration(
+    pool: &SqlitePool,
+    file: &FileLocation,
+    new_file_name: &str,
+    extension: &str,
+) -> Result<()> {
+    let mut tx = pool.begin().await.with_location()?;
+
+    // 取得待ちのワークも合わせて直す
+    for table in ["ILLUST_INFO", "ILLUST_FETCH_WORK"] {
+        sqlx::query(&format!(
+            "UPDATE {} SET file_name = ?, extension = ? WHERE save_dir = ? AND file_name = ?",
+            table
+        ))
+        .bind(new_file_name)
+        .bind(extension)
+        .bind(&file.save_dir)
+        .bind(&file.file_name)
+        .execute(&mut *tx)
+        .await
+        .with_location()?;
+    }
+
+    rename_file_caches(&mut tx, &file.save_dir, &file.file_name, new_file_name).await?;
+    sqlx::query(
+        "UPDATE FILE_HEALTH SET status = ?, message = NULL WHERE save_dir = ? AND file_name = ?",
+    )
+    .bind(FileHealthStatus::Ok as i64)
+    .bind(&file.save_dir)
+    .bind(new_file_name)
+    .execute(&mut *tx)
+    .await
+    .with_location()?;
+
+    tx.commit().await.with_location()?;
+
+    Ok(())
+}
+
+/// 壊れたファイルを退避フォルダへ移し、登録から外す
+///
+/// 退避先では元のフォルダ名が分からなくなるため、ファイル名の先頭に時刻を付ける。
+/// 先にファイルを移し、移せたものだけ登録から外す（移せなかったものは結果で返す）
+pub async fn process_quarantine_files(
+    pool: &SqlitePool,
+    files: Vec<FileLocation>,
+    quarantine_dir: &Path,
+) -> Result<QuarantineStats> {
+    fs::create_dir_all(quarantine_dir).with_location()?;
+
+    let mut conn = pool.acquire().await.with_location()?;
+    let library = load_library_paths(&mut conn).await?;
+    drop(conn);
+
+    let mut stats = QuarantineStats::default();
+    let mut moved = Vec::new();
+
+    for file in files {
+        let src = library.file_path(&file.save_dir, &file.file_name);
+        if !src.is_file() {
+            continue;
+        }
+        let dest: PathBuf = quarantine_dir.join(format!(
+            "{}_{}",
+            Utc::now().timestamp_millis(),
+            file.file_name
+        ));
+
+        // 別ドライブへはコピーして照合してから消す
+        match move_file(&src, &dest) {
+            Ok(()) => moved.push(file),
+            Err(e) => {
+                log_error(format!("退避できません: {:?} | {}", src, e));
+                stats.failed_files.push(file.file_name);
+            }
+        }
+    }
+
+    let mut tx = pool.begin().await.with_location()?;
+
+    for file in &moved {
+        let registered: Option<(i32, i32)> = sqlx::query_as(
+            "SELECT illust_id, suffix FROM ILLUST_INFO WHERE save_dir = ? AND file_name = ?",
+        )
+        .bind(&file.save_dir)
+        .bind(&file.file_name)
+        .fetch_optional(&mut *tx)
+        .await
+        .with_location()?;
+        if let Some((illust_id, suffix)) = registered {
+            unregister_file(&mut tx, illust_id, suffix).await?;
+        }
+        sqlx::query("DELETE FROM ILLUST_FETCH_WORK WHERE save_dir = ? AND file_name = ?")
+            .bind(&file.save_dir)
+            .bind(&file.file_name)
+            .execute(&mut *tx)
+            .await
+            .with_location()?;
+        forget_file_caches(&mut tx, &file.save_dir, &file.file_name).await?;
+    }
+
+    tx.commit().await.with_location()?;
+
+    stats.moved = moved.len() as u32;
+
+    Ok(stats)
+}
@@ -0,0 +1,527 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::constants::JOURNAL_LIMIT;
+use crate::models::catalog::{
+    JournalCharacter, JournalDetail, JournalFile, JournalIllust, JournalState, JournalTag,
+    JournalUgoira, OperationEntry, OperationEntryRaw, OperationKind,
+};
+use crate::service::common::parse_file_info;
+use crate::service::file_move::move_file;
+use crate::service::library::{load_library_paths, LibraryPaths};
+use crate::util::{log_error, ResultWithLocationExt};
+
+/// 作品単位で持つテーブル（元に戻すときは作品ごと入れ替える）
+const ILLUST_TABLES: &[&str] = &["ILLUST_INFO", "ILLUST_DETAIL", "TAG_INFO", "UGOIRA_INFO"];
+
+/// 操作前の状態（操作後に finish_operation で履歴にする）
+pub struct PendingOperation {
+    file_count: u32,
+    before: JournalState,
+}
+
+fn id_list(illust_ids: &[i32]) -> String {
+    illust_ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// 対象作品の登録内容と、ファイルの置き場所を取っておく
+async fn snapshot(
+    conn: &mut SqliteConnection,
+    illust_ids: &[i32],
+    entity_keys: &[String],
+) -> Result<JournalState> {
+    let mut state = JournalState {
+        illust_ids: illust_ids.to_vec(),
+        entity_keys: entity_keys.to_vec(),
+        ..Default::default()
+    };
+    let library = load_library_paths(&mut *conn).await?;
+    for entity_key in entity_keys {
+        let character: Option<JournalCharacter> = sqlx::query_as(
+            "SELECT entity_key, series, character, collect_dir FROM CHARACTER_INFO
+             WHERE entity_key = ?",
+        )
+        .bind(entity_key)
+        .fetch_optional(&mut *conn)
+        .await
+        .with_location()?;
+        state.characters.extend(character);
+    }
+    if illust_ids.is_empty() {
+        return Ok(state);
+    }
+    let ids = id_list(illust_ids);
+
+    state.illusts = sqlx::query_as(&format!(
+        "SELECT illust_id, suffix, cnum, extension, save_dir, file_name FROM ILLUST_INFO
+         WHERE illust_id IN ({ids}) ORDER BY illust_id, suffix"
+    ))
+    .fetch_all(&mut *conn)
+    .await
+    .with_location()?;
+    state.details = sqlx::query_as(&format!(
+        "SELECT illust_id, cnum, author_id, series, character, created_at FROM ILLUST_DETAIL
+         WHERE illust_id IN ({ids}) ORDER BY illust_id, cnum"
+    ))
+    .fetch_all(&mut *conn)
+    .await
+    .with_location()?;
+    state.tags = sqlx::query_as(&format!(
+        "SELECT illust_id, cnum, tag FROM TAG_INFO
+         WHERE illust_id IN ({ids}) ORDER BY illust_id, cnum, tag"
+    ))
+    .fetch_all(&mut *conn)
+    .await
+    .with_location()?;
+    state.ugoira = sqlx::query_as(&format!(
+        "SELECT illust_id, zip_name, format, converted_at FROM UGOIRA_INFO
+         WHERE illust_id IN ({ids}) ORDER BY illust_id"
+    ))
+    .fetch_all(&mut *conn)
+    .await
+    .with_location()?;
+
+    for illust in &state.illusts {
+        if let (Some(save_dir), Some(file_name)) = (&illust.save_dir, &illust.file_name) {
+            state.files.push(JournalFile {
+                file_name: file_name.clone(),
+                save_dir: Some(save_dir.clone()),
+                held_path: None,
+            });
+        }
+    }
+    // うごイラの元 zip は 1枚目と同じフォルダにある
+    for ugoira in &state.ugoira {
+        let save_dir = state
+            .illusts
+            .iter()
+            .find(|i| i.illust_id == ugoira.illust_id && i.suffix == 0)
+            .and_then(|i| i.save_dir.clone());
+        if let Some(save_dir) = save_dir {
+            if library.file_path(&save_dir, &ugoira.zip_name).is_file() {
+                state.files.push(JournalFile {
+                    file_name: ugoira.zip_name.clone(),
+                    save_dir: Some(save_dir),
+                    held_path: None,
+                });
+            }
+        }
+    }
+
+    Ok(state)
+}
+
+/// 登録内容が同じか（ファイルの置き場所は登録内容から決まる）
+fn same_rows(a: &JournalState, b: &JournalState) -> bool {
+    a.illusts == b.illusts
+        && a.details == b.details
+        && a.tags == b.tags
+        && a.ugoira == b.ugoira
+        && a.characters == b.characters
+}
+
+/// 操作の前に対象作品の状態を取っておく
+pub async fn begin_operation(pool: &SqlitePool, file_names: &[String]) -> Result<PendingOperation> {
+    begin_label_operation(pool, file_names, None).await
+}
+
+/// 名付けの前に対象作品と CHARACTER_INFO の状態を取っておく
+pub async fn begin_label_operation(
+    pool: &SqlitePool,
+    file_names: &[String],
+    character_name: Option<&str>,
+) -> Result<PendingOperation> {
+    let mut illust_ids = BTreeSet::new();
+    for file_name in file_names {
+        illust_ids.insert(parse_file_info(file_name).with_location()?.illust_id);
+    }
+    let illust_ids: Vec<i32> = illust_ids.into_iter().collect();
+
+    let entity_keys: Vec<String> = character_name.map(str::to_string).into_iter().collect();
+
+    let mut conn = pool.acquire().await.with_location()?;
+    let before = snapshot(&mut conn, &illust_ids, &entity_keys).await?;
+
+    Ok(PendingOperation {
+        file_count: file_names.len() as u32,
+        before,
+    })
+}
+
+/// 操作後の状態と合わせて履歴に記録する（何も変わっていなければ記録しない）
+///
+/// 操作が途中で失敗しても、反映済みの分を取り消せるよう呼び出す
+/// 新しい操作を記録すると、取り消し済みの操作はやり直せなくなる
+pub async fn finish_operation(
+    pool: &SqlitePool,
+    pending: PendingOperation,
+    kind: OperationKind,
+    detail: Option<&str>,
+    held_files: Vec<JournalFile>,
+) -> Result<Option<i64>> {
+    let mut tx = pool.begin().await.with_location()?;
+    let before = &pending.before;
+    let mut after = snapshot(&mut tx, &before.illust_ids, &before.entity_keys).await?;
+    if held_files.is_empty() && same_rows(before, &after) {
+        return Ok(None);
+    }
+    after.files.extend(held_files);
+
+    sqlx::query("DELETE FROM OPERATION_JOURNAL WHERE undone = 1")
+        .execute(&mut *tx)
+        .await
+        .with_location()?;
+    let id = sqlx::query(
+        "INSERT INTO OPERATION_JOURNAL
+         (kind, file_count, detail, before_state, after_state, undone, created_at)
+         VALUES (?, ?, ?, ?, ?, 0, ?)",
+    )
+    .bind(kind as i64)
+    .bind(pending.file_count)
+    .bind(detail)
+    .bind(serde_json::to_string(&pending.before).with_location()?)
+    .bind(serde_json::to_string(&after).with_location()?)
+    .bind(Utc::now().timestamp())
+    .execute(&mut *tx)
+    .await
+    .with_location()?
+    .last_insert_rowid();
+
+    // 古い履歴は消し、退避していたファイルをゴミ箱へ送る
+    let expired: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT id, after_state FROM OPERATION_JOURNAL ORDER BY id DESC LIMIT -1 OFFSET ?",
+    )
+    .bind(JOURNAL_LIMIT)
+    .fetch_all(&mut *tx)
+    .await
+    .with_location()?;
+    for (expired_id, _) in &expired {
+        sqlx::query("DELETE FROM OPERATION_JOURNAL WHERE id = ?")
+            .bind(expired_id)
+            .execute(&mut *tx)
+            .await
+            .with_location()?;
+    }
+
+    tx.commit().await.with_location()?;
+
+    for (_, after_state) in expired {
+        let state: JournalState = serde_json::from_str(&after_state).with_location()?;
+        for held in state.files.iter().filter_map(|f| f.held_path.as_ref()) {
+            if Path::new(held).exists() {
+                if let Err(e) = trash::delete(held) {
+                    log_error(format!("ゴミ箱への移動失敗: {} | {}", held, e));
+                }
+            }
+        }
+    }
+
+    Ok(Some(id))
+}
+
+fn location(library: &LibraryPaths, file: &JournalFile) -> Option<PathBuf> {
+    match (&file.held_path, &file.save_dir) {
+        (Some(held), _) => Some(PathBuf::from(held)),
+        (None, Some(save_dir)) => Some(library.file_path(save_dir, &file.file_name)),
+        (None, None) => None,
+    }
+}
+
+/// from の置き場所から to の置き場所へ移すファイル
+fn plan_moves(
+    library: &LibraryPaths,
+    from: &JournalState,
+    to: &JournalState,
+) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut moves = vec![];
+    for target in &to.files {
+        let Some(source) = from.files.iter().find(|f| f.file_name == target.file_name) else {
+            continue;
+        };
+        let (Some(src), Some(dest)) = (location(library, source), location(library, target)) else {
+            continue;
+        };
+        if src == dest {
+            continue;
+        }
+        if !src.is_file() {
+            bail!("ファイルが見つかりません: {:?}", src);
+        }
+        if dest.exists() {
+            bail!("移動先に同じ名前のファイルがあります: {:?}", dest);
+        }
+        moves.push((src, dest));
+    }
+    Ok(moves)
+}
+
+/// 移したファイルを元の場所へ戻す（途中で失敗したとき）
+pub(crate) fn revert_moves(moves: &[(PathBuf, PathBuf)]) {
+    for (src, dest) in moves.iter().rev() {
+        if let Err(e) = move_file(dest, src) {
+            log_error(format!("ファイルを戻せません: {:?} | {}", dest, e));
+        }
+    }
+}
+
+async fn restore_rows(pool: &SqlitePool, id: i64, to: &JournalState, undone: bool) -> Result<()> {
+    let mut tx = pool.begin().await.with_location()?;
+    if !to.illust_ids.is_empty() {
+        let ids = id_list(&to.illust_ids);
+        for table in ILLUST_TABLES {
+            sqlx::query(&format!("DELETE FROM {table} WHERE illust_id IN ({ids})"))
+                .execute(&mut *tx)
+                .await
+                .with_location()?;
+        }
+    }
+
+    for i in &to.illusts {
+        sqlx::query(
+            "INSERT INTO ILLUST_INFO (illust_id, suffix, cnum, extension, save_dir, file_name)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(i.illust_id)
+        .bind(i.suffix)
+        .bind(i.cnum)
+        .bind(&i.extension)
+        .bind(&i.save_dir)
+        .bind(&i.file_name)
+        .execute(&mut *tx)
+        .await
+        .with_location()?;
+    }
+    for d in &to.details {
+        sqlx::query(
+            "INSERT INTO ILLUST_DETAIL (illust_id, cnum, author_id, series, character, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(d.illust_id)
+        .bind(d.cnum)
+        .bind(d.author_id)
+        .bind(&d.series)
+        .bind(&d.character)
+        .bind(d.created_at)
+        .execute(&mut *tx)
+        .await
+        .with_location()?;
+    }
+    for t in &to.tags {
+        sqlx::query("INSERT INTO TAG_INFO (illust_id, cnum, tag) VALUES (?, ?, ?)")
+            .bind(t.illust_id)
+            .bind(t.cnum)
+            .bind(&t.tag)
+            .execute(&mut *tx)
+            .await
+            .with_location()?;
+    }
+    for entity_key in &to.entity_keys {
+        sqlx::query("DELETE FROM CHARACTER_INFO WHERE entity_key = ?")
+            .bind(entity_key)
+            .execute(&mut *tx)
+            .await
+            .with_location()?;
+    }
+    for c in &to.characters {
+        sqlx::query(
+            "INSERT INTO CHARACTER_INFO (entity_key, series, character, collect_dir)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(&c.entity_key)
+        .bind(&c.series)
+        .bind(&c.character)
+        .bind(&c.collect_dir)
+        .execute(&mut *tx)
+        .await
+        .with_location()?;
+    }
+    for u in &to.ugoira {
+        sqlx::query(
+            "INSERT INTO UGOIRA_INFO (illust_id, zip_name, format, converted_at)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(u.illust_id)
+        .bind(&u.zip_name)
+        .bind(u.format)
+        .bind(u.converted_at)
+        .execute(&mut *tx)
+        .await
+        .with_location()?;
+    }
+
+    sqlx::query("UPDATE OPERATION_JOURNAL SET undone = ? WHERE id = ?")
+        .bind(undone)
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .with_location()?;
+
+    tx.commit().await.with_location()?;
+    Ok(())
+}
+
+/// 登録内容とファイルを from の状態から to の状態へ戻す
+///
+/// 操作の後に対象作品の登録内容が変わっていたら何もしない
+async fn apply_state(
+    pool: &SqlitePool,
+    id: i64,
+    from: &JournalState,
+    to: &JournalState,
+    undone: bool,
+) -> Result<()> {
+    let mut conn = pool.acquire().await.with_location()?;
+    let current = snapshot(&mut conn, &to.illust_ids, &to.entity_keys).await?;
+    let library = load_library_paths(&mut conn).await?;
+    drop(conn);
+    if !same_rows(&current, from) {
+        bail!("操作の後に登録内容が変更されているため戻せません");
+    }
+
+    let moves = plan_moves(&library, from, to)?;
+    for (i, (src, dest)) in moves.iter().enumerate() {
+        if let Err(e) = move_file(src, dest) {
+            revert_moves(&moves[..i]);
+            return Err(e);
+        }
+    }
+
+    if let Err(e) = restore_rows(pool, id, to, undone).await {
+        revert_moves(&moves);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+async fn get_operation(pool: &SqlitePool, id: i64) -> Result<OperationEntry> {
+    let raw: OperationEntryRaw = sqlx::query_as(
+        "SELECT id, kind, file_count, detail, created_at, undone FROM OPERATION_JOURNAL
+         WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await
+    .with_location()?;
+    Ok(raw.into())
+}
+
+fn parse_state(json: &str) -> Result<JournalState> {
+    serde_json::from_str(json).with_location()
+}
+
+/// 最後の操作を取り消す（取り消せる操作がなければ None）
+pub async fn process_undo_last_operation(pool: &SqlitePool) -> Result<Option<OperationEntry>> {
+    let row: Option<(i64, String, String)> = sqlx::query_as(
+        "SELECT id, before_state, after_state FROM OPERATION_JOURNAL
+         WHERE undone = 0 ORDER BY id DESC LIMIT 1",
+    )
+    .fetch_optional(pool)
+    .await
+    .with_location()?;
+    let Some((id, before, after)) = row else {
+        return Ok(None);
+    };
+
+    apply_state(
+        pool,
+        id,
+        &parse_state(&after)?,
+        &parse_state(&before)?,
+        true,
+    )
+    .await?;
+
+    Ok(Some(get_operation(pool, id).await?))
+}
+
+/// 最後に取り消した操作をやり直す（やり直せる操作がなければ None）
+pub async fn process_redo_operation(pool: &SqlitePool) -> Result<Option<OperationEntry>> {
+    let row: Option<(i64, String, String)> = sqlx::query_as(
+        "SELECT id, before_state, after_state FROM OPERATION_JOURNAL
+         WHERE undone = 1 ORDER BY id ASC LIMIT 1",
+    )
+    .fetch_optional(pool)
+    .await
+    .with_location()?;
+    let Some((id, before, after)) = row else {
+        return Ok(None);
+    };
+
+    apply_state(
+        pool,
+        id,
+        &parse_state(&before)?,
+        &parse_state(&after)?,
+        false,
+    )
+    .await?;
+
+    Ok(Some(get_operation(pool, id).await?))
+}
+
+/// 履歴に残したフォルダを書き換える（ライブラリの移動時）
+///
+/// 退避したファイルはアプリのフォルダにあるため held_path はそのまま
+pub(crate) async fn rewrite_journal_dirs<F>(conn: &mut SqliteConnection, rewrite: F) -> Result<()>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let rewrite_dir = |dir: &mut Option<String>| {
+        if let Some(new_dir) = dir.as_deref().and_then(&rewrite) {
+            *dir = Some(new_dir);
+        }
+    };
+    let rows: Vec<(i64, String, String)> =
+        sqlx::query_as("SELECT id, before_state, after_state FROM OPERATION_JOURNAL")
+            .fetch_all(&mut *conn)
+            .await
+            .with_location()?;
+
+    for (id, before, after) in rows {
+        let mut states = [parse_state(&before)?, parse_state(&after)?];
+        for state in &mut states {
+            state
+                .illusts
+                .iter_mut()
+                .for_each(|i| rewrite_dir(&mut i.save_dir));
+            state
+                .files
+                .iter_mut()
+                .for_each(|f| rewrite_dir(&mut f.save_dir));
+            state
+                .characters
+                .iter_mut()
+                .for_each(|c| rewrite_dir(&mut c.collect_dir));
+        }
+        let [before, after] = states;
+
+        sqlx::query("UPDATE OPERATION_JOURNAL SET before_state = ?, after_state = ? WHERE id = ?")
+            .bind(serde_json::to_string(&before).with_location()?)
+            .bind(serde_json::to_string(&after).with_location()?)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .with_location()?;
+    }
+
+    Ok(())
+}
+
+pub async fn process_get_operation_history(pool: &SqlitePool) -> Result<Vec<OperationEntry>> {
+    let rows: Vec<OperationEntryRaw> = sqlx::query_as(
+        "SELECT id, kind, file_count, detail, created_at, undone FROM OPERATION_JOURNAL
+         ORDER BY id DESC",
+    )
+    .fetch_all(pool)
+    .await
+    .with_location()?;
+    Ok(rows.into_iter().map(Into::into).collect())
+}
@@ -0,0 +1,285 @@
+use anyhow::{bail, Result};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::path::{Path, PathBuf};
+
+use crate::constants::{COLLECT_ROOT, RELATIVE_PATHS};
+use crate::models::collect::RelocateStats;
+use crate::service::common::normalize_dir;
+use crate::service::journal::rewrite_journal_dirs;
+use crate::util::ResultWithLocationExt;
+
+/// root 直下のファイルを相対パスで保存するときの save_dir
+const ROOT_DIR: &str = ".";
+
+/// フォルダを保存している列（キャッシュも含めて書き換える）
+const DIR_COLUMNS: &[(&str, &str)] = &[
+    ("ILLUST_INFO", "save_dir"),
+    ("ILLUST_FETCH_WORK", "save_dir"),
+    ("CHARACTER_INFO", "collect_dir"),
+    ("COLLECT_UI_WORK", "collect_dir"),
+    ("COLLECT_FILTER_WORK", "collect_dir"),
+    ("FILE_HASH", "save_dir"),
+    ("IMAGE_HASH", "save_dir"),
+    ("FILE_HEALTH", "save_dir"),
+    ("MOVE_JOURNAL", "src_dir"),
+    ("MOVE_JOURNAL", "dest_dir"),
+];
+
+/// save_dir と実際のフォルダの対応（load_library_paths で DB ごとに読み込む）
+#[derive(Debug, Clone, Default)]
+pub struct LibraryPaths {
+    /// 相対パス保存が有効なときの root
+    relative_root: Option<PathBuf>,
+}
+
+impl LibraryPaths {
+    /// DB に保存する形の save_dir（相対パス保存が有効なら root からの相対パス）
+    pub fn to_stored_dir(&self, dir: &str) -> String {
+        let dir = normalize_dir(dir);
+        match &self.relative_root {
+            Some(root) => relative_to(Path::new(&dir), root).unwrap_or(dir),
+            None => dir,
+        }
+    }
+
+    /// 保存されている save_dir を実際のフォルダにする
+    pub fn resolve_dir(&self, save_dir: &str) -> PathBuf {
+        let dir = Path::new(save_dir);
+        match &self.relative_root {
+            Some(root) if dir.is_relative() => {
+                if save_dir == ROOT_DIR {
+                    root.clone()
+                } else {
+                    root.join(dir)
+                }
+            }
+            _ => dir.to_path_buf(),
+        }
+    }
+
+    /// 保存先フォルダとファイル名からパスを作る（SQL では組み立てない）
+    pub fn file_path(&self, save_dir: &str, file_name: &str) -> PathBuf {
+        self.resolve_dir(save_dir).join(file_name)
+    }
+}
+
+fn relative_to(dir: &Path, root: &Path) -> Option<String> {
+    let rest = dir.strip_prefix(root).ok()?;
+    if rest.as_os_str().is_empty() {
+        Some(ROOT_DIR.to_string())
+    } else {
+        Some(rest.to_string_lossy().to_string())
+    }
+}
+
+/// 相対パス保存の設定を読み込む（パスを扱う処理の始めに、その DB から読む）
+pub async fn load_library_paths(conn: &mut SqliteConnection) -> Result<LibraryPaths> {
+    let relative: Option<String> = sqlx::query_scalar("SELECT value FROM COMMON_MST WHERE key = ?")
+        .bind(RELATIVE_PATHS)
+        .fetch_optional(&mut *conn)
+        .await
+        .with_location()?;
+    let root: Option<String> = sqlx::query_scalar("SELECT value FROM COMMON_MST WHERE key = ?")
+        .bind(COLLECT_ROOT)
+        .fetch_optional(&mut *conn)
+        .await
+        .with_location()?;
+
+    let enabled = relative.as_deref() == Some("1");
+
+    Ok(LibraryPaths {
+        relative_root: root.filter(|_| enabled).map(PathBuf::from),
+    })
+}
+
+/// 絶対パスのうち old_prefix 配下のものを new_prefix 配下に置き換えた値
+fn replace_prefix(dir: &str, old_prefix: &Path, new_prefix: &Path) -> Option<String> {
+    let rest = Path::new(dir).strip_prefix(old_prefix).ok()?;
+    let replaced = if rest.as_os_str().is_empty() {
+        new_prefix.to_path_buf()
+    } else {
+        new_prefix.join(rest)
+    };
+    Some(replaced.to_string_lossy().to_string())
+}
+
+/// 列ごとに値を書き換え、書き換えた行数と、同じキーの行が既にあり書き換えなかった行数を返す
+async fn rewrite_dirs<F>(
+    conn: &mut SqliteConnection,
+    table: &str,
+    column: &str,
+    rewrite: F,
+) -> Result<(u32, u32)>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let rows: Vec<(String, i64)> = sqlx::query_as(&format!(
+        "SELECT {column}, COUNT(*) FROM {table} WHERE {column} IS NOT NULL GROUP BY {column}"
+    ))
+    .fetch_all(&mut *conn)
+    .await
+    .with_location()?;
+
+    let (mut count, mut conflicts) = (0, 0);
+    for (dir, rows) in rows {
+        let Some(new_dir) = rewrite(&dir) else {
+            continue;
+        };
+        if new_dir == dir {
+            continue;
+        }
+
+        // 同じキーの行が既にあればどちらも消さずに残し、件数を返す
+        let updated = sqlx::query(&format!(
+            "UPDATE OR IGNORE {table} SET {column} = ? WHERE {column} = ?"
+        ))
+        .bind(&new_dir)
+        .bind(&dir)
+        .execute(&mut *conn)
+        .await
+        .with_location()?
+        .rows_affected() as u32;
+        count += updated;
+        conflicts += rows as u32 - updated;
+    }
+
+    Ok((count, conflicts))
+}
+
+/// 全ての列と操作履歴のフォルダを書き換える
+async fn rewrite_all_dirs<F>(
+    conn: &mut SqliteConnection,
+    stats: &mut RelocateStats,
+    rewrite: F,
+) -> Result<()>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    for (table, column) in DIR_COLUMNS {
+        let (count, conflicts) = rewrite_dirs(&mut *conn, table, column, &rewrite).await?;
+        match *table {
+            "ILLUST_INFO" => stats.files += count,
+            "CHARACTER_INFO" => stats.collect_dirs += count,
+            _ => {}
+        }
+        stats.conflicts += conflicts;
+    }
+    // 取り消しのときに今の登録内容と照らし合わせるため、履歴も同じ形にそろえる
+    rewrite_journal_dirs(&mut *conn, &rewrite).await
+}
+
+/// ドライブ文字やマウント先が変わったライブラリの保存先をまとめて書き換える
+///
+/// 書き換えは1つのトランザクションで行い、dry_run ならコミットせずに件数だけを返す。
+/// store_relative を指定すると、以降は root 配下の保存先を root からの相対パスで持つ
+pub async fn process_relocate_library(
+    pool: &SqlitePool,
+    old_prefix: &str,
+    new_prefix: &str,
+    dry_run: bool,
+    store_relative: bool,
+) -> Result<RelocateStats> {
+    let old_prefix = PathBuf::from(normalize_dir(old_prefix));
+    let new_prefix = PathBuf::from(normalize_dir(new_prefix));
+    if old_prefix.is_relative() || new_prefix.is_relative() {
+        bail!(
+            "絶対パスを指定してください: {:?} → {:?}",
+            old_prefix,
+            new_prefix
+        );
+    }
+    if !new_prefix.is_dir() {
+        bail!("移動先のフォルダがありません: {:?}", new_prefix);
+    }
+
+    let mut tx = pool.begin().await.with_location()?;
+    let mut stats = RelocateStats::default();
+
+    let root: Option<String> = sqlx::query_scalar("SELECT value FROM COMMON_MST WHERE key = ?")
+        .bind(COLLECT_ROOT)
+        .fetch_optional(&mut *tx)
+        .await
+        .with_location()?;
+    let root = root.map(
+        |root| match replace_prefix(&root, &old_prefix, &new_prefix) {
+            Some(new_root) => {
+                stats.root_updated = true;
+                new_root
+            }
+            None => root,
+        },
+    );
+    if store_relative && root.is_none() {
+        bail!("相対パスで保存するには root を設定してください");
+    }
+    let root_path = root.as_ref().map(PathBuf::from);
+
+    // 書き換え後の場所に実際にファイルがあるか
+    let files: Vec<(String, String)> =
+        sqlx::query_as("SELECT save_dir, file_name FROM ILLUST_INFO WHERE save_dir IS NOT NULL")
+            .fetch_all(&mut *tx)
+            .await
+            .with_location()?;
+    for (save_dir, file_name) in files {
+        let dir = match &root_path {
+            // 相対パスで保存済みなら新しい root から引く
+            Some(root) if Path::new(&save_dir).is_relative() => {
+                if save_dir == ROOT_DIR {
+                    root.clone()
+                } else {
+                    root.join(&save_dir)
+                }
+            }
+            _ => replace_prefix(&save_dir, &old_prefix, &new_prefix)
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(&save_dir)),
+        };
+        if !dir.join(&file_name).is_file() {
+            stats.missing_files += 1;
+        }
+        if store_relative && Path::new(&save_dir).is_absolute() {
+            if let Some(root) = &root_path {
+                if dir.starts_with(root) {
+                    stats.relative_files += 1;
+                }
+            }
+        }
+    }
+
+    rewrite_all_dirs(&mut tx, &mut stats, |dir| {
+        replace_prefix(dir, &old_prefix, &new_prefix)
+    })
+    .await?;
+
+    if dry_run {
+        return Ok(stats);
+    }
+
+    if let Some(ref root) = root {
+        sqlx::query("INSERT OR REPLACE INTO COMMON_MST (key, value) VALUES (?, ?)")
+            .bind(COLLECT_ROOT)
+            .bind(root)
+            .execute(&mut *tx)
+            .await
+            .with_location()?;
+    }
+
+    if let (true, Some(root)) = (store_relative, &root_path) {
+        // 件数は絶対パスでの書き換え分を返す（衝突は両方とも数える）
+        let mut relative_stats = RelocateStats::default();
+        rewrite_all_dirs(&mut tx, &mut relative_stats, |dir| {
+            relative_to(Path::new(dir), root)
+        })
+        .await?;
+        stats.conflicts += relative_stats.conflicts;
+        sqlx::query("INSERT OR REPLACE INTO COMMON_MST (key, value) VALUES (?, '1')")
+            .bind(RELATIVE_PATHS)
+            .execute(&mut *tx)
+            .await
+            .with_location()?;
+    }
+
+    tx.commit().await.with_location()?;
+
+    Ok(stats)
+}
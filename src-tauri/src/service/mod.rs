@@ -1,7 +1,16 @@
 pub mod catalog;
 pub mod collect;
 pub mod common;
+pub mod duplicate;
 pub mod fetch;
+pub mod fetch_control;
+pub mod file_move;
+pub mod file_pattern;
+pub mod health;
+pub mod journal;
+pub mod library;
 pub mod manage;
+pub mod near_duplicate;
 pub mod search;
 pub mod setting;
+pub mod ugoira;
@@ -0,0 +1,377 @@
+use anyhow::{bail, Result};
+use image::imageops::FilterType;
+use rayon::prelude::*;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::models::catalog::{
+    DuplicateFile, DuplicateResolveStats, NearDuplicateCluster, NearDuplicateFile,
+};
+use crate::service::duplicate::{file_stamp, forget_registered_file, trash_files, FileKey};
+use crate::service::library::{load_library_paths, LibraryPaths};
+use crate::util::{log_error, ResultWithLocationExt};
+
+/// 知覚ハッシュを計算する拡張子
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageHash {
+    pub dhash: u64,
+    pub width: u32,
+    pub height: u32,
+    pub file_size: i64,
+}
+
+/// 9x8 のグレースケールに縮小し、横に隣り合う画素の明暗から 64bit の dHash を作る
+///
+/// 再エンコードや縮小では値がほとんど変わらない
+pub fn dhash_image(path: &Path) -> Result<ImageHash> {
+    let image = image::open(path).with_location()?;
+    let small = image::imageops::resize(&image.to_luma8(), 9, 8, FilterType::Triangle);
+
+    let mut dhash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            dhash <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                dhash |= 1;
+            }
+        }
+    }
+
+    Ok(ImageHash {
+        dhash,
+        width: image.width(),
+        height: image.height(),
+        file_size: std::fs::metadata(path).with_location()?.len() as i64,
+    })
+}
+
+/// 指定画像の dHash を返す（FILE_HASH と同じくサイズと更新日時が変わったものだけ計算）
+pub async fn ensure_image_hashes(
+    conn: &mut SqliteConnection,
+    files: &[FileKey],
+) -> Result<HashMap<FileKey, ImageHash>> {
+    let library = load_library_paths(&mut *conn).await?;
+    let mut hashes = HashMap::new();
+    let mut stale: Vec<(FileKey, PathBuf, i64)> = Vec::new();
+
+    for (save_dir, file_name) in files {
+        let path = library.file_path(save_dir, file_name);
+        let Some((file_size, modified_at)) = file_stamp(&path) else {
+            continue;
+        };
+
+        let cached: Option<(i64, u32, u32)> = sqlx::query_as(
+            "SELECT dhash, width, height FROM IMAGE_HASH
+              WHERE save_dir = ? AND file_name = ? AND file_size = ? AND modified_at = ?",
+        )
+        .bind(save_dir)
+        .bind(file_name)
+        .bind(file_size)
+        .bind(modified_at)
+        .fetch_optional(&mut *conn)
+        .await
+        .with_location()?;
+
+        let key = (save_dir.clone(), file_name.clone());
+        match cached {
+            Some((dhash, width, height)) => {
+                let hash = ImageHash {
+                    dhash: dhash as u64,
+                    width,
+                    height,
+                    file_size,
+                };
+                hashes.insert(key, hash);
+            }
+            None => stale.push((key, path, modified_at)),
+        }
+    }
+
+    // デコードは重いのでブロッキング用のスレッドで並列に行う
+    let computed = tauri::async_runtime::spawn_blocking(move || {
+        stale
+            .into_par_iter()
+            .map(|(key, path, modified_at)| (key, modified_at, dhash_image(&path)))
+            .collect::<Vec<_>>()
+    })
+    .await
+    .with_location()?;
+
+    for (key, modified_at, hash) in computed {
+        let hash = match hash {
+            Ok(hash) => hash,
+            Err(e) => {
+                log_error(format!("画像ハッシュ計算失敗: {}/{} | {}", key.0, key.1, e));
+                continue;
+            }
+        };
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO IMAGE_HASH
+                 (save_dir, file_name, file_size, modified_at, dhash, width, height)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&key.0)
+        .bind(&key.1)
+        .bind(hash.file_size)
+        .bind(modified_at)
+        .bind(hash.dhash as i64)
+        .bind(hash.width)
+        .bind(hash.height)
+        .execute(&mut *conn)
+        .await
+        .with_location()?;
+
+        hashes.insert(key, hash);
+    }
+
+    Ok(hashes)
+}
+
+/// ハミング距離で引く BK-tree（値は呼び出し側の配列の添字）
+pub struct BkTree {
+    nodes: Vec<BkNode>,
+}
+
+struct BkNode {
+    hash: u64,
+    index: usize,
+    children: HashMap<u32, usize>,
+}
+
+impl BkTree {
+    pub fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    pub fn insert(&mut self, hash: u64, index: usize) {
+        let node = BkNode {
+            hash,
+            index,
+            children: HashMap::new(),
+        };
+        if self.nodes.is_empty() {
+            self.nodes.push(node);
+            return;
+        }
+
+        let mut current = 0;
+        loop {
+            let distance = (self.nodes[current].hash ^ hash).count_ones();
+            match self.nodes[current].children.get(&distance) {
+                Some(&next) => current = next,
+                None => {
+                    let added = self.nodes.len();
+                    self.nodes.push(node);
+                    self.nodes[current].children.insert(distance, added);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 距離が threshold 以下の要素の添字
+    pub fn find(&self, hash: u64, threshold: u32) -> Vec<usize> {
+        let mut found = Vec::new();
+        if self.nodes.is_empty() {
+            return found;
+        }
+
+        let mut stack = vec![0];
+        while let Some(current) = stack.pop() {
+            let node = &self.nodes[current];
+            let distance = (node.hash ^ hash).count_ones();
+            if distance <= threshold {
+                found.push(node.index);
+            }
+            // 三角不等式で届かない枝は見ない
+            for (&child_distance, &child) in &node.children {
+                if child_distance + threshold >= distance && child_distance <= distance + threshold
+                {
+                    stack.push(child);
+                }
+            }
+        }
+
+        found
+    }
+}
+
+impl Default for BkTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 登録済み画像とその dHash（読めない画像は除く）
+pub(crate) async fn registered_image_hashes(
+    conn: &mut SqliteConnection,
+) -> Result<Vec<(DuplicateFile, ImageHash)>> {
+    let files: Vec<DuplicateFile> = sqlx::query_as::<_, (i32, i32, String, String, String)>(
+        "SELECT illust_id, suffix, save_dir, file_name, extension
+           FROM ILLUST_INFO WHERE save_dir IS NOT NULL",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .with_location()?
+    .into_iter()
+    .filter(|(_, _, _, _, extension)| IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+    .map(
+        |(illust_id, suffix, save_dir, file_name, _)| DuplicateFile {
+            illust_id,
+            suffix,
+            save_dir,
+            file_name,
+        },
+    )
+    .collect();
+
+    let keys: Vec<FileKey> = files
+        .iter()
+        .map(|file| (file.save_dir.clone(), file.file_name.clone()))
+        .collect();
+    let hashes = ensure_image_hashes(&mut *conn, &keys).await?;
+
+    Ok(files
+        .into_iter()
+        .zip(keys)
+        .filter_map(|(file, key)| hashes.get(&key).map(|hash| (file, *hash)))
+        .collect())
+}
+
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// 登録済み画像を dHash の距離でまとめる
+///
+/// 距離 threshold 以下でつながるものを同じまとまりとし、解像度・ファイルサイズの
+/// 大きい順に並べる。まとまりは削除できる容量の多い順に返す
+pub async fn process_find_near_duplicates(
+    pool: &SqlitePool,
+    threshold: u32,
+) -> Result<Vec<NearDuplicateCluster>> {
+    let mut conn = pool.acquire().await.with_location()?;
+    let entries = registered_image_hashes(&mut conn).await?;
+
+    let threshold = threshold.min(64);
+    let mut tree = BkTree::new();
+    let mut parents: Vec<usize> = (0..entries.len()).collect();
+    for (index, (_, hash)) in entries.iter().enumerate() {
+        for other in tree.find(hash.dhash, threshold) {
+            let (a, b) = (
+                find_root(&mut parents, index),
+                find_root(&mut parents, other),
+            );
+            if a != b {
+                parents[a] = b;
+            }
+        }
+        tree.insert(hash.dhash, index);
+    }
+
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for index in 0..entries.len() {
+        let root = find_root(&mut parents, index);
+        members.entry(root).or_default().push(index);
+    }
+
+    let mut clusters: Vec<(i64, NearDuplicateCluster)> = members
+        .into_values()
+        .filter(|indexes| indexes.len() > 1)
+        .map(|mut indexes| {
+            indexes.sort_by_key(|&i| {
+                let hash = &entries[i].1;
+                std::cmp::Reverse((hash.width as u64 * hash.height as u64, hash.file_size))
+            });
+            let best = entries[indexes[0]].1.dhash;
+            let files: Vec<NearDuplicateFile> = indexes
+                .iter()
+                .map(|&i| {
+                    let (file, hash) = &entries[i];
+                    NearDuplicateFile {
+                        illust_id: file.illust_id,
+                        suffix: file.suffix,
+                        save_dir: file.save_dir.clone(),
+                        file_name: file.file_name.clone(),
+                        width: hash.width,
+                        height: hash.height,
+                        file_size: hash.file_size,
+                        distance: (hash.dhash ^ best).count_ones(),
+                    }
+                })
+                .collect();
+            let reclaimable = files.iter().skip(1).map(|f| f.file_size).sum();
+            (reclaimable, NearDuplicateCluster { files })
+        })
+        .collect();
+    clusters.sort_by_key(|(reclaimable, _)| std::cmp::Reverse(*reclaimable));
+
+    Ok(clusters.into_iter().map(|(_, cluster)| cluster).collect())
+}
+
+/// まとまりから keep を残し、remove に指定したファイルを削除する
+///
+/// 既に移動・削除されていて登録と一致しないファイルは残す。
+/// ゴミ箱へは登録の解除を commit してから移す
+pub async fn process_resolve_near_duplicates(
+    pool: &SqlitePool,
+    keep: DuplicateFile,
+    remove: Vec<DuplicateFile>,
+) -> Result<DuplicateResolveStats> {
+    if remove.contains(&keep) {
+        bail!("残すファイルが削除対象に含まれています: {}", keep.file_name);
+    }
+
+    let mut tx = pool.begin().await.with_location()?;
+    let library = load_library_paths(&mut tx).await?;
+
+    if !is_registered(&mut tx, &library, &keep).await? {
+        bail!("残すファイルが見つかりません: {}", keep.file_name);
+    }
+
+    let mut stats = DuplicateResolveStats::default();
+    let mut removed = Vec::new();
+    for file in remove {
+        if !is_registered(&mut tx, &library, &file).await? {
+            stats.skipped_files.push(file.file_name);
+            continue;
+        }
+        forget_registered_file(&mut tx, &file).await?;
+        removed.push(file);
+    }
+
+    tx.commit().await.with_location()?;
+
+    trash_files(removed, &library, &mut stats);
+
+    Ok(stats)
+}
+
+/// 登録どおりの場所にファイルがあるか
+async fn is_registered(
+    conn: &mut SqliteConnection,
+    library: &LibraryPaths,
+    file: &DuplicateFile,
+) -> Result<bool> {
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM ILLUST_INFO
+          WHERE illust_id = ? AND suffix = ? AND save_dir = ? AND file_name = ?",
+    )
+    .bind(file.illust_id)
+    .bind(file.suffix)
+    .bind(&file.save_dir)
+    .bind(&file.file_name)
+    .fetch_one(&mut *conn)
+    .await
+    .with_location()?;
+
+    Ok(count > 0 && library.file_path(&file.save_dir, &file.file_name).is_file())
+}
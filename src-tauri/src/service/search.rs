@@ -1,9 +1,18 @@
 use anyhow::Result;
 use sqlx::SqlitePool;
+use std::path::Path;
 
 use crate::{
-    models::search::{AuthorInfo, CharacterInfo, SearchResult, TagInfo},
-    service::common::{build_named_query, hash_params},
+    constants::IMAGE_SEARCH_LIMIT,
+    models::search::{
+        AuthorInfo, AuthorNameHistory, AuthorNameHistoryRaw, CharacterInfo, IllustMeta,
+        IllustMetaRaw, SearchResult, TagInfo,
+    },
+    service::{
+        common::{build_named_query, hash_params},
+        library::{load_library_paths, LibraryPaths},
+        near_duplicate::{dhash_image, registered_image_hashes},
+    },
     util::ResultWithLocationExt,
 };
 
@@ -11,24 +20,32 @@ pub async fn process_search_by_criteria(
     tags: Vec<String>,
     character: Option<String>,
     author_id: Option<u32>,
+    author_name: Option<String>,
     pool: &SqlitePool,
 ) -> Result<Vec<SearchResult>> {
     let sql = include_str!("../sql/search/search_by_criteria.sql");
 
-    let results = build_named_query(
+    let mut conn = pool.acquire().await.with_location()?;
+    let library = load_library_paths(&mut conn).await?;
+
+    let results: Vec<SearchResult> = build_named_query(
         &sql,
         &hash_params(&vec![
             (":character", character.into()),
             (":author_id", author_id.into()),
+            (":author_name", normalize_author_name(author_name).into()),
             (":tag_count", tags.len().into()),
             (":tags", tags.into()),
         ])
         .with_location()?,
     )?
     .build_query_as()
-    .fetch_all(pool)
+    .fetch_all(&mut *conn)
     .await
-    .with_location()?;
+    .with_location()?
+    .into_iter()
+    .map(|result| resolve_paths(result, &library))
+    .collect();
 
     Ok(results)
 }
@@ -36,24 +53,126 @@ pub async fn process_search_by_criteria(
 pub async fn process_search_by_id(id: i64, pool: &SqlitePool) -> Result<Vec<SearchResult>> {
     let sql = include_str!("../sql/search/search_by_id.sql");
 
-    let results = sqlx::query_as::<_, SearchResult>(sql)
+    let mut conn = pool.acquire().await.with_location()?;
+    let library = load_library_paths(&mut conn).await?;
+
+    let results: Vec<SearchResult> = sqlx::query_as::<_, SearchResult>(sql)
         .bind(id)
+        .fetch_all(&mut *conn)
+        .await
+        .with_location()?
+        .into_iter()
+        .map(|result| resolve_paths(result, &library))
+        .collect();
+
+    Ok(results)
+}
+
+/// 任意の画像と見た目が近い登録済みファイルを、距離の近い順に返す
+///
+/// 検索画像のファイル名に作品IDが無くてもよい
+pub async fn process_search_by_image(
+    path: String,
+    max_distance: u32,
+    pool: &SqlitePool,
+) -> Result<Vec<SearchResult>> {
+    let query = tauri::async_runtime::spawn_blocking(move || dhash_image(Path::new(&path)))
+        .await
+        .with_location()??;
+
+    let mut conn = pool.acquire().await.with_location()?;
+    let library = load_library_paths(&mut conn).await?;
+    let mut matches: Vec<(u32, i32, i32)> = registered_image_hashes(&mut conn)
+        .await?
+        .into_iter()
+        .map(|(file, hash)| {
+            let distance = (hash.dhash ^ query.dhash).count_ones();
+            (distance, file.illust_id, file.suffix)
+        })
+        .filter(|(distance, _, _)| *distance <= max_distance)
+        .collect();
+    matches.sort();
+    matches.truncate(IMAGE_SEARCH_LIMIT);
+
+    let sql = include_str!("../sql/search/search_by_file.sql");
+    let mut results = Vec::with_capacity(matches.len());
+    for (distance, illust_id, suffix) in matches {
+        let result = sqlx::query_as::<_, SearchResult>(sql)
+            .bind(illust_id)
+            .bind(suffix)
+            .fetch_optional(&mut *conn)
+            .await
+            .with_location()?;
+        if let Some(mut result) = result {
+            result.distance = Some(distance);
+            results.push(resolve_paths(result, &library));
+        }
+    }
+
+    Ok(results)
+}
+
+/// 画面には保存形式によらず実際のパスを返す
+fn resolve_paths(mut result: SearchResult, library: &LibraryPaths) -> SearchResult {
+    result.thumbnail_url = library
+        .file_path(&result.save_dir, &result.file_name)
+        .to_string_lossy()
+        .to_string();
+    result.save_dir = library
+        .resolve_dir(&result.save_dir)
+        .to_string_lossy()
+        .to_string();
+    result
+}
+
+/// 空文字は未指定として扱う
+fn normalize_author_name(author_name: Option<String>) -> Option<String> {
+    author_name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+pub async fn process_get_author_history(
+    author_id: u32,
+    pool: &SqlitePool,
+) -> Result<Vec<AuthorNameHistory>> {
+    let sql = include_str!("../sql/search/get_author_history.sql");
+
+    let raw = sqlx::query_as::<_, AuthorNameHistoryRaw>(sql)
+        .bind(author_id)
         .fetch_all(pool)
         .await
         .with_location()?;
 
-    Ok(results)
+    Ok(raw.into_iter().map(AuthorNameHistory::from).collect())
+}
+
+pub async fn process_get_illust_meta(
+    illust_id: u32,
+    pool: &SqlitePool,
+) -> Result<Option<IllustMeta>> {
+    let sql = include_str!("../sql/search/get_illust_meta.sql");
+
+    let raw = sqlx::query_as::<_, IllustMetaRaw>(sql)
+        .bind(illust_id)
+        .fetch_optional(pool)
+        .await
+        .with_location()?;
+
+    Ok(raw.map(IllustMeta::from))
 }
 
 pub async fn process_filter_dropdowns(
     tags: Vec<String>,
     character: Option<String>,
     author_id: Option<u32>,
+    author_name: Option<String>,
     pool: &SqlitePool,
 ) -> Result<(Vec<TagInfo>, Vec<CharacterInfo>, Vec<AuthorInfo>)> {
     let param_vec = vec![
         (":character", character.into()),
         (":author_id", author_id.into()),
+        (":author_name", normalize_author_name(author_name).into()),
         (":tag_count", tags.len().into()),
         (":tags", tags.into()),
     ];
@@ -2,7 +2,6 @@ use anyhow::{anyhow, Result};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use futures::channel::oneshot;
 use rand::{distr::Alphanumeric, Rng};
-use reqwest::Client;
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
@@ -10,7 +9,8 @@ use std::sync::{Arc, Mutex};
 use std::{collections::HashMap, path::PathBuf};
 use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
 
-use crate::constants::{AUTH_TOKEN_URL, CLIENT_ID, CLIENT_SECRET, LOGIN_URL, REDIRECT_URI};
+use crate::api::pixiv::PixivEndpoint;
+use crate::constants::{CLIENT_ID, CLIENT_SECRET, LOGIN_URL, REDIRECT_URI};
 use crate::util::ResultWithLocationExt;
 
 pub async fn process_pixiv_authorization(app: tauri::AppHandle) -> Result<String> {
@@ -73,10 +73,12 @@ fn generate_code_verifier_challenge() -> (String, String) {
 }
 
 async fn exchange_code_for_token(code: String, code_verifier: String) -> Result<String> {
-    let client = Client::new();
+    // 認証もAPIと同じ接続先・プロキシを使う
+    let endpoint = PixivEndpoint::from_env();
+    let client = endpoint.build_http_client()?;
 
     let res = client
-        .post(AUTH_TOKEN_URL)
+        .post(&endpoint.auth_token_url)
         .form(&[
             ("client_id", CLIENT_ID),
             ("client_secret", CLIENT_SECRET),
@@ -86,10 +88,6 @@ async fn exchange_code_for_token(code: String, code_verifier: String) -> Result<
             ("include_policy", "true"),
             ("redirect_uri", REDIRECT_URI),
         ])
-        .header(
-            "User-Agent",
-            "PixivAndroidApp/5.0.234 (Android 11; Pixel 5)",
-        )
         .send()
         .await
         .with_location()?;
@@ -0,0 +1,236 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use sqlx::SqlitePool;
+use std::fs::{self, File};
+use std::io::{BufWriter, Read};
+use std::path::Path;
+
+use crate::models::catalog::{UgoiraConvertStats, UgoiraFormat};
+use crate::models::fetch::{UgoiraFrame, UgoiraMetadata};
+use crate::service::common::{build_named_query, hash_params};
+use crate::service::library::{load_library_paths, LibraryPaths};
+use crate::util::ResultWithLocationExt;
+
+/// 変換対象の zip
+#[derive(sqlx::FromRow)]
+struct UgoiraTarget {
+    illust_id: u32,
+    save_dir: String,
+    file_name: String,
+}
+
+/// 未変換のうごイラ zip をアニメーション画像に変換し、ILLUST_INFO の登録先を差し替える
+///
+/// 元の zip は UGOIRA_INFO に記録し、以降は変換後のファイルに付随して移動する
+pub async fn process_convert_ugoira(
+    pool: &SqlitePool,
+    format: UgoiraFormat,
+    illust_ids: Vec<u32>,
+) -> Result<UgoiraConvertStats> {
+    let sql = include_str!("../sql/catalog/get_ugoira_targets.sql");
+    let targets: Vec<UgoiraTarget> = build_named_query(
+        sql,
+        &hash_params(&vec![
+            (":target_count", illust_ids.len().into()),
+            (":illust_ids", illust_ids.into()),
+        ])
+        .with_location()?,
+    )
+    .with_location()?
+    .build_query_as()
+    .fetch_all(pool)
+    .await
+    .with_location()?;
+
+    let mut conn = pool.acquire().await.with_location()?;
+    let library = load_library_paths(&mut conn).await?;
+    drop(conn);
+
+    let mut stats = UgoiraConvertStats::default();
+    for target in targets {
+        match convert_target(pool, &library, &target, format).await {
+            Ok(()) => stats.converted += 1,
+            Err(e) => {
+                stats.failed += 1;
+                stats
+                    .failed_files
+                    .push(format!("{}:{}", target.file_name, e));
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+async fn convert_target(
+    pool: &SqlitePool,
+    library: &LibraryPaths,
+    target: &UgoiraTarget,
+    format: UgoiraFormat,
+) -> Result<()> {
+    let frames: Vec<UgoiraFrame> = sqlx::query_as(
+        "SELECT file, delay FROM UGOIRA_FRAME WHERE illust_id = ? ORDER BY frame_index",
+    )
+    .bind(target.illust_id)
+    .fetch_all(pool)
+    .await
+    .with_location()?;
+
+    let zip_path = library.file_path(&target.save_dir, &target.file_name);
+    let stem = Path::new(&target.file_name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let output_name = format!("{}.{}", stem, format.extension());
+    let output_path = library.file_path(&target.save_dir, &output_name);
+    if output_path.exists() {
+        bail!("変換先のファイルが既にあります: {:?}", output_path);
+    }
+
+    // デコード・エンコードは重いのでブロッキング用のスレッドで行う
+    let path = output_path.clone();
+    tauri::async_runtime::spawn_blocking(move || convert_ugoira(&zip_path, &path, frames, format))
+        .await
+        .with_location()??;
+
+    let mut tx = pool.begin().await.with_location()?;
+    sqlx::query(
+        "INSERT OR REPLACE INTO UGOIRA_INFO (illust_id, zip_name, format, converted_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(target.illust_id)
+    .bind(&target.file_name)
+    .bind(format as i64)
+    .bind(Utc::now().timestamp())
+    .execute(&mut *tx)
+    .await
+    .with_location()?;
+    sqlx::query(
+        "UPDATE ILLUST_INFO SET file_name = ?, extension = ? WHERE illust_id = ? AND file_name = ?",
+    )
+    .bind(&output_name)
+    .bind(format.extension())
+    .bind(target.illust_id)
+    .bind(&target.file_name)
+    .execute(&mut *tx)
+    .await
+    .with_location()?;
+
+    if let Err(e) = tx.commit().await {
+        // 登録できなかった変換結果は残さない
+        let _ = fs::remove_file(&output_path);
+        return Err(e).with_location();
+    }
+
+    Ok(())
+}
+
+/// zip 内のフレームを読み込んで指定形式で書き出す
+///
+/// フレーム情報が未取得の場合は zip 同梱の animation.json（PixivUtil2 形式）を使う
+pub fn convert_ugoira(
+    zip_path: &Path,
+    output_path: &Path,
+    frames: Vec<UgoiraFrame>,
+    format: UgoiraFormat,
+) -> Result<()> {
+    let mut archive =
+        zip::ZipArchive::new(File::open(zip_path).with_location()?).with_location()?;
+
+    let frames = if frames.is_empty() {
+        let Ok(mut entry) = archive.by_name("animation.json") else {
+            bail!("フレーム情報がありません: {:?}", zip_path);
+        };
+        let mut text = String::new();
+        entry.read_to_string(&mut text).with_location()?;
+        serde_json::from_str::<UgoiraMetadata>(&text)
+            .with_location()?
+            .frames
+    } else {
+        frames
+    };
+    if frames.is_empty() {
+        bail!("フレームがありません: {:?}", zip_path);
+    }
+
+    let mut images = Vec::with_capacity(frames.len());
+    for frame in &frames {
+        let mut entry = archive.by_name(&frame.file).with_location()?;
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes).with_location()?;
+        let image = image::load_from_memory(&bytes).with_location()?.to_rgba8();
+        images.push((image, frame.delay));
+    }
+
+    let (width, height) = images[0].0.dimensions();
+    if images
+        .iter()
+        .any(|(image, _)| image.dimensions() != (width, height))
+    {
+        bail!("フレームのサイズが揃っていません: {:?}", zip_path);
+    }
+
+    let result = match format {
+        UgoiraFormat::WebP => encode_webp(output_path, width, height, images),
+        UgoiraFormat::Gif => encode_gif(output_path, images),
+        UgoiraFormat::Apng => encode_apng(output_path, width, height, images),
+    };
+    if result.is_err() {
+        // 書きかけのファイルを残さない
+        let _ = fs::remove_file(output_path);
+    }
+    result
+}
+
+fn encode_webp(path: &Path, width: u32, height: u32, images: Vec<(RgbaImage, u32)>) -> Result<()> {
+    let mut encoder = webp_animation::Encoder::new((width, height)).with_location()?;
+    let mut timestamp = 0;
+    for (image, delay) in images {
+        encoder
+            .add_frame(image.as_raw(), timestamp)
+            .with_location()?;
+        timestamp += delay as i32;
+    }
+    let data = encoder.finalize(timestamp).with_location()?;
+    fs::write(path, &*data).with_location()?;
+
+    Ok(())
+}
+
+fn encode_gif(path: &Path, images: Vec<(RgbaImage, u32)>) -> Result<()> {
+    let mut encoder = GifEncoder::new(BufWriter::new(File::create(path).with_location()?));
+    encoder.set_repeat(Repeat::Infinite).with_location()?;
+    for (image, delay) in images {
+        let delay = Delay::from_numer_denom_ms(delay, 1);
+        encoder
+            .encode_frame(Frame::from_parts(image, 0, 0, delay))
+            .with_location()?;
+    }
+
+    Ok(())
+}
+
+fn encode_apng(path: &Path, width: u32, height: u32, images: Vec<(RgbaImage, u32)>) -> Result<()> {
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path).with_location()?),
+        width,
+        height,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(images.len() as u32, 0)
+        .with_location()?;
+
+    let mut writer = encoder.write_header().with_location()?;
+    for (image, delay) in images {
+        writer
+            .set_frame_delay(delay.min(u16::MAX as u32) as u16, 1000)
+            .with_location()?;
+        writer.write_image_data(image.as_raw()).with_location()?;
+    }
+    writer.finish().with_location()?;
+
+    Ok(())
+}
@@ -1,3 +1,6 @@
+DELETE FROM UGOIRA_INFO
+WHERE illust_id = :illust_id AND :suffix = 0;
+
 DELETE FROM ILLUST_INFO
 WHERE illust_id = :illust_id AND suffix = :suffix;
 
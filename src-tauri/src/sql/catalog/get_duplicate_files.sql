@@ -0,0 +1,15 @@
+-- 内容が同じ登録済みファイル（:hash が空なら全グループ）
+WITH registered AS (
+    SELECT H.hash, H.file_size, I.illust_id, I.suffix, I.save_dir, I.file_name
+    FROM ILLUST_INFO I
+    JOIN FILE_HASH H
+      ON H.save_dir  = I.save_dir
+     AND H.file_name = I.file_name
+    WHERE (:hash = '' OR H.hash = :hash)
+)
+SELECT R.hash, R.file_size, R.illust_id, R.suffix, R.save_dir, R.file_name
+FROM registered R
+WHERE R.hash IN (
+    SELECT hash FROM registered GROUP BY hash HAVING COUNT(*) > 1
+)
+ORDER BY R.file_size DESC, R.hash, R.illust_id, R.suffix;
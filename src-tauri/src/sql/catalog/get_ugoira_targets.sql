@@ -0,0 +1,10 @@
+-- 未変換のうごイラ zip（ID指定が無ければ全件）
+SELECT I.illust_id, I.save_dir, I.file_name
+FROM ILLUST_INFO I
+LEFT JOIN UGOIRA_INFO U
+  ON I.illust_id = U.illust_id
+WHERE I.extension = 'zip'
+  AND I.save_dir IS NOT NULL
+  AND U.illust_id IS NULL
+  AND (:target_count = 0 OR I.illust_id IN (:illust_ids))
+ORDER BY I.illust_id;
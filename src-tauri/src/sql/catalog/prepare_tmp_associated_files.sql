@@ -8,7 +8,7 @@ JOIN tmp_target_files tf ON I.illust_id = tf.illust_id AND I.suffix = tf.suffix;
 -- 関連ファイルを tmp_associated_files に取得
 DROP TABLE IF EXISTS tmp_associated_files;
 CREATE TEMP TABLE tmp_associated_files AS
-SELECT I.illust_id || '_p' || I.suffix || '.' || I.extension AS key,
+SELECT I.file_name AS key,
        COALESCE(D.character, 'None') AS character,
        I.save_dir
 FROM ILLUST_INFO I
@@ -15,12 +15,7 @@ work_with_path AS (
         COALESCE(CU.character, CU.series) AS entity_key,
         CU.character,
         CU.series,
-        CASE
-            WHEN rv.root IS NULL THEN NULL
-            WHEN CU.series IS NULL THEN rv.root || '\' || CU.character
-            WHEN CU.character IS NULL THEN rv.root || '\' || CU.series
-            ELSE rv.root || '\' || CU.series || '\' || CU.character
-        END AS collect_dir
+        CU.collect_dir
     FROM COLLECT_UI_WORK CU
     CROSS JOIN root_value rv
     WHERE collect_type = 1
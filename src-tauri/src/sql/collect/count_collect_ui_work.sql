@@ -0,0 +1,41 @@
+-- キャラクターの事前カウント
+UPDATE COLLECT_UI_WORK
+SET before_count = (
+    SELECT COUNT(I.illust_id)
+    FROM ILLUST_DETAIL D
+    JOIN ILLUST_INFO I
+      ON I.illust_id = D.illust_id
+     AND I.save_dir = COLLECT_UI_WORK.collect_dir
+    WHERE D.character = COLLECT_UI_WORK.character
+)
+WHERE character IS NOT NULL;
+
+-- シリーズの事前カウント
+UPDATE COLLECT_UI_WORK
+SET before_count = (
+    SELECT COUNT(DISTINCT I.illust_id || '-' || I.suffix)
+    FROM ILLUST_INFO I
+    WHERE I.save_dir = COLLECT_UI_WORK.collect_dir
+)
+WHERE character IS NULL AND collect_type = 1;
+
+-- 未割り当て件数の集計と挿入
+INSERT OR IGNORE INTO COLLECT_UI_WORK (
+    id, entity_key, series, character, collect_dir, before_count, collect_type
+)
+SELECT
+    -1,
+    '-',
+    NULL,
+    NULL,
+    NULL,
+    (I.total_illust_count - COALESCE(T.total_after_count, 0)),
+    0
+FROM (
+    SELECT SUM(after_count) AS total_after_count
+    FROM COLLECT_UI_WORK
+) T,
+(
+    SELECT COUNT(DISTINCT illust_id || '-' || suffix) AS total_illust_count
+    FROM ILLUST_INFO
+) I;
@@ -19,7 +19,7 @@ char_count AS (
     GROUP BY illust_id, cnum
 ),
 uncategorized_dir AS (
-    SELECT value || :uncategorized_dir AS dir FROM COMMON_MST WHERE key = :collect_root
+    SELECT :uncategorized_dir AS dir WHERE :uncategorized_dir IS NOT NULL
 )
 INSERT INTO COLLECT_FILTER_WORK (
     illust_id,
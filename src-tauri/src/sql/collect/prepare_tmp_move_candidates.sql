@@ -4,12 +4,17 @@ SELECT
     I.illust_id,
     I.suffix,
     I.extension,
+    I.file_name,
+    U.zip_name,
     I.save_dir AS src_dir,
     F.collect_dir AS dest_dir
 FROM COLLECT_FILTER_WORK F
 JOIN ILLUST_INFO I
   ON F.illust_id = I.illust_id
  AND F.cnum = I.cnum
+LEFT JOIN UGOIRA_INFO U
+  ON U.illust_id = I.illust_id
+ AND I.suffix = 0
 WHERE I.save_dir IS NOT NULL
   AND F.collect_dir IS NOT NULL
   AND I.save_dir <> F.collect_dir;
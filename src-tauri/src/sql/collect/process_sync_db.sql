@@ -1,28 +1,51 @@
+-- 変換済みうごイラの元 zip は変換後のファイルに付随するため照合しない
+DELETE FROM SYNC_DB_WORK
+WHERE EXISTS (
+    SELECT 1 FROM UGOIRA_INFO U
+    WHERE U.illust_id = SYNC_DB_WORK.illust_id
+      AND U.zip_name  = SYNC_DB_WORK.file_name
+);
+
 -- (A) 消失したファイル
 DROP TABLE IF EXISTS tmp_missing_files;
 CREATE TEMP TABLE tmp_missing_files AS
-SELECT I.illust_id, I.suffix, I.save_dir,
-       I.save_dir || '\' || I.illust_id || '_p' || I.suffix || '.' || I.extension AS path
+SELECT I.illust_id, I.suffix, I.save_dir, I.file_name
 FROM ILLUST_INFO I
 LEFT JOIN SYNC_DB_WORK SW
   ON I.illust_id = SW.illust_id AND I.suffix = SW.suffix
 WHERE SW.rowid IS NULL;
 
--- (B) 移動したファイル
+-- (B) 移動したファイル（候補が複数あれば追加キャプチャの無いファイルを優先）
 DROP TABLE IF EXISTS tmp_moved_files;
 CREATE TEMP TABLE tmp_moved_files AS
-SELECT I.illust_id, I.suffix, SW.save_dir AS actual_save_dir,
-       I.save_dir || '\' || I.illust_id || '_p' || I.suffix || '.' || I.extension AS old_path,
-       SW.save_dir || '\' || I.illust_id || '_p' || I.suffix || '.' || I.extension AS new_path
-FROM ILLUST_INFO I
-JOIN SYNC_DB_WORK SW
-  ON I.illust_id = SW.illust_id AND I.suffix = SW.suffix
-WHERE IFNULL(I.save_dir,'') <> IFNULL(SW.save_dir,'');
+SELECT illust_id, suffix, actual_save_dir, actual_file_name, actual_extension
+FROM (
+    SELECT I.illust_id, I.suffix, SW.save_dir AS actual_save_dir,
+           SW.file_name AS actual_file_name, SW.extension AS actual_extension,
+           ROW_NUMBER() OVER (
+               PARTITION BY I.illust_id, I.suffix
+               ORDER BY (SW.variant <> '') ASC, SW.save_dir, SW.file_name
+           ) AS row_num
+    FROM ILLUST_INFO I
+    JOIN SYNC_DB_WORK SW
+      ON I.illust_id = SW.illust_id AND I.suffix = SW.suffix
+    WHERE (IFNULL(I.save_dir,'') <> IFNULL(SW.save_dir,'')
+           OR IFNULL(I.file_name,'') <> SW.file_name)
+      -- 登録済みのファイルが残っていれば移動扱いにしない
+      AND NOT EXISTS (
+          SELECT 1 FROM SYNC_DB_WORK S2
+          WHERE S2.illust_id = I.illust_id
+            AND S2.suffix    = I.suffix
+            AND S2.save_dir  = I.save_dir
+            AND S2.file_name = I.file_name
+      )
+)
+WHERE row_num = 1;
 
 -- ILLUST_INFO の更新は移動ケースのみ
 UPDATE ILLUST_INFO
-SET save_dir = (
-    SELECT mf.actual_save_dir
+SET (save_dir, file_name, extension) = (
+    SELECT mf.actual_save_dir, mf.actual_file_name, mf.actual_extension
     FROM tmp_moved_files mf
     WHERE mf.illust_id = ILLUST_INFO.illust_id
       AND mf.suffix    = ILLUST_INFO.suffix
@@ -43,20 +66,29 @@ WHERE EXISTS (
     WHERE I.illust_id = SYNC_DB_WORK.illust_id
       AND I.suffix    = SYNC_DB_WORK.suffix
       AND I.save_dir  = SYNC_DB_WORK.save_dir
+      AND I.file_name = SYNC_DB_WORK.file_name
 );
 
 
--- 6) 重複ファイルをゴミ箱へ
+-- 6) 重複ファイルの候補（内容が同じかは呼び出し側でハッシュを比べる）
 DROP TABLE IF EXISTS tmp_to_trash;
 CREATE TEMP TABLE tmp_to_trash AS
 WITH ranked AS (
     SELECT *,
            ROW_NUMBER() OVER (
-               PARTITION BY illust_id, suffix 
+               PARTITION BY illust_id, suffix
                ORDER BY in_db DESC
-           ) AS row_num
+           ) AS row_num,
+           FIRST_VALUE(save_dir) OVER (
+               PARTITION BY illust_id, suffix
+               ORDER BY in_db DESC
+           ) AS keep_save_dir,
+           FIRST_VALUE(file_name) OVER (
+               PARTITION BY illust_id, suffix
+               ORDER BY in_db DESC
+           ) AS keep_file_name
     FROM SYNC_DB_WORK
 )
-SELECT path
+SELECT path, save_dir, file_name, keep_save_dir, keep_file_name
 FROM ranked
 WHERE row_num > 1;
@@ -0,0 +1,4 @@
+-- フェッチ対象をジョブに登録
+INSERT INTO FETCH_JOB_ITEM (job_id, illust_id, status, updated_at)
+SELECT :job_id, illust_id, :pending, :now
+FROM tmp_fetch_ids;
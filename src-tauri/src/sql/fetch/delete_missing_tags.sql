@@ -2,6 +2,8 @@ DELETE FROM TAG_INFO
 WHERE tag = 'Missing'
 AND EXISTS (
     SELECT 1
-    FROM ILLUST_DETAIL
-    WHERE ILLUST_DETAIL.author_id <> 0
-);
\ No newline at end of file
+    FROM ILLUST_DETAIL D
+    WHERE D.illust_id = TAG_INFO.illust_id
+      AND D.cnum = TAG_INFO.cnum
+      AND D.author_id <> 0
+);
@@ -1,15 +1,24 @@
--- missing_files.sql
+-- 取得に失敗したファイルのうち、再試行する価値のあるものを抽出
+-- （失敗種別が一時的なもの、または種別未記録の旧データ）
 SELECT I.illust_id,
        I.suffix,
        I.extension,
        I.save_dir,
-       I.save_dir || '\'  || I.illust_id || '_p' || I.suffix || '.' || I.extension AS file_path,
+       I.file_name,
+       0 AS created_time,
+       0 AS file_size
 FROM ILLUST_DETAIL D
-JOIN TAG_INFO T
-  ON D.illust_id = T.illust_id AND D.cnum = T.cnum
 JOIN ILLUST_INFO I
-  ON D.illust_id = I.illust_id
+  ON D.illust_id = I.illust_id AND D.cnum = I.cnum
+LEFT JOIN FETCH_FAILURE F
+  ON D.illust_id = F.illust_id
 WHERE D.author_id = 0
-GROUP BY D.illust_id, D.cnum
-HAVING COUNT(*) = 1
-   AND SUM(CASE WHEN T.tag = 'Missing' THEN 1 ELSE 0 END) = 1;
+  AND (F.illust_id IS NULL OR F.reason IN (:transient_reasons))
+  AND EXISTS (
+      SELECT 1 FROM TAG_INFO T
+      WHERE T.illust_id = D.illust_id AND T.cnum = D.cnum AND T.tag = 'Missing'
+  )
+  AND NOT EXISTS (
+      SELECT 1 FROM TAG_INFO T
+      WHERE T.illust_id = D.illust_id AND T.cnum = D.cnum AND T.tag <> 'Missing'
+  );
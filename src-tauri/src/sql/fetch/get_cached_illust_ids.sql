@@ -0,0 +1,6 @@
+SELECT C.illust_id
+FROM ILLUST_RAW_CACHE C
+WHERE EXISTS (
+    SELECT 1 FROM ILLUST_INFO I WHERE I.illust_id = C.illust_id
+)
+ORDER BY C.illust_id
@@ -0,0 +1,11 @@
+SELECT R.run_id,
+       R.stale_days,
+       R.target_count,
+       R.success_count,
+       R.fail_count,
+       (SELECT COUNT(*) FROM REFRESH_CHANGE C WHERE C.run_id = R.run_id) AS change_count,
+       R.cancelled,
+       R.started_at,
+       R.finished_at
+FROM REFRESH_RUN R
+ORDER BY R.run_id DESC;
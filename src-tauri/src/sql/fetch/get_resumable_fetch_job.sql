@@ -0,0 +1,15 @@
+SELECT
+    J.job_id,
+    SUM(CASE WHEN JI.status = :pending THEN 1 ELSE 0 END) AS pending,
+    SUM(CASE WHEN JI.status = :done THEN 1 ELSE 0 END) AS done,
+    SUM(CASE WHEN JI.status = :failed THEN 1 ELSE 0 END) AS failed,
+    SUM(CASE WHEN JI.status = :skipped THEN 1 ELSE 0 END) AS skipped,
+    J.created_at
+FROM FETCH_JOB J
+JOIN FETCH_JOB_ITEM JI ON J.job_id = JI.job_id
+WHERE J.status = :running
+  AND J.kind = :api
+GROUP BY J.job_id, J.created_at
+HAVING SUM(CASE WHEN JI.status = :pending THEN 1 ELSE 0 END) > 0
+ORDER BY J.job_id DESC
+LIMIT 1;
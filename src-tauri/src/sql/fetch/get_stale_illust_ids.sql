@@ -0,0 +1,13 @@
+-- 最終取得から一定期間たった作品を古い順に抽出
+-- （メタ情報が無い旧データは最も古いものとして扱う。取得不能と判明した作品は除く）
+SELECT D.illust_id
+FROM ILLUST_DETAIL D
+LEFT JOIN ILLUST_META M
+  ON D.illust_id = M.illust_id
+LEFT JOIN FETCH_FAILURE F
+  ON D.illust_id = F.illust_id
+WHERE D.author_id <> 0
+  AND COALESCE(M.fetched_at, 0) < :threshold
+  AND (F.illust_id IS NULL OR F.reason IN (:transient_reasons))
+GROUP BY D.illust_id
+ORDER BY MIN(COALESCE(M.fetched_at, 0)), D.illust_id;
@@ -1,11 +1,12 @@
 INSERT OR IGNORE INTO ILLUST_INFO (
-    illust_id, suffix, extension, save_dir, cnum
+    illust_id, suffix, extension, save_dir, file_name, cnum
 )
 SELECT
     t.illust_id,
     t.suffix,
     t.extension,
     t.save_dir,
+    t.file_name,
     ?1
 FROM tmp_insert_files AS t
 WHERE t.illust_id = ?2;
\ No newline at end of file
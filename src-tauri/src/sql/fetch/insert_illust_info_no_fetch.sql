@@ -1,4 +1,5 @@
 -- 一時テーブル inserted を作る
+DROP TABLE IF EXISTS tmp_inserted;
 CREATE TEMP TABLE tmp_inserted AS
 WITH base AS (
     SELECT inf.*
@@ -24,13 +25,14 @@ SELECT
     b.suffix,
     b.extension,
     b.save_dir,
+    b.file_name,
     cm.cnum
 FROM base b
 JOIN cnum_map cm ON b.illust_id = cm.illust_id;
 
 -- ILLUST_INFO に挿入
 INSERT OR IGNORE INTO ILLUST_INFO (
-    illust_id, suffix, extension, save_dir, cnum
+    illust_id, suffix, extension, save_dir, file_name, cnum
 )
 SELECT * FROM tmp_inserted;
 
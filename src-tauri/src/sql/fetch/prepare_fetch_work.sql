@@ -1,4 +1,13 @@
+-- 0. 変換済みうごイラの元 zip は登録済みファイルの付随物として扱う
+DELETE FROM ILLUST_FETCH_WORK
+WHERE EXISTS (
+    SELECT 1 FROM UGOIRA_INFO U
+    WHERE U.illust_id = ILLUST_FETCH_WORK.illust_id
+      AND U.zip_name  = ILLUST_FETCH_WORK.file_name
+);
+
 -- 1. 優先度の高いファイルを抽出（suffixごとに1件だけ）
+--    縮小版など追加キャプチャのあるファイルより元のファイルを優先する
 DROP TABLE IF EXISTS tmp_insert_files;
 CREATE TEMP TABLE tmp_insert_files AS
 SELECT *
@@ -6,7 +15,7 @@ FROM (
   SELECT *,
          ROW_NUMBER() OVER (
            PARTITION BY illust_id, suffix
-           ORDER BY file_size ASC, created_time ASC
+           ORDER BY (variant <> '') ASC, file_size ASC, created_time ASC
          ) AS row_num
   FROM ILLUST_FETCH_WORK
 ) AS ranked
@@ -26,12 +35,13 @@ DROP TABLE IF EXISTS tmp_delete_files;
 CREATE TEMP TABLE tmp_delete_files AS
 WITH tmp AS (
     SELECT
-        W.save_dir || '\' || W.illust_id || '_p' || W.suffix || '.' || W.extension AS file_path,
-        I.save_dir || '\' || I.illust_id || '_p' || I.suffix || '.' || I.extension AS keep_file_path,
+        I.save_dir AS keep_save_dir,
+        I.file_name AS keep_file_name,
         W.illust_id,
         W.suffix,
         W.extension,
-        W.save_dir
+        W.save_dir,
+        W.file_name
     FROM ILLUST_FETCH_WORK AS W
     LEFT JOIN tmp_insert_files AS I
            ON W.illust_id = I.illust_id
@@ -39,8 +49,8 @@ WITH tmp AS (
 )
 SELECT *
 FROM tmp
-WHERE keep_file_path IS NOT NULL
-  AND file_path <> keep_file_path;
+WHERE keep_file_name IS NOT NULL
+  AND (save_dir <> keep_save_dir OR file_name <> keep_file_name);
 
 -- 4. フェッチ対象のIDを抽出（author_id = 0 の取得済みを除外）
 DROP TABLE IF EXISTS tmp_fetch_ids;
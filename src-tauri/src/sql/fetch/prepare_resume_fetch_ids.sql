@@ -0,0 +1,9 @@
+-- 再開時はジョブの未処理IDをフェッチ対象とする
+DROP TABLE IF EXISTS tmp_fetch_ids;
+CREATE TEMP TABLE tmp_fetch_ids AS
+SELECT DISTINCT F.illust_id
+FROM tmp_insert_files F
+JOIN FETCH_JOB_ITEM J
+  ON F.illust_id = J.illust_id
+WHERE J.job_id = :job_id
+  AND J.status = :pending;
@@ -0,0 +1,5 @@
+-- 再取得時は取得失敗済み（author_id = 0）のIDもフェッチ対象とする
+DROP TABLE IF EXISTS tmp_fetch_ids;
+CREATE TEMP TABLE tmp_fetch_ids AS
+SELECT DISTINCT illust_id
+FROM tmp_insert_files;
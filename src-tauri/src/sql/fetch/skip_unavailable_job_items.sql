@@ -0,0 +1,6 @@
+-- ワークテーブルから消えたフェッチ対象はスキップ扱いにする
+UPDATE FETCH_JOB_ITEM
+SET status = :skipped, updated_at = :now
+WHERE job_id = :job_id
+  AND status = :pending
+  AND illust_id NOT IN (SELECT illust_id FROM tmp_fetch_ids);
@@ -0,0 +1,7 @@
+-- 最後に見かけた名前を現在の作者情報とする
+INSERT OR REPLACE INTO AUTHOR_INFO (author_id, author_name, author_account)
+SELECT author_id, author_name, author_account
+FROM AUTHOR_NAME_HISTORY
+WHERE author_id = ?
+ORDER BY last_seen DESC, first_seen DESC, rowid DESC
+LIMIT 1;
@@ -0,0 +1,6 @@
+-- 名前・アカウントの組み合わせごとに見かけた期間を記録
+INSERT INTO AUTHOR_NAME_HISTORY (author_id, author_name, author_account, first_seen, last_seen)
+VALUES (?1, ?2, ?3, ?4, ?4)
+ON CONFLICT (author_id, author_name, author_account) DO UPDATE SET
+    first_seen = MIN(first_seen, excluded.first_seen),
+    last_seen = MAX(last_seen, excluded.last_seen);
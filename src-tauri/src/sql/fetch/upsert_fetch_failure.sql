@@ -0,0 +1,9 @@
+-- attempts は再試行も含めたリクエスト回数の累計
+INSERT INTO FETCH_FAILURE (illust_id, reason, http_status, attempts, message, last_attempt_at)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+ON CONFLICT(illust_id) DO UPDATE SET
+    reason = excluded.reason,
+    http_status = excluded.http_status,
+    attempts = FETCH_FAILURE.attempts + excluded.attempts,
+    message = excluded.message,
+    last_attempt_at = excluded.last_attempt_at
@@ -0,0 +1,5 @@
+INSERT INTO ILLUST_DETAIL (illust_id, cnum, author_id, character, created_at)
+VALUES (?1, ?2, ?3, NULL, strftime('%s', ?4))
+ON CONFLICT(illust_id, cnum) DO UPDATE SET
+    author_id = excluded.author_id,
+    created_at = excluded.created_at
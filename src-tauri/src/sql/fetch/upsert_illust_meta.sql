@@ -0,0 +1,6 @@
+INSERT OR REPLACE INTO ILLUST_META (
+    illust_id, title, caption, page_count, width, height,
+    total_bookmarks, total_view, x_restrict, illust_ai_type,
+    tools, series_id, series_title, fetched_at
+)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
@@ -4,6 +4,7 @@ CREATE TABLE IF NOT EXISTS ILLUST_INFO (
     cnum INTEGER NOT NULL,
     extension TEXT NOT NULL,
     save_dir TEXT,
+    file_name TEXT,
     PRIMARY KEY (illust_id, suffix)
 );
 CREATE INDEX IF NOT EXISTS idx_illust_info_illust_control ON ILLUST_INFO(illust_id, cnum);
@@ -29,13 +30,153 @@ CREATE TABLE IF NOT EXISTS ILLUST_FETCH_WORK (
     suffix INTEGER NOT NULL,
     extension TEXT NOT NULL,
     save_dir TEXT NOT NULL,
+    file_name TEXT NOT NULL DEFAULT '',
     created_time INTEGER NOT NULL,
-    file_size INTEGER NOT NULL
+    file_size INTEGER NOT NULL,
+    variant TEXT NOT NULL DEFAULT ''
 );
-CREATE UNIQUE INDEX IF NOT EXISTS idx_illust_fetch_uniq_illust_suffix ON ILLUST_FETCH_WORK (illust_id, suffix);
+-- 同じ作品・ページのファイルが複数あってもよい（登録する1件は prepare_fetch_work で選ぶ）
+CREATE INDEX IF NOT EXISTS idx_illust_fetch_illust_suffix ON ILLUST_FETCH_WORK (illust_id, suffix);
 CREATE INDEX IF NOT EXISTS idx_illust_fetch_save_dir ON ILLUST_FETCH_WORK (save_dir);
 
 
+CREATE TABLE IF NOT EXISTS ILLUST_META (
+    illust_id INTEGER PRIMARY KEY,
+    title TEXT NOT NULL,
+    caption TEXT NOT NULL DEFAULT '',
+    page_count INTEGER NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    total_bookmarks INTEGER NOT NULL DEFAULT 0,
+    total_view INTEGER NOT NULL DEFAULT 0,
+    x_restrict INTEGER NOT NULL DEFAULT 0,
+    illust_ai_type INTEGER NOT NULL DEFAULT 0,
+    tools TEXT NOT NULL DEFAULT '[]',
+    series_id INTEGER,
+    series_title TEXT,
+    fetched_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_illust_meta_bookmarks ON ILLUST_META(total_bookmarks);
+CREATE INDEX IF NOT EXISTS idx_illust_meta_series ON ILLUST_META(series_id);
+
+
+CREATE TABLE IF NOT EXISTS UGOIRA_FRAME (
+    illust_id INTEGER NOT NULL,
+    frame_index INTEGER NOT NULL,
+    file TEXT NOT NULL,
+    delay INTEGER NOT NULL,
+    PRIMARY KEY (illust_id, frame_index)
+);
+
+
+CREATE TABLE IF NOT EXISTS UGOIRA_INFO (
+    illust_id INTEGER PRIMARY KEY,
+    zip_name TEXT NOT NULL,
+    format INTEGER NOT NULL,
+    converted_at INTEGER NOT NULL
+);
+
+
+CREATE TABLE IF NOT EXISTS FILE_HASH (
+    save_dir TEXT NOT NULL,
+    file_name TEXT NOT NULL,
+    file_size INTEGER NOT NULL,
+    modified_at INTEGER NOT NULL,
+    hash TEXT NOT NULL,
+    PRIMARY KEY (save_dir, file_name)
+);
+CREATE INDEX IF NOT EXISTS idx_file_hash_hash ON FILE_HASH(hash);
+
+
+CREATE TABLE IF NOT EXISTS IMAGE_HASH (
+    save_dir TEXT NOT NULL,
+    file_name TEXT NOT NULL,
+    file_size INTEGER NOT NULL,
+    modified_at INTEGER NOT NULL,
+    dhash INTEGER NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    PRIMARY KEY (save_dir, file_name)
+);
+
+
+CREATE TABLE IF NOT EXISTS FILE_HEALTH (
+    save_dir TEXT NOT NULL,
+    file_name TEXT NOT NULL,
+    file_size INTEGER NOT NULL,
+    modified_at INTEGER NOT NULL,
+    status INTEGER NOT NULL,
+    detected_format TEXT,
+    width INTEGER,
+    height INTEGER,
+    message TEXT,
+    checked_at INTEGER NOT NULL,
+    PRIMARY KEY (save_dir, file_name)
+);
+CREATE INDEX IF NOT EXISTS idx_file_health_status ON FILE_HEALTH(status);
+
+
+CREATE TABLE IF NOT EXISTS ILLUST_RAW_CACHE (
+    illust_id INTEGER PRIMARY KEY,
+    body BLOB NOT NULL,
+    fetched_at INTEGER NOT NULL
+);
+
+
+CREATE TABLE IF NOT EXISTS FETCH_JOB (
+    job_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    status INTEGER NOT NULL,
+    kind INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_fetch_job_status ON FETCH_JOB(status);
+
+
+CREATE TABLE IF NOT EXISTS FETCH_JOB_ITEM (
+    job_id INTEGER NOT NULL,
+    illust_id INTEGER NOT NULL,
+    status INTEGER NOT NULL DEFAULT 0,
+    message TEXT,
+    updated_at INTEGER,
+    PRIMARY KEY (job_id, illust_id)
+);
+CREATE INDEX IF NOT EXISTS idx_fetch_job_item_status ON FETCH_JOB_ITEM(job_id, status);
+
+
+CREATE TABLE IF NOT EXISTS FETCH_FAILURE (
+    illust_id INTEGER PRIMARY KEY,
+    reason INTEGER NOT NULL,
+    http_status INTEGER,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    message TEXT,
+    last_attempt_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_fetch_failure_reason ON FETCH_FAILURE(reason);
+
+
+CREATE TABLE IF NOT EXISTS REFRESH_RUN (
+    run_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    stale_days INTEGER NOT NULL,
+    target_count INTEGER NOT NULL,
+    success_count INTEGER NOT NULL DEFAULT 0,
+    fail_count INTEGER NOT NULL DEFAULT 0,
+    cancelled INTEGER NOT NULL DEFAULT 0,
+    started_at INTEGER NOT NULL,
+    finished_at INTEGER
+);
+
+
+CREATE TABLE IF NOT EXISTS REFRESH_CHANGE (
+    run_id INTEGER NOT NULL,
+    illust_id INTEGER NOT NULL,
+    kind INTEGER NOT NULL,
+    old_value TEXT,
+    new_value TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_refresh_change_run ON REFRESH_CHANGE(run_id, illust_id);
+
+
 CREATE TABLE IF NOT EXISTS TAG_INFO (
     illust_id INTEGER NOT NULL,
     cnum INTEGER NOT NULL,
@@ -46,6 +187,13 @@ CREATE INDEX IF NOT EXISTS idx_tag_info_tag ON TAG_INFO(tag);
 CREATE INDEX IF NOT EXISTS idx_tag_info_illust_control ON TAG_INFO(illust_id, cnum);
 
 
+CREATE TABLE IF NOT EXISTS TAG_TRANSLATION (
+    tag TEXT PRIMARY KEY,
+    translated_name TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_tag_translation_name ON TAG_TRANSLATION(translated_name);
+
+
 CREATE TABLE IF NOT EXISTS CHARACTER_INFO (
     entity_key TEXT NOT NULL,
     series TEXT,
@@ -93,6 +241,17 @@ CREATE TABLE IF NOT EXISTS AUTHOR_INFO (
 INSERT OR IGNORE INTO AUTHOR_INFO (author_id, author_name, author_account) VALUES (0, 'Missing', 'Missing');
 
 
+CREATE TABLE IF NOT EXISTS AUTHOR_NAME_HISTORY (
+    author_id INTEGER NOT NULL,
+    author_name TEXT NOT NULL,
+    author_account TEXT NOT NULL,
+    first_seen INTEGER NOT NULL,
+    last_seen INTEGER NOT NULL,
+    PRIMARY KEY (author_id, author_name, author_account)
+);
+CREATE INDEX IF NOT EXISTS idx_author_name_history_name ON AUTHOR_NAME_HISTORY(author_name);
+
+
 CREATE TABLE IF NOT EXISTS TAG_FIX_RULES (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     src_tag TEXT NOT NULL,
@@ -104,6 +263,33 @@ CREATE INDEX IF NOT EXISTS idx_tag_fix_rules_src_tag ON TAG_FIX_RULES(src_tag);
 CREATE INDEX IF NOT EXISTS idx_tag_fix_rules_dst_tag ON TAG_FIX_RULES(dst_tag);
 
 
+-- ファイル移動の途中経過（起動時に途中で止まった移動を片付ける）
+CREATE TABLE IF NOT EXISTS MOVE_JOURNAL (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    illust_id INTEGER NOT NULL,
+    suffix INTEGER NOT NULL,
+    file_name TEXT NOT NULL,
+    zip_name TEXT,
+    src_dir TEXT NOT NULL,
+    dest_dir TEXT NOT NULL,
+    state INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+
+-- 元に戻すための操作履歴（before_state / after_state は対象作品の登録内容とファイルの場所の JSON）
+CREATE TABLE IF NOT EXISTS OPERATION_JOURNAL (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind INTEGER NOT NULL,
+    file_count INTEGER NOT NULL,
+    detail TEXT,
+    before_state TEXT NOT NULL,
+    after_state TEXT NOT NULL,
+    undone INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL
+);
+
+
 CREATE TABLE IF NOT EXISTS COMMON_MST (
     key TEXT NOT NULL,
     value TEXT,
@@ -115,6 +301,8 @@ CREATE TABLE IF NOT EXISTS SYNC_DB_WORK (
     suffix INTEGER NOT NULL,
     extension TEXT NOT NULL,
     save_dir TEXT NOT NULL,
+    file_name TEXT NOT NULL DEFAULT '',
     path TEXT NOT NULL,
+    variant TEXT NOT NULL DEFAULT '',
     in_db BOOLEAN DEFAULT 0
 );
\ No newline at end of file
@@ -0,0 +1,15 @@
+-- 旧バージョンで登録したファイルは標準の名前で保存されている
+UPDATE ILLUST_INFO
+SET file_name = illust_id || '_p' || suffix || '.' || extension
+WHERE file_name IS NULL;
+
+-- 履歴導入前に登録済みの作者を引き継ぐ（一度だけ）
+INSERT OR IGNORE INTO AUTHOR_NAME_HISTORY (author_id, author_name, author_account, first_seen, last_seen)
+SELECT author_id, author_name, author_account, strftime('%s', 'now'), strftime('%s', 'now')
+FROM AUTHOR_INFO
+WHERE author_id <> 0
+  AND NOT EXISTS (SELECT 1 FROM COMMON_MST WHERE key = 'author_history_migrated');
+INSERT OR IGNORE INTO COMMON_MST (key, value) VALUES ('author_history_migrated', '1');
+
+-- 追加キャプチャの違うファイルが同じ作品・ページになることがあるため一意にしない
+DROP INDEX IF EXISTS idx_illust_fetch_uniq_illust_suffix;
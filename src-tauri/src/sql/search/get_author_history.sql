@@ -0,0 +1,4 @@
+SELECT author_name, author_account, first_seen, last_seen
+FROM AUTHOR_NAME_HISTORY
+WHERE author_id = ?
+ORDER BY last_seen DESC, first_seen DESC;
@@ -2,12 +2,26 @@ SELECT
     A.author_id,
     A.author_name,
     A.author_account,
-    COUNT(DISTINCT I.illust_id || '-' || I.suffix) AS count
+    COUNT(DISTINCT I.illust_id || '-' || I.suffix) AS count,
+    (
+        SELECT GROUP_CONCAT(DISTINCT H.author_name)
+        FROM AUTHOR_NAME_HISTORY H
+        WHERE H.author_id = A.author_id AND H.author_name <> A.author_name
+    ) AS former_names
 FROM ILLUST_INFO I
 INNER JOIN AUTHOR_INFO A ON A.author_id = D.author_id
 INNER JOIN ILLUST_DETAIL D ON I.illust_id = D.illust_id AND I.cnum = D.cnum
 WHERE (:character IS NULL OR D.character = :character)
     AND (:author_id IS NULL OR D.author_id = :author_id)
+    -- 以前の名前・アカウントでも作者を指定できる
+    AND (
+            :author_name IS NULL
+            OR D.author_id IN (
+                SELECT H.author_id FROM AUTHOR_NAME_HISTORY H
+                WHERE H.author_name LIKE '%' || :author_name || '%'
+                   OR H.author_account LIKE '%' || :author_name || '%'
+            )
+        )
     AND (
             :tag_count = 0
             OR EXISTS (
@@ -15,7 +29,14 @@ WHERE (:character IS NULL OR D.character = :character)
                 FROM TAG_INFO T2
                 WHERE T2.illust_id = D.illust_id
                 AND T2.cnum      = D.cnum
-                AND T2.tag IN (:tags)
+                AND (
+                    T2.tag IN (:tags)
+                    -- 翻訳名での指定も受け付ける
+                    OR T2.tag IN (
+                        SELECT TR.tag FROM TAG_TRANSLATION TR
+                        WHERE TR.translated_name IN (:tags)
+                    )
+                )
             )
         )
 GROUP BY A.author_id, A.author_name, A.author_account
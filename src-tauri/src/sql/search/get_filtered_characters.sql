@@ -7,6 +7,15 @@ LEFT JOIN ILLUST_INFO I ON D.illust_id = I.illust_id
 WHERE C.character IS NOT NULL
     AND (:character IS NULL OR D.character = :character)
     AND (:author_id IS NULL OR D.author_id = :author_id)
+    -- 以前の名前・アカウントでも作者を指定できる
+    AND (
+            :author_name IS NULL
+            OR D.author_id IN (
+                SELECT H.author_id FROM AUTHOR_NAME_HISTORY H
+                WHERE H.author_name LIKE '%' || :author_name || '%'
+                   OR H.author_account LIKE '%' || :author_name || '%'
+            )
+        )
     AND (
             :tag_count = 0
             OR EXISTS (
@@ -14,7 +23,14 @@ WHERE C.character IS NOT NULL
                 FROM TAG_INFO T2
                 WHERE T2.illust_id = D.illust_id
                 AND T2.cnum      = D.cnum
-                AND T2.tag IN (:tags)
+                AND (
+                    T2.tag IN (:tags)
+                    -- 翻訳名での指定も受け付ける
+                    OR T2.tag IN (
+                        SELECT TR.tag FROM TAG_TRANSLATION TR
+                        WHERE TR.translated_name IN (:tags)
+                    )
+                )
             )
         )
 GROUP BY C.character
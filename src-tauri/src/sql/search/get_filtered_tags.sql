@@ -1,10 +1,20 @@
-SELECT T.tag, COUNT(DISTINCT I.illust_id || '-' || I.suffix) AS count
+SELECT T.tag, COUNT(DISTINCT I.illust_id || '-' || I.suffix) AS count, TR.translated_name
 FROM TAG_INFO T
 JOIN ILLUST_INFO I
 ON T.illust_id = I.illust_id AND T.cnum = I.cnum
+LEFT JOIN TAG_TRANSLATION TR ON T.tag = TR.tag
 INNER JOIN ILLUST_DETAIL D ON I.illust_id = D.illust_id AND I.cnum = D.cnum
 WHERE (:character IS NULL OR D.character = :character)
     AND (:author_id IS NULL OR D.author_id = :author_id)
+    -- 以前の名前・アカウントでも作者を指定できる
+    AND (
+            :author_name IS NULL
+            OR D.author_id IN (
+                SELECT H.author_id FROM AUTHOR_NAME_HISTORY H
+                WHERE H.author_name LIKE '%' || :author_name || '%'
+                   OR H.author_account LIKE '%' || :author_name || '%'
+            )
+        )
     AND (
             :tag_count = 0
             OR EXISTS (
@@ -12,8 +22,15 @@ WHERE (:character IS NULL OR D.character = :character)
                 FROM TAG_INFO T2
                 WHERE T2.illust_id = D.illust_id
                 AND T2.cnum      = D.cnum
-                AND T2.tag IN (:tags)
+                AND (
+                    T2.tag IN (:tags)
+                    -- 翻訳名での指定も受け付ける
+                    OR T2.tag IN (
+                        SELECT TR.tag FROM TAG_TRANSLATION TR
+                        WHERE TR.translated_name IN (:tags)
+                    )
+                )
             )
         )
-GROUP BY T.tag
+GROUP BY T.tag, TR.translated_name
 ORDER BY count DESC, T.tag ASC;
\ No newline at end of file
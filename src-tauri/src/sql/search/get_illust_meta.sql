@@ -0,0 +1,16 @@
+SELECT illust_id,
+       title,
+       caption,
+       page_count,
+       width,
+       height,
+       total_bookmarks,
+       total_view,
+       x_restrict,
+       illust_ai_type,
+       tools,
+       series_id,
+       series_title,
+       fetched_at
+FROM ILLUST_META
+WHERE illust_id = ?1
@@ -2,7 +2,12 @@ SELECT
     A.author_id,
     A.author_name,
     A.author_account,
-    COUNT(DISTINCT I.illust_id || '-' || I.suffix) AS count
+    COUNT(DISTINCT I.illust_id || '-' || I.suffix) AS count,
+    (
+        SELECT GROUP_CONCAT(DISTINCT H.author_name)
+        FROM AUTHOR_NAME_HISTORY H
+        WHERE H.author_id = A.author_id AND H.author_name <> A.author_name
+    ) AS former_names
 FROM ILLUST_INFO I
 INNER JOIN AUTHOR_INFO A ON A.author_id = D.author_id
 INNER JOIN ILLUST_DETAIL D ON I.illust_id = D.illust_id AND I.cnum = D.cnum
@@ -1,6 +1,7 @@
-SELECT T.tag, COUNT(DISTINCT I.illust_id || '-' || I.suffix) AS count
+SELECT T.tag, COUNT(DISTINCT I.illust_id || '-' || I.suffix) AS count, TR.translated_name
 FROM TAG_INFO T
 JOIN ILLUST_INFO I
 ON T.illust_id = I.illust_id AND T.cnum = I.cnum
-GROUP BY T.tag
+LEFT JOIN TAG_TRANSLATION TR ON T.tag = TR.tag
+GROUP BY T.tag, TR.translated_name
 ORDER BY count DESC, T.tag ASC;
\ No newline at end of file
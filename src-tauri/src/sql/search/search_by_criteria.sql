@@ -4,6 +4,7 @@ WITH base AS (
         I.suffix,
         I.extension,
         I.save_dir,
+        I.file_name,
         I.cnum,
         D.character,
         D.author_id,
@@ -13,13 +14,28 @@ WITH base AS (
     JOIN AUTHOR_INFO A ON D.author_id = A.author_id
     WHERE (:character IS NULL OR D.character = :character)
       AND (:author_id IS NULL OR D.author_id = :author_id)
+      -- 以前の名前・アカウントでも作者を指定できる
+      AND (
+          :author_name IS NULL
+          OR D.author_id IN (
+              SELECT H.author_id FROM AUTHOR_NAME_HISTORY H
+              WHERE H.author_name LIKE '%' || :author_name || '%'
+                 OR H.author_account LIKE '%' || :author_name || '%'
+          )
+      )
 ),
 filter AS (
     -- 検索条件に合致するレコードのみ抽出
     SELECT DISTINCT b.illust_id, suffix
     FROM base b
     LEFT JOIN TAG_INFO T ON b.illust_id = T.illust_id AND b.cnum = T.cnum
-    WHERE :tag_count = 0 OR T.tag IN (:tags)
+    WHERE :tag_count = 0
+       OR T.tag IN (:tags)
+       -- 翻訳名での指定も受け付ける
+       OR T.tag IN (
+           SELECT TR.tag FROM TAG_TRANSLATION TR
+           WHERE TR.translated_name IN (:tags)
+       )
 ),
 tagged AS (
     -- 条件に合致したイラストのみ選択するが、全タグを集計
@@ -28,13 +44,14 @@ tagged AS (
         b.suffix,
         b.extension,
         b.save_dir,
-        b.illust_id || '_p' || b.suffix || '.' || b.extension AS file_name,
-        b.save_dir || '\'  || b.illust_id || '_p' || b.suffix || '.' || b.extension AS thumbnail_url,
+        b.file_name,
         b.character,
         b.author_name,
-        GROUP_CONCAT(T.tag, ',') AS tags
+        GROUP_CONCAT(T.tag, ',') AS tags,
+        GROUP_CONCAT(COALESCE(TR.translated_name, T.tag), ',') AS translated_tags
     FROM base b
     LEFT JOIN TAG_INFO T ON b.illust_id = T.illust_id AND b.cnum = T.cnum
+    LEFT JOIN TAG_TRANSLATION TR ON T.tag = TR.tag
     JOIN filter f ON b.illust_id = f.illust_id AND b.suffix = f.suffix
     GROUP BY b.illust_id, b.suffix
 )
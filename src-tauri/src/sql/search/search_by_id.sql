@@ -3,11 +3,11 @@ SELECT
     I.suffix,
     I.extension,
     I.save_dir,
-    I.illust_id || '_p' || I.suffix || '.' || I.extension AS file_name,
-    I.save_dir || '\'  || I.illust_id || '_p' || I.suffix || '.' || I.extension AS thumbnail_url,
+    I.file_name,
     D.character,
     A.author_name,
-    GROUP_CONCAT(T.tag, ',') AS tags
+    GROUP_CONCAT(T.tag, ',') AS tags,
+    GROUP_CONCAT(COALESCE(TR.translated_name, T.tag), ',') AS translated_tags
 FROM ILLUST_INFO I
 LEFT JOIN ILLUST_DETAIL D
     ON I.illust_id = D.illust_id AND I.cnum = D.cnum
@@ -15,6 +15,8 @@ LEFT JOIN AUTHOR_INFO A
     ON D.author_id = A.author_id
 LEFT JOIN TAG_INFO T
     ON I.illust_id = T.illust_id AND I.cnum = T.cnum
+LEFT JOIN TAG_TRANSLATION TR
+    ON T.tag = TR.tag
 WHERE I.illust_id = ?
 GROUP BY 
     I.illust_id,
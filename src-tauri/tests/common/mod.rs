@@ -0,0 +1,82 @@
+// テストごとに使う関数が異なるため
+#![allow(dead_code)]
+
+use anyhow::Result;
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::SqlitePool;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use pixiv_file_manager_lib::init_db;
+use pixiv_file_manager_lib::models::fetch::{ProgressSink, TagProgress};
+
+/// 1接続だけのインメモリDB（一時テーブルを共有するため）
+pub async fn memory_pool() -> SqlitePool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    init_db(&pool).await.unwrap();
+    pool
+}
+
+pub fn fixture_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/illust")
+}
+
+pub fn text(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// 作品を1件登録する（拡張子はファイル名から取る）
+pub async fn register_file(
+    pool: &SqlitePool,
+    illust_id: i32,
+    save_dir: impl AsRef<Path>,
+    file_name: &str,
+) {
+    let extension = Path::new(file_name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_default();
+    sqlx::query(
+        "INSERT INTO ILLUST_INFO (illust_id, suffix, cnum, extension, save_dir, file_name)
+         VALUES (?, 0, 1, ?, ?, ?)",
+    )
+    .bind(illust_id)
+    .bind(extension)
+    .bind(text(save_dir.as_ref()))
+    .bind(file_name)
+    .execute(pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO ILLUST_DETAIL (illust_id, cnum, author_id) VALUES (?, 1, 0)")
+        .bind(illust_id)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+/// 指定サイズのダミー画像を作る
+pub fn write_file(dir: &Path, name: &str, size: usize) -> PathBuf {
+    fs::create_dir_all(dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, vec![0u8; size]).unwrap();
+    path
+}
+
+#[derive(Default)]
+pub struct RecordingSink {
+    pub progress: Mutex<Vec<TagProgress>>,
+}
+
+impl ProgressSink for RecordingSink {
+    fn report(&self, progress: &TagProgress) -> Result<()> {
+        self.progress.lock().unwrap().push(progress.clone());
+        Ok(())
+    }
+}
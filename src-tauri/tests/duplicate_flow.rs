@@ -0,0 +1,141 @@
+mod common;
+
+use std::path::Path;
+
+use pixiv_file_manager_lib::models::catalog::DuplicateFile;
+use pixiv_file_manager_lib::service::collect::process_sync_db;
+use pixiv_file_manager_lib::service::duplicate::{
+    ensure_file_hashes, hash_file, process_get_duplicate_report, process_resolve_duplicates,
+};
+
+use common::{memory_pool, register_file, text, write_file};
+
+fn key(dir: &Path, name: &str) -> (String, String) {
+    (text(dir), name.to_string())
+}
+
+#[tokio::test]
+async fn hashes_are_reused_until_size_or_mtime_changes() {
+    let pool = memory_pool().await;
+    let mut conn = pool.acquire().await.unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    let path = write_file(&dir, "100_p0.jpg", 10);
+    let file = key(&dir, "100_p0.jpg");
+
+    let hashes = ensure_file_hashes(&mut conn, &[file.clone()])
+        .await
+        .unwrap();
+    assert_eq!(hashes[&file], hash_file(&path).unwrap());
+
+    // キャッシュが使われていれば書き換えた値がそのまま返る
+    sqlx::query("UPDATE FILE_HASH SET hash = 'cached'")
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    let hashes = ensure_file_hashes(&mut conn, &[file.clone()])
+        .await
+        .unwrap();
+    assert_eq!(hashes[&file], "cached");
+
+    // サイズが変われば読み直す
+    std::fs::write(&path, b"changed").unwrap();
+    let hashes = ensure_file_hashes(&mut conn, &[file.clone()])
+        .await
+        .unwrap();
+    assert_eq!(hashes[&file], hash_file(&path).unwrap());
+
+    // 消えたファイルはキャッシュからも外す
+    std::fs::remove_file(&path).unwrap();
+    let hashes = ensure_file_hashes(&mut conn, &[file.clone()])
+        .await
+        .unwrap();
+    assert!(hashes.is_empty());
+    let cached: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM FILE_HASH")
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+    assert_eq!(cached, 0);
+}
+
+#[tokio::test]
+async fn report_groups_identical_files_and_resolve_keeps_the_chosen_copy() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("100_p0.jpg"), b"same bytes").unwrap();
+    std::fs::write(dir.join("101_p0.jpg"), b"same bytes").unwrap();
+    std::fs::write(dir.join("102_p0.jpg"), b"other bytes").unwrap();
+    for (id, name) in [
+        (100, "100_p0.jpg"),
+        (101, "101_p0.jpg"),
+        (102, "102_p0.jpg"),
+    ] {
+        register_file(&pool, id, &dir, name).await;
+    }
+
+    let groups = process_get_duplicate_report(&pool).await.unwrap();
+    assert_eq!(groups.len(), 1);
+    let group = &groups[0];
+    assert_eq!(group.file_size, 10);
+    let ids: Vec<i32> = group.files.iter().map(|f| f.illust_id).collect();
+    assert_eq!(ids, vec![100, 101]);
+
+    // グループに無いファイルは残す対象に選べない
+    let outsider = DuplicateFile {
+        illust_id: 102,
+        suffix: 0,
+        save_dir: text(&dir),
+        file_name: "102_p0.jpg".to_string(),
+    };
+    assert!(
+        process_resolve_duplicates(&pool, group.hash.clone(), outsider)
+            .await
+            .is_err()
+    );
+
+    let keep = group.files[1].clone();
+    let stats = process_resolve_duplicates(&pool, group.hash.clone(), keep)
+        .await
+        .unwrap();
+    assert_eq!(stats.removed, 1);
+    assert!(stats.skipped_files.is_empty());
+    assert!(!dir.join("100_p0.jpg").exists());
+    assert!(dir.join("101_p0.jpg").exists());
+
+    let registered: Vec<i64> =
+        sqlx::query_scalar("SELECT illust_id FROM ILLUST_INFO ORDER BY illust_id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(registered, vec![101, 102]);
+    assert!(process_get_duplicate_report(&pool)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn sync_trashes_only_identical_copies() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir_a = tmp.path().join("a");
+    let dir_b = tmp.path().join("b");
+    let dir_c = tmp.path().join("c");
+    write_file(&dir_a, "100_p0.jpg", 10);
+    register_file(&pool, 100, &dir_a, "100_p0.jpg").await;
+
+    // 同じ内容のコピーと、同じ ID でも内容の違う再投稿
+    write_file(&dir_b, "100_p0.jpg", 10);
+    write_file(&dir_c, "100_p0.jpg", 20);
+
+    let result = process_sync_db(text(tmp.path()), &pool).await.unwrap();
+
+    assert!(dir_a.join("100_p0.jpg").exists());
+    assert!(!dir_b.join("100_p0.jpg").exists());
+    assert!(dir_c.join("100_p0.jpg").exists());
+    // 残したファイルは結果で知らせる
+    assert_eq!(result.kept_files.len(), 1);
+    assert_eq!(Path::new(&result.kept_files[0]), dir_c.join("100_p0.jpg"));
+}
@@ -0,0 +1,453 @@
+mod common;
+
+use std::path::Path;
+
+use pixiv_file_manager_lib::api::fixture::FixtureIllustSource;
+use pixiv_file_manager_lib::init_db;
+use pixiv_file_manager_lib::models::fetch::{FetchFailureKind, ProcessStats, RefreshChangeKind};
+use pixiv_file_manager_lib::service::collect::process_sync_db;
+use pixiv_file_manager_lib::service::fetch::{
+    extract_dir_detail, extract_missing_files, get_refresh_changes, get_refresh_runs,
+    prepare_illust_fetch_work, process_fetch_illust_detail, process_refresh_stale_details,
+};
+use pixiv_file_manager_lib::service::fetch_control::FetchControl;
+use pixiv_file_manager_lib::service::library::LibraryPaths;
+use pixiv_file_manager_lib::service::search::{
+    process_get_author_history, process_search_by_criteria, process_search_by_id,
+};
+use sqlx::SqlitePool;
+
+use common::{fixture_dir, memory_pool, write_file, RecordingSink};
+
+async fn run_fetch(pool: &SqlitePool, dirs: &[&Path]) -> (ProcessStats, RecordingSink) {
+    std::env::set_var("INTERVAL_MILL_SEC", "0");
+
+    let mut details = Vec::new();
+    for dir in dirs {
+        details.extend(extract_dir_detail(dir, &LibraryPaths::default()).await);
+    }
+    prepare_illust_fetch_work(pool, &details).await.unwrap();
+
+    let source = FixtureIllustSource::new(fixture_dir());
+    let control = FetchControl::new();
+    let sink = RecordingSink::default();
+
+    let stats = process_fetch_illust_detail(pool, &source, &control, &sink)
+        .await
+        .unwrap();
+
+    (stats, sink)
+}
+
+#[tokio::test]
+async fn fetch_registers_detail_tags_and_meta() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    write_file(&dir, "100_p0.jpg", 10);
+    write_file(&dir, "100_p1.jpg", 10);
+
+    let (stats, sink) = run_fetch(&pool, &[&dir]).await;
+
+    assert_eq!(stats.successed_ids, 1);
+    assert_eq!(stats.failed_ids, 0);
+    assert!(!stats.cancelled);
+
+    let progress = sink.progress.lock().unwrap();
+    let last = progress.last().unwrap();
+    assert_eq!(last.current, last.total);
+
+    let author_id: i64 =
+        sqlx::query_scalar("SELECT author_id FROM ILLUST_DETAIL WHERE illust_id = 100")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(author_id, 500);
+
+    let author_name: String =
+        sqlx::query_scalar("SELECT author_name FROM AUTHOR_INFO WHERE author_id = 500")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(author_name, "テスト作者");
+
+    let tags: Vec<String> =
+        sqlx::query_scalar("SELECT tag FROM TAG_INFO WHERE illust_id = 100 ORDER BY tag")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(tags, vec!["オリジナル", "女の子", "風景"]);
+
+    let translated: String =
+        sqlx::query_scalar("SELECT translated_name FROM TAG_TRANSLATION WHERE tag = '女の子'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(translated, "girl");
+
+    let (title, page_count, bookmarks): (String, i64, i64) = sqlx::query_as(
+        "SELECT title, page_count, total_bookmarks FROM ILLUST_META WHERE illust_id = 100",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(title, "テスト作品");
+    assert_eq!(page_count, 2);
+    assert_eq!(bookmarks, 56);
+
+    let files: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ILLUST_INFO WHERE illust_id = 100")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(files, 2);
+}
+
+#[tokio::test]
+async fn fetch_records_not_found_and_recapture_skips_it() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    write_file(&dir, "200_p0.png", 10);
+
+    let (stats, _) = run_fetch(&pool, &[&dir]).await;
+
+    assert_eq!(stats.successed_ids, 0);
+    assert_eq!(stats.failed_ids, 1);
+
+    let (reason, http_status, attempts): (i64, i64, i64) = sqlx::query_as(
+        "SELECT reason, http_status, attempts FROM FETCH_FAILURE WHERE illust_id = 200",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(reason, FetchFailureKind::NotFound as i64);
+    assert_eq!(http_status, 404);
+    assert_eq!(attempts, 1);
+
+    let missing: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM TAG_INFO WHERE illust_id = 200 AND tag = 'Missing'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(missing, 1);
+
+    // 削除済み作品は再取得の対象にしない
+    let retry = extract_missing_files(&pool).await.unwrap();
+    assert!(retry.is_empty());
+}
+
+#[tokio::test]
+async fn fetch_keeps_smaller_duplicate_and_splits_cnum_per_directory() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir_a = tmp.path().join("a");
+    let dir_b = tmp.path().join("b");
+    // p0 は両方にあり、小さい方を残す
+    write_file(&dir_a, "100_p0.jpg", 10);
+    write_file(&dir_b, "100_p0.jpg", 20);
+    // p1 は b にだけある
+    write_file(&dir_b, "100_p1.jpg", 10);
+
+    let (stats, _) = run_fetch(&pool, &[&dir_a, &dir_b]).await;
+    assert_eq!(stats.successed_ids, 1);
+
+    let kept_dir: String =
+        sqlx::query_scalar("SELECT save_dir FROM ILLUST_INFO WHERE illust_id = 100 AND suffix = 0")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(Path::new(&kept_dir), dir_a);
+
+    // ディレクトリごとに管理番号が分かれ、詳細とタグも複製される
+    let cnums: Vec<i64> = sqlx::query_scalar(
+        "SELECT DISTINCT cnum FROM ILLUST_INFO WHERE illust_id = 100 ORDER BY cnum",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(cnums.len(), 2);
+
+    for cnum in cnums {
+        let details: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM ILLUST_DETAIL WHERE illust_id = 100 AND cnum = ? AND author_id = 500",
+        )
+        .bind(cnum)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(details, 1);
+
+        let tags: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM TAG_INFO WHERE illust_id = 100 AND cnum = ?")
+                .bind(cnum)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(tags, 3);
+    }
+}
+
+#[tokio::test]
+async fn refresh_merges_new_tags_and_keeps_user_edits() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    write_file(&dir, "100_p0.jpg", 10);
+    run_fetch(&pool, &[&dir]).await;
+
+    // ユーザーがタグを編集し、取得から時間がたった状態にする
+    sqlx::query("DELETE FROM TAG_INFO WHERE illust_id = 100 AND tag = '風景'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO TAG_INFO (illust_id, cnum, tag) SELECT illust_id, cnum, 'お気に入り' FROM ILLUST_DETAIL WHERE illust_id = 100")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE ILLUST_META SET fetched_at = 0")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // pixiv 側でタイトルが変わり、タグが1つ増えた
+    let mut body: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(fixture_dir().join("100.json")).unwrap())
+            .unwrap();
+    body["illust"]["title"] = "新しいタイトル".into();
+    body["illust"]["tags"]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!({ "name": "新タグ", "translated_name": null }));
+    let updated = tmp.path().join("fixtures");
+    std::fs::create_dir_all(&updated).unwrap();
+    std::fs::write(updated.join("100.json"), body.to_string()).unwrap();
+
+    let source = FixtureIllustSource::new(updated);
+    let stats = process_refresh_stale_details(
+        &pool,
+        30,
+        &source,
+        &FetchControl::new(),
+        &RecordingSink::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.successed_ids, 1);
+
+    let tags: Vec<String> =
+        sqlx::query_scalar("SELECT tag FROM TAG_INFO WHERE illust_id = 100 ORDER BY tag")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(tags, vec!["お気に入り", "オリジナル", "女の子", "新タグ"]);
+
+    let runs = get_refresh_runs(&pool).await.unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].target_count, 1);
+
+    let changes = get_refresh_changes(&pool, runs[0].run_id).await.unwrap();
+    let kinds: Vec<RefreshChangeKind> = changes.iter().map(|c| c.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![RefreshChangeKind::TagAdded, RefreshChangeKind::Title]
+    );
+    assert_eq!(changes[0].new_value.as_deref(), Some("新タグ"));
+    assert_eq!(changes[1].old_value.as_deref(), Some("テスト作品"));
+
+    // 取り直した作品は次回の対象にならない
+    let stats = process_refresh_stale_details(
+        &pool,
+        30,
+        &FixtureIllustSource::new(fixture_dir()),
+        &FetchControl::new(),
+        &RecordingSink::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.total_ids, 0);
+}
+
+#[tokio::test]
+async fn renamed_author_keeps_history_and_matches_old_name() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    write_file(&dir, "100_p0.jpg", 10);
+    run_fetch(&pool, &[&dir]).await;
+
+    sqlx::query("UPDATE ILLUST_META SET fetched_at = 0")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE AUTHOR_NAME_HISTORY SET first_seen = 0, last_seen = 0")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // pixiv 側で作者が改名した
+    let mut body: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(fixture_dir().join("100.json")).unwrap())
+            .unwrap();
+    body["illust"]["user"]["name"] = "新しい名前".into();
+    let updated = tmp.path().join("fixtures");
+    std::fs::create_dir_all(&updated).unwrap();
+    std::fs::write(updated.join("100.json"), body.to_string()).unwrap();
+
+    process_refresh_stale_details(
+        &pool,
+        30,
+        &FixtureIllustSource::new(updated),
+        &FetchControl::new(),
+        &RecordingSink::default(),
+    )
+    .await
+    .unwrap();
+
+    let author_name: String =
+        sqlx::query_scalar("SELECT author_name FROM AUTHOR_INFO WHERE author_id = 500")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(author_name, "新しい名前");
+
+    let history = process_get_author_history(500, &pool).await.unwrap();
+    let names: Vec<&str> = history.iter().map(|h| h.author_name.as_str()).collect();
+    assert_eq!(names, vec!["新しい名前", "テスト作者"]);
+
+    // 以前の名前でも検索できる
+    let results = process_search_by_criteria(
+        Vec::new(),
+        None,
+        None,
+        Some("テスト作者".to_string()),
+        &pool,
+    )
+    .await
+    .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].author_name, "新しい名前");
+}
+
+#[tokio::test]
+async fn non_standard_file_names_survive_fetch_and_sync() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    write_file(&dir, "テスト作者 - 100_p0.png", 10);
+    write_file(&dir, "100_p1_master1200.jpg", 10);
+
+    let (stats, _) = run_fetch(&pool, &[&dir]).await;
+    assert_eq!(stats.successed_ids, 1);
+
+    let results = process_search_by_id(100, &pool).await.unwrap();
+    let names: Vec<&str> = results.iter().map(|r| r.file_name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["テスト作者 - 100_p0.png", "100_p1_master1200.jpg"]
+    );
+
+    // フォルダごと移動しても元のファイル名のまま追従する
+    let moved = tmp.path().join("b");
+    std::fs::rename(&dir, &moved).unwrap();
+    let missing = process_sync_db(tmp.path().to_string_lossy().to_string(), &pool)
+        .await
+        .unwrap()
+        .missing_files;
+    assert!(missing.is_empty());
+
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT save_dir, file_name FROM ILLUST_INFO WHERE illust_id = 100 ORDER BY suffix",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let moved_dir = moved.to_string_lossy().to_string();
+    assert_eq!(
+        rows,
+        vec![
+            (moved_dir.clone(), "テスト作者 - 100_p0.png".to_string()),
+            (moved_dir, "100_p1_master1200.jpg".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn size_variant_of_the_same_page_does_not_collide() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    // 縮小版の方が小さくても元のファイルを登録する
+    write_file(&dir, "100_p0.jpg", 10);
+    write_file(&dir, "100_p0_master1200.jpg", 5);
+
+    let (stats, _) = run_fetch(&pool, &[&dir]).await;
+    assert_eq!(stats.successed_ids, 1);
+
+    let names: Vec<String> =
+        sqlx::query_scalar("SELECT file_name FROM ILLUST_INFO WHERE illust_id = 100")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(names, vec!["100_p0.jpg".to_string()]);
+    // 内容が違うので縮小版は消さず、結果で知らせる
+    assert!(dir.join("100_p0_master1200.jpg").is_file());
+    assert_eq!(stats.kept_files.len(), 1);
+    assert_eq!(
+        Path::new(&stats.kept_files[0]),
+        dir.join("100_p0_master1200.jpg")
+    );
+
+    // フォルダごと移動しても元のファイルに追従する
+    let moved = tmp.path().join("b");
+    std::fs::rename(&dir, &moved).unwrap();
+    process_sync_db(tmp.path().to_string_lossy().to_string(), &pool)
+        .await
+        .unwrap();
+
+    let row: (String, String) =
+        sqlx::query_as("SELECT save_dir, file_name FROM ILLUST_INFO WHERE illust_id = 100")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        row,
+        (
+            moved.to_string_lossy().to_string(),
+            "100_p0.jpg".to_string()
+        )
+    );
+}
+
+async fn author_history_count(pool: &SqlitePool, author_id: i64) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM AUTHOR_NAME_HISTORY WHERE author_id = ?")
+        .bind(author_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn author_history_is_backfilled_only_once() {
+    let pool = memory_pool().await;
+
+    // 履歴導入前のDB
+    sqlx::query("DELETE FROM COMMON_MST WHERE key = 'author_history_migrated'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO AUTHOR_INFO (author_id, author_name, author_account) VALUES (5, '旧作者', 'old')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    init_db(&pool).await.unwrap();
+    assert_eq!(author_history_count(&pool, 5).await, 1);
+
+    // 移行後の起動では引き継がない
+    sqlx::query("INSERT INTO AUTHOR_INFO (author_id, author_name, author_account) VALUES (6, '新作者', 'new')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    init_db(&pool).await.unwrap();
+    assert_eq!(author_history_count(&pool, 6).await, 0);
+}
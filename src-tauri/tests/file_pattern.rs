@@ -0,0 +1,83 @@
+mod common;
+
+use pixiv_file_manager_lib::constants::DEFAULT_FILE_NAME_PATTERNS;
+use pixiv_file_manager_lib::service::common::parse_file_info;
+use pixiv_file_manager_lib::service::file_pattern::{
+    get_file_name_patterns, save_file_name_patterns, FileNamePatterns,
+};
+
+use common::memory_pool;
+
+#[test]
+fn default_patterns_accept_common_downloader_names() {
+    let patterns = FileNamePatterns::defaults();
+
+    let info = patterns.parse("12345_p0_master1200.jpg").unwrap();
+    assert_eq!((info.illust_id, info.suffix), (12345, 0));
+    assert_eq!(info.extension, "jpg");
+    assert_eq!(
+        info.extras.get("size").map(String::as_str),
+        Some("master1200")
+    );
+
+    let info = patterns.parse("artist - 12345_p3.png").unwrap();
+    assert_eq!((info.illust_id, info.suffix), (12345, 3));
+    assert_eq!(
+        info.extras.get("artist").map(String::as_str),
+        Some("artist")
+    );
+
+    for name in ["12345_p0.gif", "12345_p0.webp", "12345_p0.jpeg"] {
+        assert!(patterns.parse(name).is_ok(), "{}", name);
+    }
+
+    // サイドカーなどはファイル名全体が一致しないので対象外
+    assert!(patterns.parse("12345_p0.jpg.json").is_err());
+}
+
+#[tokio::test]
+async fn saved_patterns_are_validated_and_used_by_parsers() {
+    let pool = memory_pool().await;
+    let mut conn = pool.acquire().await.unwrap();
+
+    assert_eq!(
+        get_file_name_patterns(&mut conn).await.unwrap(),
+        DEFAULT_FILE_NAME_PATTERNS.to_vec()
+    );
+
+    // id の無いパターンや壊れた正規表現は保存しない
+    for invalid in [r"(?P<page>\d+)\.png", r"(?P<id>\d+"] {
+        assert!(
+            save_file_name_patterns(&mut conn, vec![invalid.to_string()])
+                .await
+                .is_err()
+        );
+    }
+    assert!(parse_file_info("100-2.png").is_err());
+
+    // page と ext を省略したパターンはページ0・実際の拡張子になる
+    save_file_name_patterns(
+        &mut conn,
+        vec![
+            r"(?P<id>\d+)-(?P<page>\d+)\.png".to_string(),
+            r"illust_(?P<id>\d+)\.\w+".to_string(),
+        ],
+    )
+    .await
+    .unwrap();
+
+    let info = parse_file_info("100-2.png").unwrap();
+    assert_eq!((info.illust_id, info.suffix), (100, 2));
+    let info = parse_file_info("illust_100.webp").unwrap();
+    assert_eq!((info.illust_id, info.suffix), (100, 0));
+    assert_eq!(info.extension, "webp");
+    assert!(parse_file_info("100_p0.jpg").is_err());
+
+    assert_eq!(
+        get_file_name_patterns(&mut conn).await.unwrap(),
+        vec![
+            r"(?P<id>\d+)-(?P<page>\d+)\.png",
+            r"illust_(?P<id>\d+)\.\w+"
+        ]
+    );
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use std::path::Path;
+
+use image::{ImageFormat, Rgb, RgbImage};
+use pixiv_file_manager_lib::models::catalog::{FileHealthStatus, FileLocation};
+use pixiv_file_manager_lib::service::health::{
+    inspect_file, process_fix_file_extension, process_quarantine_files, process_verify_files,
+};
+use pixiv_file_manager_lib::service::library::LibraryPaths;
+
+use common::{memory_pool, register_file, text};
+
+fn encode(format: ImageFormat) -> Vec<u8> {
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    RgbImage::from_fn(200, 150, |x, y| {
+        Rgb([
+            (x * 7 % 256) as u8,
+            (y * 13 % 256) as u8,
+            ((x ^ y) % 256) as u8,
+        ])
+    })
+    .write_to(&mut bytes, format)
+    .unwrap();
+    bytes.into_inner()
+}
+
+/// 正常・拡張子違い・HTML・途中で切れた JPEG を用意する
+fn write_samples(dir: &Path) {
+    std::fs::create_dir_all(dir).unwrap();
+    std::fs::write(dir.join("100_p0.png"), encode(ImageFormat::Png)).unwrap();
+    std::fs::write(dir.join("101_p0.jpg"), encode(ImageFormat::Png)).unwrap();
+    std::fs::write(
+        dir.join("102_p0.jpg"),
+        "<!DOCTYPE html><html><body>403 Forbidden</body></html>",
+    )
+    .unwrap();
+    let jpeg = encode(ImageFormat::Jpeg);
+    std::fs::write(dir.join("103_p0.jpg"), &jpeg[..jpeg.len() / 2]).unwrap();
+}
+
+#[test]
+fn inspection_checks_magic_bytes_dimensions_and_trailer() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    write_samples(&dir);
+    let dir = text(&dir);
+    let library = LibraryPaths::default();
+
+    let ok = inspect_file(&library, &dir, "100_p0.png").unwrap();
+    assert_eq!(ok.status, FileHealthStatus::Ok);
+    assert_eq!((ok.width, ok.height), (Some(200), Some(150)));
+
+    let mislabeled = inspect_file(&library, &dir, "101_p0.jpg").unwrap();
+    assert_eq!(mislabeled.status, FileHealthStatus::Mislabeled);
+    assert_eq!(mislabeled.detected_format.as_deref(), Some("png"));
+
+    let html = inspect_file(&library, &dir, "102_p0.jpg").unwrap();
+    assert_eq!(html.status, FileHealthStatus::NotImage);
+    assert!(html.detected_format.is_none());
+
+    // ヘッダは読めるので寸法は分かる
+    let truncated = inspect_file(&library, &dir, "103_p0.jpg").unwrap();
+    assert_eq!(truncated.status, FileHealthStatus::Truncated);
+    assert_eq!(truncated.detected_format.as_deref(), Some("jpg"));
+    assert_eq!((truncated.width, truncated.height), (Some(200), Some(150)));
+}
+
+#[tokio::test]
+async fn verify_then_fix_extension_and_quarantine() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    write_samples(&dir);
+    register_file(&pool, 101, &dir, "101_p0.jpg").await;
+    register_file(&pool, 103, &dir, "103_p0.jpg").await;
+
+    let found = process_verify_files(&pool, vec![text(&dir)]).await.unwrap();
+    let names: Vec<&str> = found.iter().map(|h| h.file_name.as_str()).collect();
+    assert_eq!(names, vec!["101_p0.jpg", "102_p0.jpg", "103_p0.jpg"]);
+
+    let statuses: Vec<i64> =
+        sqlx::query_scalar("SELECT status FROM FILE_HEALTH ORDER BY file_name")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(statuses, vec![0, 1, 3, 2]);
+
+    // 壊れたファイルの拡張子は直せない
+    let location = |name: &str| FileLocation {
+        save_dir: text(&dir),
+        file_name: name.to_string(),
+    };
+    assert!(process_fix_file_extension(&pool, location("103_p0.jpg"))
+        .await
+        .is_err());
+
+    let fixed = process_fix_file_extension(&pool, location("101_p0.jpg"))
+        .await
+        .unwrap();
+    assert_eq!(fixed.file_name, "101_p0.png");
+    assert!(dir.join("101_p0.png").is_file());
+    assert!(!dir.join("101_p0.jpg").exists());
+    let (file_name, extension): (String, String) =
+        sqlx::query_as("SELECT file_name, extension FROM ILLUST_INFO WHERE illust_id = 101")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        (file_name.as_str(), extension.as_str()),
+        ("101_p0.png", "png")
+    );
+
+    let quarantine = tmp.path().join("quarantine");
+    let stats = process_quarantine_files(
+        &pool,
+        vec![location("102_p0.jpg"), location("103_p0.jpg")],
+        &quarantine,
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.moved, 2);
+    assert!(stats.failed_files.is_empty());
+    assert!(!dir.join("103_p0.jpg").exists());
+    assert_eq!(std::fs::read_dir(&quarantine).unwrap().count(), 2);
+
+    let registered: Vec<i64> = sqlx::query_scalar("SELECT illust_id FROM ILLUST_INFO")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(registered, vec![101]);
+
+    // 直した後は問題が残っていない
+    let found = process_verify_files(&pool, vec![text(&dir)]).await.unwrap();
+    assert!(found.is_empty());
+}
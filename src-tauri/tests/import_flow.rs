@@ -0,0 +1,205 @@
+mod common;
+
+use std::path::Path;
+
+use pixiv_file_manager_lib::models::fetch::FileDetail;
+use pixiv_file_manager_lib::service::fetch::{
+    extract_dir_detail, get_resumable_fetch_job, prepare_illust_fetch_work,
+    process_import_local_metadata, process_reparse_cached_details,
+};
+use pixiv_file_manager_lib::service::fetch_control::FetchControl;
+use pixiv_file_manager_lib::service::library::LibraryPaths;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, SqlitePool};
+
+use common::{memory_pool, write_file, RecordingSink};
+
+async fn details_of(dir: &Path) -> Vec<FileDetail> {
+    std::env::set_var("INTERVAL_MILL_SEC", "0");
+    extract_dir_detail(dir, &LibraryPaths::default()).await
+}
+
+async fn tags_of(pool: &SqlitePool, illust_id: u32) -> Vec<String> {
+    sqlx::query_scalar("SELECT tag FROM TAG_INFO WHERE illust_id = ? ORDER BY tag")
+        .bind(illust_id)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn import_reads_gallery_dl_sidecars_and_skips_files_without_metadata() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    write_file(&dir, "300_p0.jpg", 10);
+    write_file(&dir, "301_p0.jpg", 10);
+
+    // gallery-dl の既定ではタグは名前だけの配列
+    let sidecar = serde_json::json!({
+        "category": "pixiv",
+        "id": 300,
+        "title": "サイドカー作品",
+        "caption": "",
+        "create_date": "2023-05-01T12:00:00+09:00",
+        "user": { "id": 700, "name": "gallery作者", "account": "gallery_author" },
+        "tags": ["オリジナル", "風景"],
+        "tools": [],
+        "page_count": 1,
+        "width": 800,
+        "height": 600,
+        "total_bookmarks": 12,
+        "num": 0,
+        "extension": "jpg"
+    });
+    std::fs::write(dir.join("300_p0.jpg.json"), sidecar.to_string()).unwrap();
+
+    let details = details_of(&dir).await;
+    let stats = process_import_local_metadata(
+        &pool,
+        details,
+        None,
+        &FetchControl::new(),
+        &RecordingSink::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.successed_ids, 1);
+    assert_eq!(stats.failed_ids, 0);
+
+    let author_id: i64 =
+        sqlx::query_scalar("SELECT author_id FROM ILLUST_DETAIL WHERE illust_id = 300")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(author_id, 700);
+    assert_eq!(tags_of(&pool, 300).await, vec!["オリジナル", "風景"]);
+
+    // 取得時期が不明なので次回の更新対象になる
+    let fetched_at: i64 =
+        sqlx::query_scalar("SELECT fetched_at FROM ILLUST_META WHERE illust_id = 300")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(fetched_at, 0);
+
+    // 組み直した本文はキャッシュせず、再解析でも使わない
+    let cached: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM ILLUST_RAW_CACHE WHERE illust_id = 300")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(cached, 0);
+    let reparsed = process_reparse_cached_details(&pool).await.unwrap();
+    assert_eq!(reparsed.total_ids, 0);
+
+    // メタデータの無いファイルは登録しない
+    let registered: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM ILLUST_INFO WHERE illust_id = 301")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(registered, 0);
+
+    // 途中で止まった取り込みは API で再開しない
+    sqlx::query("UPDATE FETCH_JOB SET status = 0")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE FETCH_JOB_ITEM SET status = 0")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert!(get_resumable_fetch_job(&pool).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn import_reads_pixivutil_db() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    write_file(&dir, "400_p0.png", 10);
+    write_file(&dir, "400_p1.png", 10);
+
+    // PixivUtil2 のテーブルを最小限の列で作る
+    let db_path = tmp.path().join("db.sqlite");
+    let mut conn = SqliteConnectOptions::new()
+        .filename(&db_path)
+        .create_if_missing(true)
+        .connect()
+        .await
+        .unwrap();
+    for sql in [
+        "CREATE TABLE pixiv_master_member (member_id INTEGER PRIMARY KEY, name TEXT, save_folder TEXT, member_token TEXT)",
+        "CREATE TABLE pixiv_master_image (image_id INTEGER PRIMARY KEY, member_id INTEGER, title TEXT, save_name TEXT, created_date DATE, caption TEXT)",
+        "CREATE TABLE pixiv_image_to_tag (image_id INTEGER, tag_id TEXT)",
+        "CREATE TABLE pixiv_tag_translation (tag_id TEXT, translation_type TEXT, translation TEXT)",
+        "INSERT INTO pixiv_master_member VALUES (800, 'PixivUtil作者', '', 'pu_author')",
+        "INSERT INTO pixiv_master_image VALUES (400, 800, 'DB作品', '', '2022-03-04 05:06:07.000000', '')",
+        "INSERT INTO pixiv_image_to_tag VALUES (400, '女の子'), (400, '制服')",
+        "INSERT INTO pixiv_tag_translation VALUES ('女の子', 'en', 'girl')",
+    ] {
+        sqlx::query(sql).execute(&mut conn).await.unwrap();
+    }
+    drop(conn);
+
+    let details = details_of(&dir).await;
+    let stats = process_import_local_metadata(
+        &pool,
+        details,
+        Some(db_path.to_string_lossy().to_string()),
+        &FetchControl::new(),
+        &RecordingSink::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.successed_ids, 1);
+
+    let (author_name, author_account): (String, String) =
+        sqlx::query_as("SELECT author_name, author_account FROM AUTHOR_INFO WHERE author_id = 800")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(author_name, "PixivUtil作者");
+    assert_eq!(author_account, "pu_author");
+
+    assert_eq!(tags_of(&pool, 400).await, vec!["制服", "女の子"]);
+
+    let translated: String =
+        sqlx::query_scalar("SELECT translated_name FROM TAG_TRANSLATION WHERE tag = '女の子'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(translated, "girl");
+
+    let page_count: i64 =
+        sqlx::query_scalar("SELECT page_count FROM ILLUST_META WHERE illust_id = 400")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(page_count, 2);
+}
+
+#[tokio::test]
+async fn import_keeps_work_table_while_fetch_is_running() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    write_file(&dir, "300_p0.jpg", 10);
+    let details = details_of(&dir).await;
+    prepare_illust_fetch_work(&pool, &details).await.unwrap();
+
+    // 別のフェッチが実行中
+    let control = FetchControl::new();
+    control.start().unwrap();
+    let result =
+        process_import_local_metadata(&pool, vec![], None, &control, &RecordingSink::default())
+            .await;
+    assert!(result.is_err());
+
+    let queued: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ILLUST_FETCH_WORK")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(queued, 1);
+}
@@ -0,0 +1,288 @@
+mod common;
+
+use std::path::Path;
+
+use pixiv_file_manager_lib::models::catalog::{EditTag, OperationKind};
+use pixiv_file_manager_lib::service::catalog::{
+    process_delete_files, process_edit_tags, process_label_character_name, process_move_files,
+};
+use pixiv_file_manager_lib::service::journal::{
+    begin_label_operation, begin_operation, finish_operation, process_get_operation_history,
+    process_redo_operation, process_undo_last_operation,
+};
+use pixiv_file_manager_lib::service::library::process_relocate_library;
+use sqlx::SqlitePool;
+
+use common::{memory_pool, register_file, text, write_file};
+
+async fn register(pool: &SqlitePool, illust_id: i32, save_dir: &Path, tags: &[&str]) {
+    register_file(pool, illust_id, save_dir, &format!("{}_p0.jpg", illust_id)).await;
+    for tag in tags {
+        sqlx::query("INSERT INTO TAG_INFO (illust_id, cnum, tag) VALUES (?, 1, ?)")
+            .bind(illust_id)
+            .bind(tag)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+}
+
+async fn save_dir(pool: &SqlitePool, illust_id: i32) -> Option<String> {
+    sqlx::query_scalar("SELECT save_dir FROM ILLUST_INFO WHERE illust_id = ?")
+        .bind(illust_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap()
+}
+
+async fn tags(pool: &SqlitePool, illust_id: i32) -> Vec<String> {
+    sqlx::query_scalar("SELECT tag FROM TAG_INFO WHERE illust_id = ? ORDER BY tag")
+        .bind(illust_id)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+}
+
+async fn character_info(pool: &SqlitePool) -> Option<Option<String>> {
+    sqlx::query_scalar("SELECT collect_dir FROM CHARACTER_INFO WHERE entity_key = 'キャラ'")
+        .fetch_optional(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn move_and_tag_edits_can_be_undone_and_redone() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let (dir_a, dir_b) = (tmp.path().join("a"), tmp.path().join("b"));
+    write_file(&dir_a, "100_p0.jpg", 10);
+    register(&pool, 100, &dir_a, &["元タグ"]).await;
+
+    // 移動
+    let files = vec!["100_p0.jpg".to_string()];
+    let pending = begin_operation(&pool, &files).await.unwrap();
+    process_move_files(&pool, files.clone(), &text(&dir_b), false, None)
+        .await
+        .unwrap();
+    finish_operation(
+        &pool,
+        pending,
+        OperationKind::MoveFiles,
+        Some(&text(&dir_b)),
+        vec![],
+    )
+    .await
+    .unwrap();
+
+    // タグの付け替え
+    let pending = begin_operation(&pool, &files).await.unwrap();
+    let edit = vec![EditTag {
+        file_name: files[0].clone(),
+        tags: vec!["新タグ".to_string()],
+    }];
+    process_edit_tags(&pool, edit, true).await.unwrap();
+    finish_operation(&pool, pending, OperationKind::OverwriteTags, None, vec![])
+        .await
+        .unwrap();
+    assert_eq!(tags(&pool, 100).await, vec!["新タグ"]);
+
+    // 新しい順に取り消す
+    let undone = process_undo_last_operation(&pool).await.unwrap().unwrap();
+    assert_eq!(undone.kind, OperationKind::OverwriteTags);
+    assert_eq!(tags(&pool, 100).await, vec!["元タグ"]);
+
+    let undone = process_undo_last_operation(&pool).await.unwrap().unwrap();
+    assert_eq!(undone.kind, OperationKind::MoveFiles);
+    assert_eq!(save_dir(&pool, 100).await, Some(text(&dir_a)));
+    assert!(dir_a.join("100_p0.jpg").is_file());
+    assert!(!dir_b.join("100_p0.jpg").exists());
+    assert!(process_undo_last_operation(&pool).await.unwrap().is_none());
+
+    // 古い順にやり直す
+    let redone = process_redo_operation(&pool).await.unwrap().unwrap();
+    assert_eq!(redone.kind, OperationKind::MoveFiles);
+    assert_eq!(save_dir(&pool, 100).await, Some(text(&dir_b)));
+    assert!(dir_b.join("100_p0.jpg").is_file());
+
+    let history = process_get_operation_history(&pool).await.unwrap();
+    assert_eq!(history.len(), 2);
+    assert!(history[0].undone);
+    assert!(!history[1].undone);
+
+    // 操作の後に変わった登録内容は上書きしない
+    sqlx::query("INSERT INTO TAG_INFO (illust_id, cnum, tag) VALUES (100, 1, '手動')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert!(process_undo_last_operation(&pool).await.is_err());
+    assert_eq!(save_dir(&pool, 100).await, Some(text(&dir_b)));
+    assert!(dir_b.join("100_p0.jpg").is_file());
+}
+
+#[tokio::test]
+async fn deleted_files_are_held_until_undone() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    let hold_dir = tmp.path().join("hold");
+    write_file(&dir, "100_p0.jpg", 10);
+    write_file(&dir, "101_p0.jpg", 10);
+    register(&pool, 100, &dir, &["タグ"]).await;
+    register(&pool, 101, &dir, &[]).await;
+
+    let files = vec!["100_p0.jpg".to_string()];
+    let pending = begin_operation(&pool, &files).await.unwrap();
+    let held = process_delete_files(&pool, files, &hold_dir).await.unwrap();
+    assert_eq!(held.len(), 1);
+    finish_operation(&pool, pending, OperationKind::DeleteFiles, None, held)
+        .await
+        .unwrap();
+    assert!(!dir.join("100_p0.jpg").exists());
+    assert_eq!(save_dir(&pool, 100).await, None);
+    assert_eq!(std::fs::read_dir(&hold_dir).unwrap().count(), 1);
+
+    process_undo_last_operation(&pool).await.unwrap().unwrap();
+    assert!(dir.join("100_p0.jpg").is_file());
+    assert_eq!(save_dir(&pool, 100).await, Some(text(&dir)));
+    assert_eq!(tags(&pool, 100).await, vec!["タグ"]);
+    assert_eq!(std::fs::read_dir(&hold_dir).unwrap().count(), 0);
+
+    process_redo_operation(&pool).await.unwrap().unwrap();
+    assert!(!dir.join("100_p0.jpg").exists());
+    assert_eq!(save_dir(&pool, 100).await, None);
+    assert!(tags(&pool, 100).await.is_empty());
+    // 対象外のファイルはそのまま
+    assert!(dir.join("101_p0.jpg").is_file());
+    assert_eq!(save_dir(&pool, 101).await, Some(text(&dir)));
+}
+
+#[tokio::test]
+async fn failed_delete_puts_held_files_back() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    let hold_dir = tmp.path().join("hold");
+    write_file(&dir, "100_p0.jpg", 10);
+    register(&pool, 100, &dir, &["タグ"]).await;
+
+    // 2 件目は登録がないので途中で失敗する
+    let files = vec!["100_p0.jpg".to_string(), "999_p0.jpg".to_string()];
+    assert!(process_delete_files(&pool, files, &hold_dir).await.is_err());
+
+    assert!(dir.join("100_p0.jpg").is_file());
+    assert_eq!(save_dir(&pool, 100).await, Some(text(&dir)));
+    assert_eq!(tags(&pool, 100).await, vec!["タグ"]);
+    assert_eq!(std::fs::read_dir(&hold_dir).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn label_undo_restores_character_info() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    write_file(&dir, "100_p0.jpg", 10);
+    register(&pool, 100, &dir, &[]).await;
+
+    let files = vec!["100_p0.jpg".to_string()];
+    let collect_dir = text(&tmp.path().join("キャラ"));
+    let pending = begin_label_operation(&pool, &files, Some("キャラ"))
+        .await
+        .unwrap();
+    process_label_character_name(&pool, &files, Some("キャラ"), true, Some(&collect_dir))
+        .await
+        .unwrap();
+    finish_operation(
+        &pool,
+        pending,
+        OperationKind::LabelCharacter,
+        Some("キャラ"),
+        vec![],
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(character_info(&pool).await, Some(Some(collect_dir.clone())));
+
+    process_undo_last_operation(&pool).await.unwrap().unwrap();
+    assert_eq!(character_info(&pool).await, None);
+    let character: Option<String> =
+        sqlx::query_scalar("SELECT character FROM ILLUST_DETAIL WHERE illust_id = 100")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(character, None);
+
+    process_redo_operation(&pool).await.unwrap().unwrap();
+    assert_eq!(character_info(&pool).await, Some(Some(collect_dir)));
+}
+
+#[tokio::test]
+async fn unchanged_operations_are_not_recorded() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    write_file(&dir, "100_p0.jpg", 10);
+    register(&pool, 100, &dir, &[]).await;
+
+    // 途中で失敗して何も反映されなかった操作は残さない
+    let files = vec!["100_p0.jpg".to_string()];
+    let pending = begin_operation(&pool, &files).await.unwrap();
+    let id = finish_operation(&pool, pending, OperationKind::MoveFiles, None, vec![])
+        .await
+        .unwrap();
+    assert_eq!(id, None);
+    assert!(process_get_operation_history(&pool)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn moves_can_be_undone_after_relocating_library() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let (old_root, new_root) = (tmp.path().join("old"), tmp.path().join("new"));
+    write_file(&old_root.join("a"), "100_p0.jpg", 10);
+    register(&pool, 100, &old_root.join("a"), &[]).await;
+
+    let files = vec!["100_p0.jpg".to_string()];
+    let pending = begin_operation(&pool, &files).await.unwrap();
+    process_move_files(&pool, files, &text(&old_root.join("b")), false, None)
+        .await
+        .unwrap();
+    finish_operation(&pool, pending, OperationKind::MoveFiles, None, vec![])
+        .await
+        .unwrap();
+
+    // 同じ場所のキャッシュが既にあれば書き換えずに件数を返す
+    for dir in [old_root.join("b"), new_root.join("b")] {
+        sqlx::query(
+            "INSERT INTO FILE_HASH (save_dir, file_name, file_size, modified_at, hash)
+             VALUES (?, '100_p0.jpg', 10, 0, 'hash')",
+        )
+        .bind(text(&dir))
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    // ドライブを付け替えた
+    std::fs::rename(&old_root, &new_root).unwrap();
+    let stats = process_relocate_library(&pool, &text(&old_root), &text(&new_root), false, false)
+        .await
+        .unwrap();
+    assert_eq!(stats.files, 1);
+    assert_eq!(stats.conflicts, 1);
+    let hashes: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM FILE_HASH")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(hashes, 2);
+
+    // 履歴も新しい場所で戻せる
+    process_undo_last_operation(&pool).await.unwrap().unwrap();
+    assert_eq!(save_dir(&pool, 100).await, Some(text(&new_root.join("a"))));
+    assert!(new_root.join("a").join("100_p0.jpg").is_file());
+    assert!(!new_root.join("b").join("100_p0.jpg").exists());
+}
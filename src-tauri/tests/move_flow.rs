@@ -0,0 +1,173 @@
+mod common;
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use pixiv_file_manager_lib::models::catalog::MoveState;
+use pixiv_file_manager_lib::service::catalog::process_move_files;
+use pixiv_file_manager_lib::service::file_move::{
+    copy_file_verified, is_cross_device, move_by_copy, move_file, reconcile_file_moves,
+};
+use sqlx::SqlitePool;
+
+use common::{memory_pool, register_file, text, write_file};
+
+async fn save_dir(pool: &SqlitePool, illust_id: i32) -> String {
+    sqlx::query_scalar("SELECT save_dir FROM ILLUST_INFO WHERE illust_id = ?")
+        .bind(illust_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+async fn journal_count(pool: &SqlitePool) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM MOVE_JOURNAL")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+async fn plan(pool: &SqlitePool, illust_id: i32, src: &Path, dest: &Path, state: MoveState) {
+    sqlx::query(
+        "INSERT INTO MOVE_JOURNAL
+         (illust_id, suffix, file_name, zip_name, src_dir, dest_dir, state, created_at)
+         VALUES (?, 0, ?, NULL, ?, ?, ?, 0)",
+    )
+    .bind(illust_id)
+    .bind(format!("{}_p0.jpg", illust_id))
+    .bind(text(src))
+    .bind(text(dest))
+    .bind(state as i64)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn failed_moves_are_reported_and_left_unchanged() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let (dir_a, dir_b) = (tmp.path().join("a"), tmp.path().join("b"));
+    write_file(&dir_a, "100_p0.jpg", 10);
+    write_file(&dir_a, "101_p0.jpg", 10);
+    register_file(&pool, 100, &dir_a, "100_p0.jpg").await;
+    register_file(&pool, 101, &dir_a, "101_p0.jpg").await;
+    // 移動先に同じ名前のファイルがある
+    write_file(&dir_b, "101_p0.jpg", 20);
+
+    let files = vec!["100_p0.jpg".to_string(), "101_p0.jpg".to_string()];
+    let failures = process_move_files(&pool, files, &text(&dir_b), false, None)
+        .await
+        .unwrap();
+
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].file_name, "101_p0.jpg");
+    assert!(!failures[0].reason.is_empty());
+    assert_eq!(save_dir(&pool, 100).await, text(&dir_b));
+    assert_eq!(save_dir(&pool, 101).await, text(&dir_a));
+    assert!(dir_b.join("100_p0.jpg").is_file());
+    assert!(dir_a.join("101_p0.jpg").is_file());
+    assert_eq!(journal_count(&pool).await, 0);
+}
+
+#[tokio::test]
+async fn startup_finishes_or_rolls_back_interrupted_moves() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let (dir_a, dir_b) = (tmp.path().join("a"), tmp.path().join("b"));
+    for id in [100, 101, 102, 103] {
+        register_file(&pool, id, &dir_a, &format!("{}_p0.jpg", id)).await;
+    }
+
+    // ファイル移動後、DB に反映する前に止まった
+    write_file(&dir_b, "100_p0.jpg", 10);
+    plan(&pool, 100, &dir_a, &dir_b, MoveState::Moved).await;
+    // 移動直後、状態を残す前に止まった
+    write_file(&dir_b, "101_p0.jpg", 10);
+    plan(&pool, 101, &dir_a, &dir_b, MoveState::Planned).await;
+    // 移動前に止まった
+    write_file(&dir_a, "102_p0.jpg", 10);
+    plan(&pool, 102, &dir_a, &dir_b, MoveState::Planned).await;
+    // 別ドライブへのコピー後、元を消す前に止まった
+    write_file(&dir_a, "103_p0.jpg", 10);
+    write_file(&dir_b, "103_p0.jpg", 10);
+    write_file(&dir_b, "103_p0.jpg.part", 4);
+    plan(&pool, 103, &dir_a, &dir_b, MoveState::Planned).await;
+
+    let mut conn = pool.acquire().await.unwrap();
+    assert_eq!(reconcile_file_moves(&mut conn).await.unwrap(), 4);
+    drop(conn);
+
+    assert_eq!(save_dir(&pool, 100).await, text(&dir_b));
+    assert_eq!(save_dir(&pool, 101).await, text(&dir_b));
+    assert_eq!(save_dir(&pool, 102).await, text(&dir_a));
+    assert!(dir_a.join("102_p0.jpg").is_file());
+    assert_eq!(save_dir(&pool, 103).await, text(&dir_b));
+    assert!(!dir_a.join("103_p0.jpg").exists());
+    assert!(!dir_b.join("103_p0.jpg.part").exists());
+    assert_eq!(journal_count(&pool).await, 0);
+}
+
+#[test]
+fn verified_copy_keeps_content_and_modified_time() {
+    let tmp = tempfile::tempdir().unwrap();
+    let src = tmp.path().join("src").join("100_p0.jpg");
+    std::fs::create_dir_all(src.parent().unwrap()).unwrap();
+    std::fs::write(&src, b"image bytes").unwrap();
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+    std::fs::File::options()
+        .write(true)
+        .open(&src)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+
+    let dest_dir = tmp.path().join("dest");
+    std::fs::create_dir_all(&dest_dir).unwrap();
+    let dest = dest_dir.join("100_p0.jpg");
+    copy_file_verified(&src, &dest).unwrap();
+
+    assert_eq!(std::fs::read(&dest).unwrap(), b"image bytes");
+    assert_eq!(
+        std::fs::metadata(&dest).unwrap().modified().unwrap(),
+        modified
+    );
+    // 元を消すのは呼び出し側
+    assert!(src.is_file());
+    assert_eq!(std::fs::read_dir(&dest_dir).unwrap().count(), 1);
+}
+
+#[test]
+fn only_cross_device_errors_fall_back_to_copy() {
+    #[cfg(unix)]
+    assert!(is_cross_device(&std::io::Error::from_raw_os_error(18)));
+    assert!(!is_cross_device(&std::io::Error::from(
+        std::io::ErrorKind::PermissionDenied
+    )));
+
+    // 移動元がないときはコピーを試さずに失敗する
+    let tmp = tempfile::tempdir().unwrap();
+    let dest = tmp.path().join("b").join("100_p0.jpg");
+    assert!(move_file(&tmp.path().join("a").join("100_p0.jpg"), &dest).is_err());
+    assert!(!dest.exists());
+    assert_eq!(std::fs::read_dir(tmp.path().join("b")).unwrap().count(), 0);
+}
+
+#[test]
+fn copy_is_removed_when_source_cannot_be_deleted() {
+    let tmp = tempfile::tempdir().unwrap();
+    let src = write_file(&tmp.path().join("a"), "100_p0.jpg", 10);
+    let dest = tmp.path().join("b").join("100_p0.jpg");
+    std::fs::create_dir_all(dest.parent().unwrap()).unwrap();
+
+    let result = move_by_copy(&src, &dest, |_| {
+        Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+    });
+    assert!(result.is_err());
+    assert!(src.is_file());
+    assert!(!dest.exists());
+
+    move_by_copy(&src, &dest, |src| std::fs::remove_file(src)).unwrap();
+    assert!(!src.exists());
+    assert!(dest.is_file());
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use std::path::Path;
+
+use image::imageops::FilterType;
+use image::{GrayImage, Luma};
+use pixiv_file_manager_lib::models::catalog::DuplicateFile;
+use pixiv_file_manager_lib::service::near_duplicate::{
+    process_find_near_duplicates, process_resolve_near_duplicates,
+};
+use pixiv_file_manager_lib::service::search::process_search_by_image;
+
+use common::{memory_pool, register_file, text};
+
+/// 8x8 のブロック模様（invert で明暗を反転した別の絵になる）
+fn pattern(invert: bool) -> GrayImage {
+    GrayImage::from_fn(128, 128, |x, y| {
+        let (bx, by) = (x / 16, y / 16);
+        let v = (((bx * 37 + by * 91) * 53) % 256) as u8;
+        Luma([if invert { 255 - v } else { v }])
+    })
+}
+
+fn file(dir: &Path, illust_id: i32, name: &str) -> DuplicateFile {
+    DuplicateFile {
+        illust_id,
+        suffix: 0,
+        save_dir: text(dir),
+        file_name: name.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn resized_reencodes_cluster_with_the_original() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // 元画像・縮小して JPEG にしたもの・別の絵
+    pattern(false).save(dir.join("100_p0.png")).unwrap();
+    image::imageops::resize(&pattern(false), 64, 64, FilterType::Triangle)
+        .save(dir.join("101_p0.jpg"))
+        .unwrap();
+    pattern(true).save(dir.join("102_p0.png")).unwrap();
+    register_file(&pool, 100, &dir, "100_p0.png").await;
+    register_file(&pool, 101, &dir, "101_p0.jpg").await;
+    register_file(&pool, 102, &dir, "102_p0.png").await;
+
+    let clusters = process_find_near_duplicates(&pool, 10).await.unwrap();
+    assert_eq!(clusters.len(), 1);
+    let files = &clusters[0].files;
+    let ids: Vec<i32> = files.iter().map(|f| f.illust_id).collect();
+    assert_eq!(ids, vec![100, 101]);
+    assert_eq!((files[0].width, files[0].height), (128, 128));
+    assert_eq!((files[1].width, files[1].height), (64, 64));
+    assert_eq!(files[0].distance, 0);
+    assert!(files[1].file_size > 0);
+
+    // 2回目はキャッシュから同じ結果になる
+    let cached = process_find_near_duplicates(&pool, 10).await.unwrap();
+    assert_eq!(cached[0].files[1].distance, files[1].distance);
+
+    // 残すファイルは削除対象にできない
+    let keep = file(&dir, 100, "100_p0.png");
+    assert!(
+        process_resolve_near_duplicates(&pool, keep.clone(), vec![keep.clone()])
+            .await
+            .is_err()
+    );
+
+    let stats = process_resolve_near_duplicates(
+        &pool,
+        keep,
+        vec![file(&dir, 101, "101_p0.jpg"), file(&dir, 103, "103_p0.jpg")],
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.removed, 1);
+    assert_eq!(stats.skipped_files, vec!["103_p0.jpg"]);
+    assert!(!dir.join("101_p0.jpg").exists());
+    assert!(dir.join("100_p0.png").exists());
+
+    let registered: Vec<i64> =
+        sqlx::query_scalar("SELECT illust_id FROM ILLUST_INFO ORDER BY illust_id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(registered, vec![100, 102]);
+    assert!(process_find_near_duplicates(&pool, 10)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn failed_trash_does_not_undo_other_removals() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    pattern(false).save(dir.join("100_p0.png")).unwrap();
+    pattern(false).save(dir.join("101_p0.png")).unwrap();
+    register_file(&pool, 100, &dir, "100_p0.png").await;
+    register_file(&pool, 101, &dir, "101_p0.png").await;
+    // 登録はあるがファイルが無いのでゴミ箱へ移せない
+    register_file(&pool, 102, &dir, "102_p0.png").await;
+
+    let stats = process_resolve_near_duplicates(
+        &pool,
+        file(&dir, 100, "100_p0.png"),
+        vec![file(&dir, 102, "102_p0.png"), file(&dir, 101, "101_p0.png")],
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.removed, 1);
+    assert_eq!(stats.skipped_files, vec!["102_p0.png"]);
+    assert!(!dir.join("101_p0.png").exists());
+    assert!(dir.join("100_p0.png").exists());
+
+    // ゴミ箱へ移したファイルの登録は戻らない
+    let registered: Vec<i64> =
+        sqlx::query_scalar("SELECT illust_id FROM ILLUST_INFO ORDER BY illust_id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(registered, vec![100]);
+}
+
+#[tokio::test]
+async fn example_image_without_id_finds_local_copies() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    std::fs::create_dir_all(&dir).unwrap();
+    pattern(false).save(dir.join("100_p0.png")).unwrap();
+    pattern(true).save(dir.join("102_p0.png")).unwrap();
+    register_file(&pool, 100, &dir, "100_p0.png").await;
+    register_file(&pool, 102, &dir, "102_p0.png").await;
+
+    // チャットで受け取ったような、作品IDの無い縮小版
+    let example = tmp.path().join("from_chat.jpg");
+    image::imageops::resize(&pattern(false), 48, 48, FilterType::Triangle)
+        .save(&example)
+        .unwrap();
+
+    let results = process_search_by_image(text(&example), 10, &pool)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].illust_id, 100);
+    assert_eq!(results[0].file_name, "100_p0.png");
+    assert!(results[0].distance.unwrap() <= 10);
+
+    // 距離を広げると別の絵も遠い順に並ぶ
+    let results = process_search_by_image(text(&example), 64, &pool)
+        .await
+        .unwrap();
+    let ids: Vec<u32> = results.iter().map(|r| r.illust_id).collect();
+    assert_eq!(ids, vec![100, 102]);
+    assert!(results[0].distance < results[1].distance);
+}
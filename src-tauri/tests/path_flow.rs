@@ -0,0 +1,72 @@
+mod common;
+
+use std::path::Path;
+
+use pixiv_file_manager_lib::service::collect::{collect_dir, process_sync_db};
+use pixiv_file_manager_lib::service::common::{normalize_dir, normalize_saved_dirs};
+use pixiv_file_manager_lib::service::library::LibraryPaths;
+use pixiv_file_manager_lib::service::search::process_search_by_id;
+
+use common::{memory_pool, register_file, text, write_file};
+
+#[test]
+fn dirs_are_joined_with_the_platform_separator() {
+    let root = Path::new("library").join("pixiv");
+    let with_slash = format!("{}/", root.to_string_lossy());
+    let library = LibraryPaths::default();
+
+    assert_eq!(normalize_dir(&with_slash), root.to_string_lossy());
+    assert_eq!(
+        Path::new(&collect_dir(
+            &library,
+            &with_slash,
+            Some("シリーズ"),
+            Some("キャラ")
+        )),
+        root.join("シリーズ").join("キャラ")
+    );
+    assert_eq!(
+        Path::new(&collect_dir(&library, &with_slash, None, Some("キャラ"))),
+        root.join("キャラ")
+    );
+}
+
+#[tokio::test]
+async fn sync_and_search_use_native_paths() {
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir_a = tmp.path().join("a");
+    let dir_b = tmp.path().join("b");
+    write_file(&dir_a, "100_p0.jpg", 10);
+
+    // 末尾に区切りが付いた旧データも起動時にそろえる
+    let saved = format!("{}/", dir_a.to_string_lossy());
+    register_file(&pool, 100, &saved, "100_p0.jpg").await;
+    register_file(&pool, 101, &saved, "101_p0.jpg").await;
+    let mut conn = pool.acquire().await.unwrap();
+    normalize_saved_dirs(&mut conn).await.unwrap();
+    drop(conn);
+    let dirs: Vec<String> = sqlx::query_scalar("SELECT DISTINCT save_dir FROM ILLUST_INFO")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(dirs, vec![text(&dir_a)]);
+
+    // a から b へ移動したファイルを同期する
+    std::fs::create_dir_all(&dir_b).unwrap();
+    std::fs::rename(dir_a.join("100_p0.jpg"), dir_b.join("100_p0.jpg")).unwrap();
+    let missing = process_sync_db(text(tmp.path()), &pool)
+        .await
+        .unwrap()
+        .missing_files;
+    assert_eq!(missing.len(), 1);
+    assert_eq!(Path::new(&missing[0].path), dir_a.join("101_p0.jpg"));
+
+    let results = process_search_by_id(100, &pool).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].save_dir, dir_b.to_string_lossy());
+    assert_eq!(
+        Path::new(&results[0].thumbnail_url),
+        dir_b.join("100_p0.jpg")
+    );
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use pixiv_file_manager_lib::constants::COLLECT_ROOT;
+use pixiv_file_manager_lib::service::collect::process_sync_db;
+use pixiv_file_manager_lib::service::library::process_relocate_library;
+use pixiv_file_manager_lib::service::search::process_search_by_id;
+use sqlx::SqlitePool;
+use tempfile::TempDir;
+
+use common::{memory_pool, register_file, text, write_file};
+
+async fn scalar(pool: &SqlitePool, sql: &str) -> String {
+    sqlx::query_scalar(sql).fetch_one(pool).await.unwrap()
+}
+
+/// old 以下に登録済みのライブラリを作り、new へ付け替えた状態にする
+async fn moved_library(pool: &SqlitePool) -> (TempDir, PathBuf, PathBuf) {
+    let tmp = tempfile::tempdir().unwrap();
+    let old_root = tmp.path().join("old");
+    let new_root = tmp.path().join("new");
+    write_file(&old_root.join("a"), "100_p0.jpg", 10);
+
+    register_file(pool, 100, old_root.join("a"), "100_p0.jpg").await;
+    sqlx::query(
+        "INSERT INTO CHARACTER_INFO (entity_key, character, collect_dir) VALUES ('キャラ', 'キャラ', ?)",
+    )
+    .bind(text(&old_root.join("キャラ")))
+    .execute(pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO COMMON_MST (key, value) VALUES (?, ?)")
+        .bind(COLLECT_ROOT)
+        .bind(text(&old_root))
+        .execute(pool)
+        .await
+        .unwrap();
+
+    // ドライブを付け替えた
+    std::fs::rename(&old_root, &new_root).unwrap();
+    (tmp, old_root, new_root)
+}
+
+#[tokio::test]
+async fn relocate_requires_an_existing_destination() {
+    let pool = memory_pool().await;
+    let (tmp, old_root, _) = moved_library(&pool).await;
+
+    let nowhere = text(&tmp.path().join("nowhere"));
+    assert!(
+        process_relocate_library(&pool, &text(&old_root), &nowhere, false, false)
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        scalar(&pool, "SELECT save_dir FROM ILLUST_INFO").await,
+        text(&old_root.join("a"))
+    );
+}
+
+#[tokio::test]
+async fn preview_counts_without_rewriting() {
+    let pool = memory_pool().await;
+    let (_tmp, old_root, new_root) = moved_library(&pool).await;
+
+    let preview = process_relocate_library(&pool, &text(&old_root), &text(&new_root), true, true)
+        .await
+        .unwrap();
+    assert_eq!(preview.files, 1);
+    assert_eq!(preview.collect_dirs, 1);
+    assert!(preview.root_updated);
+    assert_eq!(preview.missing_files, 0);
+    assert_eq!(preview.relative_files, 1);
+    assert_eq!(
+        scalar(&pool, "SELECT save_dir FROM ILLUST_INFO").await,
+        text(&old_root.join("a"))
+    );
+}
+
+#[tokio::test]
+async fn relocate_can_switch_to_relative_paths() {
+    let pool = memory_pool().await;
+    let (_tmp, old_root, new_root) = moved_library(&pool).await;
+
+    process_relocate_library(&pool, &text(&old_root), &text(&new_root), false, true)
+        .await
+        .unwrap();
+    assert_eq!(scalar(&pool, "SELECT save_dir FROM ILLUST_INFO").await, "a");
+    assert_eq!(
+        scalar(&pool, "SELECT collect_dir FROM CHARACTER_INFO").await,
+        "キャラ"
+    );
+    assert_eq!(
+        scalar(
+            &pool,
+            "SELECT value FROM COMMON_MST WHERE key = 'collect_root'"
+        )
+        .await,
+        text(&new_root)
+    );
+
+    // 画面には実際のパスを返す
+    let results = process_search_by_id(100, &pool).await.unwrap();
+    assert_eq!(results[0].save_dir, text(&new_root.join("a")));
+    assert_eq!(
+        Path::new(&results[0].thumbnail_url),
+        new_root.join("a").join("100_p0.jpg")
+    );
+}
+
+#[tokio::test]
+async fn relative_library_only_needs_the_root_rewritten() {
+    let pool = memory_pool().await;
+    let (tmp, old_root, new_root) = moved_library(&pool).await;
+    process_relocate_library(&pool, &text(&old_root), &text(&new_root), false, true)
+        .await
+        .unwrap();
+
+    let newer_root = tmp.path().join("newer");
+    std::fs::rename(&new_root, &newer_root).unwrap();
+    let stats = process_relocate_library(&pool, &text(&new_root), &text(&newer_root), false, false)
+        .await
+        .unwrap();
+    assert_eq!(stats.files, 0);
+    assert!(stats.root_updated);
+    assert_eq!(stats.missing_files, 0);
+
+    let missing = process_sync_db(text(&newer_root), &pool)
+        .await
+        .unwrap()
+        .missing_files;
+    assert!(missing.is_empty());
+    assert_eq!(scalar(&pool, "SELECT save_dir FROM ILLUST_INFO").await, "a");
+    let results = process_search_by_id(100, &pool).await.unwrap();
+    assert_eq!(
+        Path::new(&results[0].thumbnail_url),
+        newer_root.join("a").join("100_p0.jpg")
+    );
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use image::{ImageFormat, Rgba, RgbaImage};
+use pixiv_file_manager_lib::api::fixture::FixtureIllustSource;
+use pixiv_file_manager_lib::models::catalog::UgoiraFormat;
+use pixiv_file_manager_lib::service::collect::process_sync_db;
+use pixiv_file_manager_lib::service::fetch::{
+    extract_dir_detail, prepare_illust_fetch_work, process_fetch_illust_detail,
+};
+use pixiv_file_manager_lib::service::fetch_control::FetchControl;
+use pixiv_file_manager_lib::service::library::LibraryPaths;
+use pixiv_file_manager_lib::service::ugoira::{convert_ugoira, process_convert_ugoira};
+use zip::write::SimpleFileOptions;
+
+use common::{fixture_dir, memory_pool, RecordingSink};
+
+/// 2x2 の単色フレームを詰めた zip を作る
+fn write_ugoira_zip(path: &Path, colors: &[[u8; 4]], animation_json: Option<&str>) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+    for (i, color) in colors.iter().enumerate() {
+        let mut png = std::io::Cursor::new(Vec::new());
+        RgbaImage::from_pixel(2, 2, Rgba(*color))
+            .write_to(&mut png, ImageFormat::Png)
+            .unwrap();
+        zip.start_file(format!("{:06}.png", i), SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(png.get_ref()).unwrap();
+    }
+    if let Some(json) = animation_json {
+        zip.start_file("animation.json", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(json.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+#[tokio::test]
+async fn fetched_ugoira_is_converted_and_keeps_its_zip() {
+    std::env::set_var("INTERVAL_MILL_SEC", "0");
+    let pool = memory_pool().await;
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("a");
+    write_ugoira_zip(
+        &dir.join("200_ugoira.zip"),
+        &[[255, 0, 0, 255], [0, 0, 255, 255]],
+        None,
+    );
+
+    let details = extract_dir_detail(&dir, &LibraryPaths::default()).await;
+    prepare_illust_fetch_work(&pool, &details).await.unwrap();
+    let stats = process_fetch_illust_detail(
+        &pool,
+        &FixtureIllustSource::new(fixture_dir()),
+        &FetchControl::new(),
+        &RecordingSink::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.successed_ids, 1);
+
+    // フレームの遅延は取得時に保存する
+    let frames: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT file, delay FROM UGOIRA_FRAME WHERE illust_id = 200 ORDER BY frame_index",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        frames,
+        vec![
+            ("000000.png".to_string(), 100),
+            ("000001.png".to_string(), 250)
+        ]
+    );
+
+    let result = process_convert_ugoira(&pool, UgoiraFormat::Gif, vec![])
+        .await
+        .unwrap();
+    assert_eq!((result.converted, result.failed), (1, 0));
+    assert!(dir.join("200_ugoira.gif").is_file());
+
+    let (file_name, extension): (String, String) =
+        sqlx::query_as("SELECT file_name, extension FROM ILLUST_INFO WHERE illust_id = 200")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        (file_name.as_str(), extension.as_str()),
+        ("200_ugoira.gif", "gif")
+    );
+
+    // 変換済みなので再実行しても対象にならない
+    let result = process_convert_ugoira(&pool, UgoiraFormat::Gif, vec![200])
+        .await
+        .unwrap();
+    assert_eq!(result.converted, 0);
+
+    // 同期しても元の zip は別の作品ファイルとして扱われない
+    let missing = process_sync_db(tmp.path().to_string_lossy().to_string(), &pool)
+        .await
+        .unwrap()
+        .missing_files;
+    assert!(missing.is_empty());
+    let registered: Vec<String> =
+        sqlx::query_scalar("SELECT file_name FROM ILLUST_INFO WHERE illust_id = 200")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(registered, vec!["200_ugoira.gif"]);
+}
+
+#[test]
+fn zip_without_fetched_frames_uses_animation_json() {
+    let tmp = tempfile::tempdir().unwrap();
+    let zip_path = tmp.path().join("300_ugoira.zip");
+    let output = tmp.path().join("300_ugoira.png");
+    write_ugoira_zip(
+        &zip_path,
+        &[[0, 255, 0, 255], [0, 0, 0, 255], [255, 255, 255, 255]],
+        Some(
+            r#"{"frames":[{"file":"000000.png","delay":50},{"file":"000001.png","delay":50},{"file":"000002.png","delay":80}]}"#,
+        ),
+    );
+
+    convert_ugoira(&zip_path, &output, vec![], UgoiraFormat::Apng).unwrap();
+
+    let decoder = png::Decoder::new(File::open(&output).unwrap());
+    let reader = decoder.read_info().unwrap();
+    let info = reader.info();
+    assert_eq!((info.width, info.height), (2, 2));
+    assert_eq!(info.animation_control.unwrap().num_frames, 3);
+
+    // フレーム情報が無ければ変換しない
+    let bare = tmp.path().join("301_ugoira.zip");
+    write_ugoira_zip(&bare, &[[0, 0, 0, 255]], None);
+    assert!(convert_ugoira(
+        &bare,
+        &tmp.path().join("301_ugoira.gif"),
+        vec![],
+        UgoiraFormat::Gif
+    )
+    .is_err());
+    assert!(!tmp.path().join("301_ugoira.gif").exists());
+}
@@ -2,23 +2,31 @@ import { invoke } from "@tauri-apps/api/core";
 import { Copy, Save, Loader2, Eye, EyeOff } from "lucide-react";
 import { useState, useEffect } from "react";
 
+import type { AuthStatus } from "@/bindings/AuthStatus";
 import type { EnvConfig } from "@/bindings/EnvConfig";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 
+import { FileNamePatterns } from "./file-name-patterns";
+
 export default function EnvSettings() {
   const [config, setConfig] = useState<EnvConfig>({
-    REFRESH_TOKEN: "",
     INTERVAL_MILL_SEC: "1000",
     DB_NAME: "pixiv_def",
+    FETCH_CONCURRENCY: "4",
+    API_BASE_URL: "",
+    AUTH_TOKEN_URL: "",
+    PROXY_URL: "",
   });
 
   const [isLoading, setIsLoading] = useState(true);
   const [isSaving, setIsSaving] = useState(false);
   const [saveSuccess, setSaveSuccess] = useState(false);
   const [showToken, setShowToken] = useState(false);
+  const [passphrase, setPassphrase] = useState("");
+  const [authStatus, setAuthStatus] = useState<AuthStatus | null>(null);
 
   useEffect(() => {
     const loadInitialSettings = async () => {
@@ -29,6 +37,7 @@ export default function EnvSettings() {
         if (initialConfig) {
           setConfig(initialConfig);
         }
+        setAuthStatus(await invoke<AuthStatus>("get_auth_status"));
       } catch (error) {
         console.error("Failed to load initial settings:", error);
       } finally {
@@ -71,13 +80,23 @@ export default function EnvSettings() {
 
   const handleLogin = async () => {
     try {
-      const token = await invoke<string>("pixiv_authorization");
-      setConfig((prev) => ({ ...prev, REFRESH_TOKEN: token }));
+      setAuthStatus(
+        await invoke<AuthStatus>("pixiv_authorization", { passphrase })
+      );
     } catch (error) {
       console.error("Failed to login:", error);
     }
   };
 
+  const handleUnlock = async () => {
+    try {
+      setAuthStatus(await invoke<AuthStatus>("unlock_auth", { passphrase }));
+    } catch (error) {
+      console.error("Failed to unlock:", error);
+      setAuthStatus(await invoke<AuthStatus>("get_auth_status"));
+    }
+  };
+
   if (isLoading) {
     return (
       <div className="h-full bg-gray-50 p-4 flex items-center justify-center">
@@ -101,27 +120,16 @@ export default function EnvSettings() {
         </CardHeader>
         <CardContent className="space-y-4">
           <div className="space-y-3">
-            <Button
-              type="button"
-              className="w-full bg-blue-600 text-white hover:bg-blue-800"
-              variant="default"
-              size="sm"
-              onClick={() => void handleLogin()}
-            >
-              Pixiv Login & Get Refresh Token
-            </Button>
             <div>
-              <Label htmlFor="refresh-token" className="text-sm font-medium">
-                Refresh Token
+              <Label htmlFor="passphrase" className="text-sm font-medium">
+                Passphrase
               </Label>
               <div className="relative mt-1">
                 <Input
-                  id="refresh-token"
+                  id="passphrase"
                   type={showToken ? "text" : "password"}
-                  value={config.REFRESH_TOKEN}
-                  onChange={(e) =>
-                    handleChange("REFRESH_TOKEN", e.target.value)
-                  }
+                  value={passphrase}
+                  onChange={(e) => setPassphrase(e.target.value)}
                   className="pr-10"
                 />
                 <Button
@@ -140,6 +148,49 @@ export default function EnvSettings() {
               </div>
             </div>
 
+            <div className="grid grid-cols-2 gap-3">
+              <Button
+                type="button"
+                className="w-full bg-blue-600 text-white hover:bg-blue-800"
+                variant="default"
+                size="sm"
+                disabled={!passphrase}
+                onClick={() => void handleLogin()}
+              >
+                Pixiv Login
+              </Button>
+              <Button
+                type="button"
+                className="w-full"
+                variant="outline"
+                size="sm"
+                disabled={!passphrase}
+                onClick={() => void handleUnlock()}
+              >
+                Unlock
+              </Button>
+            </div>
+
+            {authStatus && (
+              <div className="text-xs text-gray-600 space-y-1">
+                <p>
+                  {authStatus.authorized
+                    ? `Authorized: ${authStatus.user_name ?? "-"} (${
+                        authStatus.user_account ?? "-"
+                      })`
+                    : authStatus.locked
+                    ? "Locked: enter passphrase and unlock"
+                    : "Not authorized"}
+                </p>
+                {authStatus.expires_at && (
+                  <p>Expires: {authStatus.expires_at}</p>
+                )}
+                {authStatus.last_error && (
+                  <p className="text-red-600">{authStatus.last_error}</p>
+                )}
+              </div>
+            )}
+
             <div className="grid grid-cols-2 gap-3">
               <div>
                 <Label htmlFor="interval" className="text-sm font-medium">
@@ -167,6 +218,60 @@ export default function EnvSettings() {
                 />
               </div>
             </div>
+
+            <div>
+              <Label htmlFor="concurrency" className="text-sm font-medium">
+                Concurrent Requests
+              </Label>
+              <Input
+                id="concurrency"
+                value={config.FETCH_CONCURRENCY}
+                placeholder="4"
+                onChange={(e) =>
+                  handleChange("FETCH_CONCURRENCY", e.target.value)
+                }
+                className="mt-1"
+              />
+            </div>
+
+            <div>
+              <Label htmlFor="api-base-url" className="text-sm font-medium">
+                API Base URL
+              </Label>
+              <Input
+                id="api-base-url"
+                value={config.API_BASE_URL}
+                placeholder="https://app-api.pixiv.net"
+                onChange={(e) => handleChange("API_BASE_URL", e.target.value)}
+                className="mt-1"
+              />
+            </div>
+
+            <div>
+              <Label htmlFor="auth-token-url" className="text-sm font-medium">
+                OAuth Token URL
+              </Label>
+              <Input
+                id="auth-token-url"
+                value={config.AUTH_TOKEN_URL}
+                placeholder="https://oauth.secure.pixiv.net/auth/token"
+                onChange={(e) => handleChange("AUTH_TOKEN_URL", e.target.value)}
+                className="mt-1"
+              />
+            </div>
+
+            <div>
+              <Label htmlFor="proxy-url" className="text-sm font-medium">
+                Proxy URL
+              </Label>
+              <Input
+                id="proxy-url"
+                value={config.PROXY_URL}
+                placeholder="http://proxy:8080 / socks5://proxy:1080"
+                onChange={(e) => handleChange("PROXY_URL", e.target.value)}
+                className="mt-1"
+              />
+            </div>
           </div>
 
           <div className="space-y-2 mt-6">
@@ -193,6 +298,8 @@ export default function EnvSettings() {
               Copy .env File
             </Button>
           </div>
+
+          <FileNamePatterns />
         </CardContent>
       </Card>
     </div>
@@ -0,0 +1,101 @@
+import { invoke } from "@tauri-apps/api/core";
+import { Save } from "lucide-react";
+import { useEffect, useState } from "react";
+
+import type { FileNamePreview } from "@/bindings/FileNamePreview";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+
+// 1行に1つの正規表現。名前付きキャプチャ id は必須、page / ext は省略可
+const toPatterns = (text: string) =>
+  text
+    .split("\n")
+    .map((line) => line.trim())
+    .filter((line) => line.length > 0);
+
+export const FileNamePatterns = () => {
+  const [text, setText] = useState("");
+  const [sample, setSample] = useState("");
+  const [preview, setPreview] = useState<FileNamePreview | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [saveSuccess, setSaveSuccess] = useState(false);
+
+  useEffect(() => {
+    invoke<string[]>("get_file_name_patterns")
+      .then((patterns) => setText(patterns.join("\n")))
+      .catch((e) => console.error("Failed to load patterns:", e));
+  }, []);
+
+  useEffect(() => {
+    if (!sample) {
+      setPreview(null);
+      return;
+    }
+    invoke<FileNamePreview[]>("preview_file_name_patterns", {
+      patterns: toPatterns(text),
+      fileNames: [sample],
+    })
+      .then((result) => {
+        setPreview(result[0] ?? null);
+        setError(null);
+      })
+      .catch((e) => setError(String(e)));
+  }, [text, sample]);
+
+  const savePatterns = async () => {
+    try {
+      await invoke("set_file_name_patterns", { patterns: toPatterns(text) });
+      setError(null);
+      setSaveSuccess(true);
+      setTimeout(() => setSaveSuccess(false), 3000);
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  return (
+    <div className="space-y-2">
+      <Label htmlFor="file-name-patterns" className="text-sm font-medium">
+        File Name Patterns
+      </Label>
+      <textarea
+        id="file-name-patterns"
+        value={text}
+        rows={4}
+        spellCheck={false}
+        onChange={(e) => {
+          setText(e.target.value);
+          setSaveSuccess(false);
+        }}
+        className="w-full rounded-md border px-3 py-2 font-mono text-xs"
+      />
+      <Input
+        value={sample}
+        placeholder="12345_p0_master1200.jpg"
+        onChange={(e) => setSample(e.target.value)}
+      />
+      {preview && (
+        <p className="text-xs text-gray-600">
+          {preview.error
+            ? preview.error
+            : `id: ${preview.illust_id} / page: ${preview.suffix} / ext: ${
+                preview.extension
+              }${Object.entries(preview.extras)
+                .map(([key, value]) => ` / ${key}: ${value}`)
+                .join("")}`}
+        </p>
+      )}
+      {error && <p className="text-xs text-red-600">{error}</p>}
+      <Button
+        onClick={() => void savePatterns()}
+        className="w-full"
+        variant="outline"
+        size="sm"
+      >
+        <Save className="w-4 h-4 mr-2" />
+        {saveSuccess ? "Saved!" : "Save Patterns"}
+      </Button>
+    </div>
+  );
+};
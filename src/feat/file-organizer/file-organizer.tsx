@@ -1,6 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
-import { open } from "@tauri-apps/plugin-dialog";
+import { confirm, message, open } from "@tauri-apps/plugin-dialog";
 import {
   Settings,
   Plus,
@@ -8,13 +8,16 @@ import {
   Database,
   Play,
   ArrowBigRightDash,
+  HardDrive,
 } from "lucide-react";
 import { useState, useEffect } from "react";
 
 import { ResultArea } from "./result-area";
 import { SyncResultsDialog } from "./sync-results-dialog";
 
+import type { CollectResult } from "@/bindings/CollectResult";
 import type { CollectSummary } from "@/bindings/CollectSummary";
+import type { RelocateStats } from "@/bindings/RelocateStats";
 import type { TagAssignment } from "@/bindings/TagAssignment";
 import type { TagInfo } from "@/bindings/TagInfo";
 import { Button } from "@/components/ui/button";
@@ -39,6 +42,7 @@ export default function FileOrganizer() {
   const [selectedCharacterTag, setSelectedCharacterTag] = useState<string>("");
   const [isChangeRoot, setIsChangeRoot] = useState(false);
   const [rootPath, setRootPath] = useState("");
+  const [savedRoot, setSavedRoot] = useState("");
   const [filteredSeriesTagList, setFilteredSeriesTagList] = useState<TagInfo[]>(
     []
   );
@@ -60,6 +64,7 @@ export default function FileOrganizer() {
           setIsChangeRoot(true);
         } else {
           setRootPath(root);
+          setSavedRoot(root);
         }
         await loadSummary();
         const tags = await invoke<TagInfo[]>("get_available_unique_tags");
@@ -109,8 +114,16 @@ export default function FileOrganizer() {
   const performCollect = async () => {
     setLoading(true);
     try {
-      const summary: CollectSummary[] = await invoke("perform_collect");
-      setCollectSummary(summary);
+      const result: CollectResult = await invoke("perform_collect");
+      setCollectSummary(result.summary);
+      if (result.failed_moves.length > 0) {
+        await message(
+          result.failed_moves
+            .map((f) => `${f.file_name}: ${f.reason}`)
+            .join("\n"),
+          { title: "Some files could not be moved", kind: "warning" }
+        );
+      }
     } finally {
       setLoading(false);
     }
@@ -126,6 +139,7 @@ export default function FileOrganizer() {
       await invoke("set_root", {
         root: rootPath,
       });
+      setSavedRoot(rootPath);
       setIsChangeRoot(false);
       void loadSummary();
     } finally {
@@ -133,6 +147,52 @@ export default function FileOrganizer() {
     }
   };
 
+  // ドライブ文字やマウント先が変わったとき、登録済みのパスを新しい root に書き換える
+  const relocateLibrary = async () => {
+    const newRoot = rootPath.trim();
+    if (!savedRoot || !newRoot || newRoot === savedRoot) {
+      return;
+    }
+
+    setLoading(true);
+    try {
+      const args = { oldPrefix: savedRoot, newPrefix: newRoot };
+      const preview = await invoke<RelocateStats>("relocate_library", {
+        ...args,
+        dryRun: true,
+        storeRelative: false,
+      });
+      const proceed = await confirm(
+        `${preview.files} files and ${preview.collect_dirs} folders will be moved to ${newRoot}.` +
+          (preview.missing_files > 0
+            ? `\n${preview.missing_files} files were not found at the new location.`
+            : "") +
+          (preview.conflicts > 0
+            ? `\n${preview.conflicts} entries already exist at the new location and will be kept as they are.`
+            : ""),
+        { title: "Relocate Library", kind: "warning" }
+      );
+      if (!proceed) {
+        return;
+      }
+      const storeRelative = await confirm(
+        "Store paths relative to the root from now on?",
+        { title: "Relocate Library" }
+      );
+      await invoke<RelocateStats>("relocate_library", {
+        ...args,
+        dryRun: false,
+        storeRelative,
+      });
+      setSavedRoot(newRoot);
+      setIsChangeRoot(false);
+    } catch (error) {
+      console.error("Error relocating library:", error);
+    } finally {
+      setLoading(false);
+    }
+  };
+
   const selectFolders = async () => {
     try {
       const selected = await open({
@@ -201,6 +261,15 @@ export default function FileOrganizer() {
               <ArrowBigRightDash />
               Update
             </Button>
+            <Button
+              onClick={() => void relocateLibrary()}
+              disabled={loading || !isChangeRoot || !savedRoot}
+              size="sm"
+              className="text-xs bg-sky-600 hover:bg-sky-700"
+            >
+              <HardDrive />
+              Relocate
+            </Button>
             <Button
               onClick={() => void syncDB()}
               disabled={loading}
@@ -22,6 +22,7 @@ export const SyncResultsDialog = () => {
     selectedItems,
     setSelectedItems,
     syncResults,
+    keptFiles,
     syncDialogOpen,
     setSyncDialogOpen,
     deleteSelectedItems,
@@ -70,6 +71,25 @@ export const SyncResultsDialog = () => {
           </Alert>
         )}
 
+        {!loading && keptFiles.length > 0 && (
+          <Alert className="mb-4">
+            <AlertCircle className="h-4 w-4" />
+            <AlertDescription>
+              <div>
+                {keptFiles.length} duplicate files were kept because their
+                content differs:
+              </div>
+              <div className="mt-1 max-h-24 overflow-y-auto">
+                {keptFiles.map((path, index) => (
+                  <div key={index} className="text-xs truncate" title={path}>
+                    {path}
+                  </div>
+                ))}
+              </div>
+            </AlertDescription>
+          </Alert>
+        )}
+
         <div className="flex-1 overflow-auto">
           {loading && (
             <div className="flex items-center justify-center py-8">
@@ -19,6 +19,8 @@ const TAG_PROGRESS_INIT = {
   total: 0,
   elapsed_time: "00:00:00",
   remaining_time: "00:00:00",
+  current_delay_ms: 0,
+  state: "idle",
 } as const;
 
 export default function TagFetcher() {
@@ -27,11 +29,13 @@ export default function TagFetcher() {
     folders: [],
     total: 0,
     process_time: "",
+    unhealthy: 0,
   });
   const [isProcessing, setIsProcessing] = useState(false);
   const [progress, setProgress] = useState<TagProgress>(TAG_PROGRESS_INIT);
   const [stats, setStats] = useState<ProcessStats | null>(null);
   const [showFailedDetails, setShowFailedDetails] = useState(false);
+  const [showKeptDetails, setShowKeptDetails] = useState(false);
 
   useEffect(() => {
     let unlisten: (() => void) | undefined;
@@ -182,6 +186,11 @@ export default function TagFetcher() {
             <div className="text-xs font-medium text-indigo-700 dark:text-indigo-300">
               Est. time: {fileCounts.process_time}
             </div>
+            {fileCounts.unhealthy > 0 && (
+              <div className="text-xs font-medium text-red-600 dark:text-red-400">
+                {fileCounts.unhealthy} broken or mislabeled files
+              </div>
+            )}
           </div>
         </div>
       )}
@@ -352,6 +361,38 @@ export default function TagFetcher() {
               )}
             </div>
           )}
+
+          {stats.kept_files.length > 0 && (
+            <div className="mt-2">
+              <Button
+                variant="outline"
+                size="sm"
+                onClick={() => setShowKeptDetails(!showKeptDetails)}
+                className="w-full justify-between bg-amber-50 text-amber-700 border-amber-200 hover:bg-amber-100 dark:bg-amber-900/20 dark:text-amber-300 dark:border-amber-700 dark:hover:bg-amber-900/30"
+              >
+                Kept Duplicates (content differs)
+                <Badge className="bg-amber-600 text-white">
+                  {stats.kept_files.length}
+                </Badge>
+              </Button>
+
+              {showKeptDetails && (
+                <ScrollArea className="h-[80px] mt-2 border rounded-md border-amber-200 dark:border-amber-700 bg-amber-50/50 dark:bg-amber-900/10">
+                  <div className="p-2">
+                    {stats.kept_files.map((path, index) => (
+                      <div
+                        key={index}
+                        className="text-xs truncate py-0.5 text-amber-700 dark:text-amber-300 hover:bg-amber-100 dark:hover:bg-amber-900/20 px-1 rounded"
+                        title={path}
+                      >
+                        {path}
+                      </div>
+                    ))}
+                  </div>
+                </ScrollArea>
+              )}
+            </div>
+          )}
         </Card>
       )}
     </div>
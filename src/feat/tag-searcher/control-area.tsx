@@ -1,4 +1,11 @@
-import { Search, Trash2, CheckSquare, Square } from "lucide-react";
+import { open } from "@tauri-apps/plugin-dialog";
+import {
+  Search,
+  Trash2,
+  CheckSquare,
+  Square,
+  Image as ImageIcon,
+} from "lucide-react";
 import { useEffect } from "react";
 
 import { DropdownAuthor } from "./dropdowns/dropdown-author";
@@ -27,7 +34,21 @@ export const ControlArea = () => {
     setSearchId,
     filterDropdowns,
   } = useTagSearcherStore();
-  const { handleSearch } = useTagSearcherStore();
+  const { handleSearch, searchByImage } = useTagSearcherStore();
+
+  // Search by an example image (file name doesn't need a pixiv id)
+  const selectExampleImage = async () => {
+    const selected = await open({
+      multiple: false,
+      directory: false,
+      title: "Select an image to search",
+      filters: [
+        { name: "Images", extensions: ["jpg", "jpeg", "png", "gif", "webp"] },
+      ],
+    });
+    if (!selected) return;
+    await searchByImage(selected);
+  };
 
   // Clear all search conditions
   const clearSearchConditions = () => {
@@ -99,6 +120,17 @@ export const ControlArea = () => {
         Search
       </Button>
 
+      {/* Search by Image Button */}
+      <Button
+        variant="outline"
+        size="sm"
+        className="h-9 bg-white dark:bg-gray-800"
+        onClick={() => void selectExampleImage()}
+      >
+        <ImageIcon className="h-4 w-4 mr-1" />
+        By Image
+      </Button>
+
       {/* History dropdown */}
       <DropdownHistory />
 
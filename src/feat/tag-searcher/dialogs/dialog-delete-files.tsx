@@ -70,7 +70,7 @@ export const DialogDeleteFiles = () => {
           <p>
             Are you sure you want to delete{" "}
             {deleteFilesDialogSelectedFiles?.length ?? "0"}
-            file(s)? This action cannot be undone.
+            file(s)? You can restore them with Undo.
           </p>
         </div>
         <DialogFooter>
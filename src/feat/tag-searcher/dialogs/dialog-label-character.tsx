@@ -1,10 +1,11 @@
 import { invoke } from "@tauri-apps/api/core";
-import { open } from "@tauri-apps/plugin-dialog";
+import { message, open } from "@tauri-apps/plugin-dialog";
 import { FolderOpen } from "lucide-react";
 import type React from "react";
 import { useState, useEffect } from "react";
 
 import { AssociateInfo } from "@/bindings/AssociateInfo";
+import { MoveFailure } from "@/bindings/MoveFailure";
 import { SearchResult } from "@/bindings/SearchResult";
 import { Badge } from "@/components/ui/badge";
 import { Button } from "@/components/ui/button";
@@ -224,7 +225,7 @@ export const DialogLabelCharacter = () => {
     updateLinkedFiles,
     collectDir,
   }: DialogLabelCharaSubmitParams) => {
-    await invoke("label_character_name", {
+    const failedMoves = await invoke<MoveFailure[]>("label_character_name", {
       fileNames: labelCharacterDialogSelectedFiles.map((p) => p.file_name),
       characterName,
       updateLinkedFiles,
@@ -232,6 +233,12 @@ export const DialogLabelCharacter = () => {
     });
     await fetchCharacters();
     await quickReload();
+    if (failedMoves.length > 0) {
+      await message(
+        failedMoves.map((f) => `${f.file_name}: ${f.reason}`).join("\n"),
+        { title: "Some files could not be moved", kind: "warning" }
+      );
+    }
   };
 
   const handleSubmit = async () => {
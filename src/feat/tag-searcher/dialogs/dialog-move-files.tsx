@@ -1,10 +1,13 @@
 import { Label } from "@radix-ui/react-dropdown-menu";
 import { invoke } from "@tauri-apps/api/core";
-import { open } from "@tauri-apps/plugin-dialog";
+import { listen } from "@tauri-apps/api/event";
+import { message, open } from "@tauri-apps/plugin-dialog";
 import { Folder } from "lucide-react";
 import { useEffect, useState } from "react";
 
 import { AssociateInfo } from "@/bindings/AssociateInfo";
+import { MoveFailure } from "@/bindings/MoveFailure";
+import { MoveProgress } from "@/bindings/MoveProgress";
 import { SearchResult } from "@/bindings/SearchResult";
 import { Badge } from "@/components/ui/badge";
 import { Button } from "@/components/ui/button";
@@ -32,6 +35,7 @@ export const DialogMoveFiles = () => {
   const [moveLinkedFiles, setMoveLinkedFiles] = useState(false);
   const [targetFolder, setTargetFolder] = useState("");
   const [pathsToUpdate, setPathsToUpdate] = useState(0);
+  const [moveProgress, setMoveProgress] = useState<MoveProgress | null>(null);
   const [isLoadingAssociations, setIsLoadingAssociations] = useState(false);
   const [associateInfo, setAssociateInfo] = useState<AssociateInfo | null>(
     null
@@ -95,6 +99,7 @@ export const DialogMoveFiles = () => {
     setMoveLinkedFiles(false);
     setTargetFolder("");
     setPathsToUpdate(0);
+    setMoveProgress(null);
     setLoading(false);
     setIsLoadingAssociations(false);
     setAssociateInfo(null);
@@ -109,6 +114,17 @@ export const DialogMoveFiles = () => {
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, [isMoveFilesDialogOpen]);
 
+  // 別ドライブへのコピーは時間がかかるので進捗を表示する
+  useEffect(() => {
+    if (!isMoveFilesDialogOpen) return;
+    const unlisten = listen<MoveProgress>("move_progress", (event) => {
+      setMoveProgress(event.payload);
+    });
+    return () => {
+      void unlisten.then((f) => f());
+    };
+  }, [isMoveFilesDialogOpen]);
+
   // Handle folder selection
   const handleSelectFolder = async () => {
     try {
@@ -133,10 +149,11 @@ export const DialogMoveFiles = () => {
     setLoading(true);
 
     const fileNames = selectedFiles.map((p) => p.file_name);
+    let failedMoves: MoveFailure[] = [];
 
     try {
       // Invoke to Rust backend
-      await invoke("move_files", {
+      failedMoves = await invoke<MoveFailure[]>("move_files", {
         fileNames,
         targetFolder,
         moveLinkedFiles,
@@ -151,6 +168,13 @@ export const DialogMoveFiles = () => {
 
     // refresh
     await quickReload();
+
+    if (failedMoves.length > 0) {
+      await message(
+        failedMoves.map((f) => `${f.file_name}: ${f.reason}`).join("\n"),
+        { title: "Some files could not be moved", kind: "warning" }
+      );
+    }
   };
 
   return (
@@ -221,7 +245,11 @@ export const DialogMoveFiles = () => {
             className="bg-blue-600 hover:bg-blue-700 text-white"
             disabled={loading}
           >
-            {loading ? "Moving..." : "Move Files"}
+            {loading
+              ? moveProgress
+                ? `Moving ${moveProgress.current}/${moveProgress.total}...`
+                : "Moving..."
+              : "Move Files"}
           </Button>
         </DialogFooter>
       </DialogContent>
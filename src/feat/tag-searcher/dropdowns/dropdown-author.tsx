@@ -1,9 +1,20 @@
 import { User } from "lucide-react";
 
+import { AuthorInfo } from "@/bindings/AuthorInfo";
 import { DropdownButton } from "@/src/components/dropdown-button";
 import { useTagSearcherStore } from "@/src/stores/tag-searcher-store";
 import { useDropdownStore } from "@/stores/dropdown-store";
 
+type AuthorItem = AuthorInfo & { label: string };
+
+// 改名前の名前でも絞り込めるよう表示名に併記する
+const withLabel = (item: AuthorInfo): AuthorItem => ({
+  ...item,
+  label: item.former_names
+    ? `${item.author_name} (${item.former_names})`
+    : item.author_name,
+});
+
 export const DropdownAuthor = () => {
   const { selectedAuthor, setSelectedAuthor } = useTagSearcherStore();
   const { authorDropdownItems } = useDropdownStore();
@@ -13,11 +24,11 @@ export const DropdownAuthor = () => {
       mode="single"
       ButtonIcon={<User className="h-4 w-4 mr-1 text-green-500" />}
       buttonText={"Author"}
-      selectedItem={selectedAuthor}
-      availableItems={authorDropdownItems}
+      selectedItem={selectedAuthor && withLabel(selectedAuthor)}
+      availableItems={authorDropdownItems.map(withLabel)}
       onClick={(item) => setSelectedAuthor(item)}
       valueKey="author_id"
-      labelKey="author_name"
+      labelKey="label"
     />
   );
 };
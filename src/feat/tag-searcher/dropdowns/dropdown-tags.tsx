@@ -5,11 +5,21 @@ import { DropdownButton } from "@/src/components/dropdown-button";
 import { useTagSearcherStore } from "@/src/stores/tag-searcher-store";
 import { useDropdownStore } from "@/stores/dropdown-store";
 
+type TagItem = TagInfo & { label: string };
+
+// 翻訳名でも絞り込めるよう表示名に併記する
+const withLabel = (item: TagInfo): TagItem => ({
+  ...item,
+  label: item.translated_name
+    ? `${item.tag} (${item.translated_name})`
+    : item.tag,
+});
+
 export const DropdownTags = () => {
   const { selectedTags, setSelectedTags } = useTagSearcherStore();
   const { tagDropdownItems } = useDropdownStore();
 
-  const applyTag = (items: TagInfo[]) => {
+  const applyTag = (items: TagItem[]) => {
     const uniqueItems = items.filter(
       (item, index, self) => index === self.findIndex((t) => t.tag === item.tag)
     );
@@ -21,11 +31,11 @@ export const DropdownTags = () => {
       mode="multiple"
       ButtonIcon={<Filter className="h-4 w-4 mr-1 text-blue-500" />}
       buttonText={"Tag"}
-      selectedItem={selectedTags}
-      availableItems={tagDropdownItems}
+      selectedItem={selectedTags.map(withLabel)}
+      availableItems={tagDropdownItems.map(withLabel)}
       onClick={applyTag}
       valueKey="tag"
-      labelKey="tag"
+      labelKey="label"
     />
   );
 };
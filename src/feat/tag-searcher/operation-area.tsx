@@ -1,12 +1,17 @@
+import { invoke } from "@tauri-apps/api/core";
+import { message } from "@tauri-apps/plugin-dialog";
 import {
   CheckSquare,
   FolderInput,
+  Redo2,
   Square,
   Tag,
   Trash2,
+  Undo2,
   User,
 } from "lucide-react";
 
+import type { OperationEntry } from "@/bindings/OperationEntry";
 import { Button } from "@/components/ui/button";
 import { useTagSearcherStore } from "@/src/stores/tag-searcher-store";
 import { useDialogDeleteStore } from "@/stores/dialog-delete-store";
@@ -82,6 +87,18 @@ export const OperationArea = () => {
     }
   };
 
+  // Undo / redo the last catalog operation (results reload on "update_db")
+  const handleJournal = async (
+    command: "undo_last_operation" | "redo_operation"
+  ) => {
+    try {
+      const entry = await invoke<OperationEntry | null>(command);
+      if (entry) setSelectedFiles([]);
+    } catch (error) {
+      await message(String(error), { kind: "error" });
+    }
+  };
+
   return (
     <>
       {operationMode && searchResults.length > 0 && (
@@ -150,6 +167,28 @@ export const OperationArea = () => {
             {isDeleting ? "Tagging..." : `Edit Tag`}
           </Button>
 
+          <Button
+            variant="outline"
+            size="sm"
+            className="h-8 bg-white dark:bg-gray-800"
+            onClick={() => void handleJournal("undo_last_operation")}
+            disabled={isDeleting}
+          >
+            <Undo2 className="h-3.5 w-3.5 mr-1 text-gray-500" />
+            Undo
+          </Button>
+
+          <Button
+            variant="outline"
+            size="sm"
+            className="h-8 bg-white dark:bg-gray-800"
+            onClick={() => void handleJournal("redo_operation")}
+            disabled={isDeleting}
+          >
+            <Redo2 className="h-3.5 w-3.5 mr-1 text-gray-500" />
+            Redo
+          </Button>
+
           <div className="ml-auto text-sm font-medium text-blue-700 dark:text-blue-300">
             {selectedFiles.length} of {searchResults.length} selected
           </div>
@@ -263,7 +263,10 @@ export const TagsSearcherResultArea = () => {
                 <td className="p-2 text-sm text-gray-600 dark:text-gray-300">
                   {result.character ?? "-"}
                 </td>
-                <td className="p-2 text-sm text-gray-600 dark:text-gray-300">
+                <td
+                  className="p-2 text-sm text-gray-600 dark:text-gray-300"
+                  title={result.translated_tags ?? undefined}
+                >
                   {result.tags ?? "-"}
                 </td>
               </tr>
@@ -1,4 +1,3 @@
-import { invoke } from "@tauri-apps/api/core";
 import React from "react";
 import ReactDOM from "react-dom/client";
 
@@ -6,18 +5,6 @@ import { App } from "./app";
 
 import "@/styles/styles.css";
 
-window.addEventListener("DOMContentLoaded", () => {
-  void (async () => {
-    try {
-      console.log("Initializing Pixiv client...");
-      await invoke("init_pixiv_client");
-      console.log("Pixiv client initialized successfully.");
-    } catch (e) {
-      console.error("Failed to initialize Pixiv client:", e);
-    }
-  })();
-});
-
 ReactDOM.createRoot(document.getElementById("root")!).render(
   <React.StrictMode>
     <App />
@@ -6,6 +6,7 @@ import { useCommonStore } from "./common-store";
 
 import { CollectSummary } from "@/bindings/CollectSummary";
 import { FileSummary } from "@/bindings/FileSummary";
+import { SyncResult } from "@/bindings/SyncResult";
 import { TagInfo } from "@/bindings/TagInfo";
 
 type FileOrganizerStore = {
@@ -17,6 +18,8 @@ type FileOrganizerStore = {
   setSyncDialogOpen: (open: boolean) => void;
   syncResults: FileSummary[];
   setSyncResults: (results: FileSummary[]) => void;
+  keptFiles: string[];
+  setKeptFiles: (files: string[]) => void;
   selectedItems: Set<number>;
   setSelectedItems: (items: Set<number>) => void;
   // Commands
@@ -34,6 +37,8 @@ export const useFileOrganizerStore = create<FileOrganizerStore>((set, get) => ({
   setSyncDialogOpen: (open) => set({ syncDialogOpen: open }),
   syncResults: [],
   setSyncResults: (results) => set({ syncResults: results }),
+  keptFiles: [],
+  setKeptFiles: (files) => set({ keptFiles: files }),
   selectedItems: new Set(),
   setSelectedItems: (items) => set({ selectedItems: items }),
 
@@ -51,7 +56,8 @@ export const useFileOrganizerStore = create<FileOrganizerStore>((set, get) => ({
 
   syncDB: async () => {
     const setLoading = useCommonStore.getState().setLoading;
-    const { setSyncDialogOpen, setSyncResults, setSelectedItems } = get();
+    const { setSyncDialogOpen, setSyncResults, setKeptFiles, setSelectedItems } =
+      get();
 
     try {
       const selected = await open({
@@ -65,10 +71,11 @@ export const useFileOrganizerStore = create<FileOrganizerStore>((set, get) => ({
       setLoading(true);
       setSyncDialogOpen(true);
       try {
-        const fileSummary: FileSummary[] = await invoke("sync_db", {
+        const result: SyncResult = await invoke("sync_db", {
           root: selected,
         });
-        setSyncResults(fileSummary);
+        setSyncResults(result.missing_files);
+        setKeptFiles(result.kept_files);
         setSelectedItems(new Set());
       } finally {
         setLoading(false);
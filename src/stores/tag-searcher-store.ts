@@ -56,6 +56,7 @@ type TagSearcherStore = {
   fetchAuthors: () => Promise<void>;
   filterDropdowns: () => Promise<void>;
   handleSearch: () => Promise<SearchResult[] | undefined>;
+  searchByImage: (path: string) => Promise<void>;
   quickReload: () => Promise<void>;
 };
 
@@ -230,6 +231,25 @@ export const useTagSearcherStore = create<TagSearcherStore>((set, get) => ({
     return await performSearch();
   },
 
+  // Find local files that look like the given image
+  searchByImage: async (path) => {
+    const { setSearchResults, setSelectedFiles } = get();
+    try {
+      const results: SearchResult[] = await invoke("search_by_image", {
+        path,
+      });
+      setSearchResults(
+        results.map((r) => {
+          r.thumbnail_url = convertFileSrc(r.thumbnail_url);
+          return r;
+        })
+      );
+      setSelectedFiles([]);
+    } catch (error) {
+      console.error("Error search by image:", error);
+    }
+  },
+
   quickReload: async () => {
     get().isQuickReload.current = true;
     await get().handleSearch();